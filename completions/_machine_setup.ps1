@@ -25,20 +25,38 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
             [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
             [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
             [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
             [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
             [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
             [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
             [CompletionResult]::new('install', 'install', [CompletionResultType]::ParameterValue, 'Install all of the defined tasks')
             [CompletionResult]::new('update', 'update', [CompletionResultType]::ParameterValue, 'Update all of the defined tasks')
-            [CompletionResult]::new('uninstall', 'uninstall', [CompletionResultType]::ParameterValue, 'Uninstall all of the defined tasks')
+            [CompletionResult]::new('uninstall', 'uninstall', [CompletionResultType]::ParameterValue, 'Uninstall all of the defined tasks (in reverse order)')
+            [CompletionResult]::new('upgrade', 'upgrade', [CompletionResultType]::ParameterValue, 'Install added, update changed and uninstall removed tasks since the last run')
             [CompletionResult]::new('list', 'list', [CompletionResultType]::ParameterValue, 'List defined tasks')
+            [CompletionResult]::new('test', 'test', [CompletionResultType]::ParameterValue, 'Install the config inside of disposable containers')
+            [CompletionResult]::new('schedule', 'schedule', [CompletionResultType]::ParameterValue, 'Run machine_setup periodically with the scheduler of the OS')
+            [CompletionResult]::new('unschedule', 'unschedule', [CompletionResultType]::ParameterValue, 'Remove the scheduled runs')
+            [CompletionResult]::new('import', 'import', [CompletionResultType]::ParameterValue, 'Create a config from the files of another dotfiles manager')
+            [CompletionResult]::new('discover', 'discover', [CompletionResultType]::ParameterValue, 'Find symlinks in the home directory that point into a repository and print symlink tasks for them')
+            [CompletionResult]::new('bootstrap-script', 'bootstrap-script', [CompletionResultType]::ParameterValue, 'Print a script that installs machine_setup and runs a config repository on a new machine')
             [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
             break
         }
@@ -47,12 +65,23 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
             [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
             [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
             [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
             [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
             [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
@@ -64,12 +93,23 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
             [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
             [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
             [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
             [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
             [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
@@ -81,12 +121,51 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
             [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
             [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
             [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
             [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
             [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;upgrade' {
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
@@ -98,12 +177,243 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
             [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
             [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
             [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;test' {
+            [CompletionResult]::new('--image', 'image', [CompletionResultType]::ParameterName, 'container image, e.g. ubuntu:24.04 (can be given multiple times)')
+            [CompletionResult]::new('--engine', 'engine', [CompletionResultType]::ParameterName, 'docker or podman (detected automatically by default)')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;schedule' {
+            [CompletionResult]::new('--every', 'every', [CompletionResultType]::ParameterName, 'hour, day or week')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;unschedule' {
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;import' {
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('chezmoi', 'chezmoi', [CompletionResultType]::ParameterValue, 'Translate a chezmoi source directory')
+            [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
+            break
+        }
+        'machine_setup;import;chezmoi' {
+            [CompletionResult]::new('--output', 'output', [CompletionResultType]::ParameterName, 'write the config to this file instead of printing it')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;import;help' {
+            [CompletionResult]::new('chezmoi', 'chezmoi', [CompletionResultType]::ParameterValue, 'Translate a chezmoi source directory')
+            [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
+            break
+        }
+        'machine_setup;import;help;chezmoi' {
+            break
+        }
+        'machine_setup;import;help;help' {
+            break
+        }
+        'machine_setup;discover' {
+            [CompletionResult]::new('--home', 'home', [CompletionResultType]::ParameterName, 'directory that is scanned for symlinks')
+            [CompletionResult]::new('--max-depth', 'max-depth', [CompletionResultType]::ParameterName, 'how many directory levels below the home directory are scanned')
+            [CompletionResult]::new('--output', 'output', [CompletionResultType]::ParameterName, 'write the config to this file instead of printing it')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
+            [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version')
+            break
+        }
+        'machine_setup;bootstrap-script' {
+            [CompletionResult]::new('--repo', 'repo', [CompletionResultType]::ParameterName, 'git repository that contains the config')
+            [CompletionResult]::new('--config-file', 'config-file', [CompletionResultType]::ParameterName, 'path of the config file inside of the repository')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'sh or powershell')
+            [CompletionResult]::new('--release', 'release', [CompletionResultType]::ParameterName, 'release that is installed (defaults to the version of this binary)')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('--config', 'config', [CompletionResultType]::ParameterName, 'path to the config file')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--task', 'task', [CompletionResultType]::ParameterName, 'run a single task')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'run all tasks with this tag')
+            [CompletionResult]::new('--host', 'host', [CompletionResultType]::ParameterName, 'Run the tasks on a remote machine over SSH ([user@]host[:port])')
+            [CompletionResult]::new('--group', 'group', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of an inventory group')
+            [CompletionResult]::new('--var', 'var', [CompletionResultType]::ParameterName, 'Set a variable for {{ }} placeholders (can be given multiple times)')
             [CompletionResult]::new('-l', 'l', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('--level', 'level', [CompletionResultType]::ParameterName, 'Set log level')
             [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('--select', 'select', [CompletionResultType]::ParameterName, 'Select a task to run')
             [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Add debug information')
             [CompletionResult]::new('--debug', 'debug', [CompletionResultType]::ParameterName, 'Add debug information')
+            [CompletionResult]::new('--frozen', 'frozen', [CompletionResultType]::ParameterName, 'Pin clones and downloads to the commits and checksums in machine-setup.lock')
+            [CompletionResult]::new('-f', 'f', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--force', 'force', [CompletionResultType]::ParameterName, 'Install tasks even if nothing changed since the last successful run')
+            [CompletionResult]::new('--purge', 'purge', [CompletionResultType]::ParameterName, 'Also remove state, cached downloads and temp scripts when uninstalling')
+            [CompletionResult]::new('--ui', 'ui', [CompletionResultType]::ParameterName, 'Show a full-screen dashboard instead of progress bars')
+            [CompletionResult]::new('--reboot', 'reboot', [CompletionResultType]::ParameterName, 'Reboot at the end of a successful run, if a task requires it')
+            [CompletionResult]::new('--all', 'all', [CompletionResultType]::ParameterName, 'Run the tasks on all hosts of the inventory')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help')
             [CompletionResult]::new('-V', 'V ', [CompletionResultType]::ParameterName, 'Print version')
@@ -113,8 +423,15 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
         'machine_setup;help' {
             [CompletionResult]::new('install', 'install', [CompletionResultType]::ParameterValue, 'Install all of the defined tasks')
             [CompletionResult]::new('update', 'update', [CompletionResultType]::ParameterValue, 'Update all of the defined tasks')
-            [CompletionResult]::new('uninstall', 'uninstall', [CompletionResultType]::ParameterValue, 'Uninstall all of the defined tasks')
+            [CompletionResult]::new('uninstall', 'uninstall', [CompletionResultType]::ParameterValue, 'Uninstall all of the defined tasks (in reverse order)')
+            [CompletionResult]::new('upgrade', 'upgrade', [CompletionResultType]::ParameterValue, 'Install added, update changed and uninstall removed tasks since the last run')
             [CompletionResult]::new('list', 'list', [CompletionResultType]::ParameterValue, 'List defined tasks')
+            [CompletionResult]::new('test', 'test', [CompletionResultType]::ParameterValue, 'Install the config inside of disposable containers')
+            [CompletionResult]::new('schedule', 'schedule', [CompletionResultType]::ParameterValue, 'Run machine_setup periodically with the scheduler of the OS')
+            [CompletionResult]::new('unschedule', 'unschedule', [CompletionResultType]::ParameterValue, 'Remove the scheduled runs')
+            [CompletionResult]::new('import', 'import', [CompletionResultType]::ParameterValue, 'Create a config from the files of another dotfiles manager')
+            [CompletionResult]::new('discover', 'discover', [CompletionResultType]::ParameterValue, 'Find symlinks in the home directory that point into a repository and print symlink tasks for them')
+            [CompletionResult]::new('bootstrap-script', 'bootstrap-script', [CompletionResultType]::ParameterValue, 'Print a script that installs machine_setup and runs a config repository on a new machine')
             [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
             break
         }
@@ -127,9 +444,34 @@ Register-ArgumentCompleter -Native -CommandName 'machine_setup' -ScriptBlock {
         'machine_setup;help;uninstall' {
             break
         }
+        'machine_setup;help;upgrade' {
+            break
+        }
         'machine_setup;help;list' {
             break
         }
+        'machine_setup;help;test' {
+            break
+        }
+        'machine_setup;help;schedule' {
+            break
+        }
+        'machine_setup;help;unschedule' {
+            break
+        }
+        'machine_setup;help;import' {
+            [CompletionResult]::new('chezmoi', 'chezmoi', [CompletionResultType]::ParameterValue, 'Translate a chezmoi source directory')
+            break
+        }
+        'machine_setup;help;import;chezmoi' {
+            break
+        }
+        'machine_setup;help;discover' {
+            break
+        }
+        'machine_setup;help;bootstrap-script' {
+            break
+        }
         'machine_setup;help;help' {
             break
         }
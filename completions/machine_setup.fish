@@ -1,45 +1,261 @@
 complete -c machine_setup -n "__fish_use_subcommand" -s c -l config -d 'path to the config file' -r
 complete -c machine_setup -n "__fish_use_subcommand" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_use_subcommand" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_use_subcommand" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_use_subcommand" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_use_subcommand" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
 complete -c machine_setup -n "__fish_use_subcommand" -s l -l level -d 'Set log level' -r
 complete -c machine_setup -n "__fish_use_subcommand" -s s -l select -d 'Select a task to run'
 complete -c machine_setup -n "__fish_use_subcommand" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_use_subcommand" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_use_subcommand" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_use_subcommand" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_use_subcommand" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_use_subcommand" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_use_subcommand" -l all -d 'Run the tasks on all hosts of the inventory'
 complete -c machine_setup -n "__fish_use_subcommand" -s h -l help -d 'Print help'
 complete -c machine_setup -n "__fish_use_subcommand" -s V -l version -d 'Print version'
 complete -c machine_setup -n "__fish_use_subcommand" -f -a "install" -d 'Install all of the defined tasks'
 complete -c machine_setup -n "__fish_use_subcommand" -f -a "update" -d 'Update all of the defined tasks'
-complete -c machine_setup -n "__fish_use_subcommand" -f -a "uninstall" -d 'Uninstall all of the defined tasks'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "uninstall" -d 'Uninstall all of the defined tasks (in reverse order)'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "upgrade" -d 'Install added, update changed and uninstall removed tasks since the last run'
 complete -c machine_setup -n "__fish_use_subcommand" -f -a "list" -d 'List defined tasks'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "test" -d 'Install the config inside of disposable containers'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "schedule" -d 'Run machine_setup periodically with the scheduler of the OS'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "unschedule" -d 'Remove the scheduled runs'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "import" -d 'Create a config from the files of another dotfiles manager'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "discover" -d 'Find symlinks in the home directory that point into a repository and print symlink tasks for them'
+complete -c machine_setup -n "__fish_use_subcommand" -f -a "bootstrap-script" -d 'Print a script that installs machine_setup and runs a config repository on a new machine'
 complete -c machine_setup -n "__fish_use_subcommand" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s c -l config -d 'path to the config file' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s l -l level -d 'Set log level' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s s -l select -d 'Select a task to run'
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from install" -l all -d 'Run the tasks on all hosts of the inventory'
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s h -l help -d 'Print help'
 complete -c machine_setup -n "__fish_seen_subcommand_from install" -s V -l version -d 'Print version'
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s c -l config -d 'path to the config file' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s l -l level -d 'Set log level' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s s -l select -d 'Select a task to run'
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from update" -l all -d 'Run the tasks on all hosts of the inventory'
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s h -l help -d 'Print help'
 complete -c machine_setup -n "__fish_seen_subcommand_from update" -s V -l version -d 'Print version'
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s c -l config -d 'path to the config file' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s l -l level -d 'Set log level' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s s -l select -d 'Select a task to run'
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -l all -d 'Run the tasks on all hosts of the inventory'
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s h -l help -d 'Print help'
 complete -c machine_setup -n "__fish_seen_subcommand_from uninstall" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from upgrade" -s V -l version -d 'Print version'
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s c -l config -d 'path to the config file' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s l -l level -d 'Set log level' -r
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s s -l select -d 'Select a task to run'
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from list" -l all -d 'Run the tasks on all hosts of the inventory'
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s h -l help -d 'Print help'
 complete -c machine_setup -n "__fish_seen_subcommand_from list" -s V -l version -d 'Print version'
-complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from help" -f -a "install" -d 'Install all of the defined tasks'
-complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from help" -f -a "update" -d 'Update all of the defined tasks'
-complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from help" -f -a "uninstall" -d 'Uninstall all of the defined tasks'
-complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from help" -f -a "list" -d 'List defined tasks'
-complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l image -d 'container image, e.g. ubuntu:24.04 (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l engine -d 'docker or podman (detected automatically by default)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from test" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l every -d 'hour, day or week' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from schedule" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from unschedule" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -f -a "chezmoi" -d 'Translate a chezmoi source directory'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l output -d 'write the config to this file instead of printing it' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from chezmoi" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -f -a "chezmoi" -d 'Translate a chezmoi source directory'
+complete -c machine_setup -n "__fish_seen_subcommand_from import; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from chezmoi; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l home -d 'directory that is scanned for symlinks' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l max-depth -d 'how many directory levels below the home directory are scanned' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l output -d 'write the config to this file instead of printing it' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from discover" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l repo -d 'git repository that contains the config' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l config-file -d 'path of the config file inside of the repository' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l format -d 'sh or powershell' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l release -d 'release that is installed (defaults to the version of this binary)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s c -l config -d 'path to the config file' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s t -l task -d 'run a single task' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l tag -d 'run all tasks with this tag' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l host -d 'Run the tasks on a remote machine over SSH ([user@]host[:port])' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l group -d 'Run the tasks on all hosts of an inventory group' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l var -d 'Set a variable for {{ }} placeholders (can be given multiple times)' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s l -l level -d 'Set log level' -r
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s s -l select -d 'Select a task to run'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s d -l debug -d 'Add debug information'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l frozen -d 'Pin clones and downloads to the commits and checksums in machine-setup.lock'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s f -l force -d 'Install tasks even if nothing changed since the last successful run'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l purge -d 'Also remove state, cached downloads and temp scripts when uninstalling'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l ui -d 'Show a full-screen dashboard instead of progress bars'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l reboot -d 'Reboot at the end of a successful run, if a task requires it'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -l all -d 'Run the tasks on all hosts of the inventory'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s h -l help -d 'Print help'
+complete -c machine_setup -n "__fish_seen_subcommand_from bootstrap-script" -s V -l version -d 'Print version'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "install" -d 'Install all of the defined tasks'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "update" -d 'Update all of the defined tasks'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "uninstall" -d 'Uninstall all of the defined tasks (in reverse order)'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "upgrade" -d 'Install added, update changed and uninstall removed tasks since the last run'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "list" -d 'List defined tasks'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "test" -d 'Install the config inside of disposable containers'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "schedule" -d 'Run machine_setup periodically with the scheduler of the OS'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "unschedule" -d 'Remove the scheduled runs'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "import" -d 'Create a config from the files of another dotfiles manager'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "discover" -d 'Find symlinks in the home directory that point into a repository and print symlink tasks for them'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "bootstrap-script" -d 'Print a script that installs machine_setup and runs a config repository on a new machine'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from install; and not __fish_seen_subcommand_from update; and not __fish_seen_subcommand_from uninstall; and not __fish_seen_subcommand_from upgrade; and not __fish_seen_subcommand_from list; and not __fish_seen_subcommand_from test; and not __fish_seen_subcommand_from schedule; and not __fish_seen_subcommand_from unschedule; and not __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from discover; and not __fish_seen_subcommand_from bootstrap-script; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c machine_setup -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from import; and not __fish_seen_subcommand_from chezmoi" -f -a "chezmoi" -d 'Translate a chezmoi source directory'
@@ -0,0 +1,237 @@
+use core::fmt;
+use std::str::FromStr;
+
+static RELEASE_URL: &str = "https://github.com/timopruesse/machine_setup/releases/download";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptFormat {
+    Sh,
+    PowerShell,
+}
+
+impl fmt::Display for ScriptFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptFormat::Sh => write!(f, "sh"),
+            ScriptFormat::PowerShell => write!(f, "powershell"),
+        }
+    }
+}
+
+impl FromStr for ScriptFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sh" => Ok(ScriptFormat::Sh),
+            "powershell" | "ps1" => Ok(ScriptFormat::PowerShell),
+            _ => Err(format!("Unknown script format: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BootstrapOptions {
+    /** git repository that contains the config */
+    pub repo: String,
+    /** path of the config file inside of the repository */
+    pub config_file: String,
+    pub task: Option<String>,
+    /** release tag that is downloaded, e.g. `v1.3.6` */
+    pub release: String,
+}
+
+pub fn get_current_release() -> String {
+    format!("v{}", env!("CARGO_PKG_VERSION"))
+}
+
+const SH_TEMPLATE: &str = r#"#!/bin/sh
+# Installs machine_setup and runs the config from the repository below.
+# Usage: curl -fsSL <url of this script> | sh
+set -eu
+
+RELEASE={{release}}
+REPO={{repo}}
+CONFIG_FILE={{config_file}}
+TASK={{task}}
+CONFIG_DIR="${MACHINE_SETUP_CONFIG_DIR:-$HOME/.machine_setup}"
+BIN_DIR="${MACHINE_SETUP_BIN_DIR:-$HOME/.local/bin}"
+
+fail() {
+  echo "$1" >&2
+  exit 1
+}
+
+case "$(uname -s)" in
+  Linux) target=x86_64-unknown-linux-gnu ;;
+  Darwin) target=x86_64-apple-darwin ;;
+  *) fail "There is no release for $(uname -s), install machine_setup with: cargo install machine_setup" ;;
+esac
+
+case "$(uname -m)" in
+  x86_64 | amd64) ;;
+  # runs via Rosetta
+  arm64) [ "$target" = x86_64-apple-darwin ] || fail "There is no release for $(uname -m), install machine_setup with: cargo install machine_setup" ;;
+  *) fail "There is no release for $(uname -m), install machine_setup with: cargo install machine_setup" ;;
+esac
+
+command -v git >/dev/null 2>&1 || fail "git is required to fetch $REPO"
+
+tmp_dir=$(mktemp -d)
+trap 'rm -rf "$tmp_dir"' EXIT
+
+archive="machine_setup-$target-$RELEASE.zip"
+echo "Downloading $archive ..."
+if command -v curl >/dev/null 2>&1; then
+  curl -fsSL "{{release_url}}/$RELEASE/$archive" -o "$tmp_dir/$archive"
+else
+  wget -q "{{release_url}}/$RELEASE/$archive" -O "$tmp_dir/$archive"
+fi
+
+if command -v unzip >/dev/null 2>&1; then
+  unzip -q "$tmp_dir/$archive" -d "$tmp_dir"
+else
+  python3 -m zipfile -e "$tmp_dir/$archive" "$tmp_dir"
+fi
+
+mkdir -p "$BIN_DIR"
+cp "$tmp_dir/machine_setup" "$BIN_DIR/machine_setup"
+chmod 755 "$BIN_DIR/machine_setup"
+
+if [ -d "$CONFIG_DIR/.git" ]; then
+  git -C "$CONFIG_DIR" pull --ff-only
+else
+  git clone "$REPO" "$CONFIG_DIR"
+fi
+
+if [ -n "$TASK" ]; then
+  "$BIN_DIR/machine_setup" install --config "$CONFIG_DIR/$CONFIG_FILE" --task "$TASK"
+else
+  "$BIN_DIR/machine_setup" install --config "$CONFIG_DIR/$CONFIG_FILE"
+fi
+"#;
+
+const POWERSHELL_TEMPLATE: &str = r#"# Installs machine_setup and runs the config from the repository below.
+# Usage: irm <url of this script> | iex
+$ErrorActionPreference = "Stop"
+
+$Release = {{release}}
+$Repo = {{repo}}
+$ConfigFile = {{config_file}}
+$Task = {{task}}
+$ConfigDir = if ($env:MACHINE_SETUP_CONFIG_DIR) { $env:MACHINE_SETUP_CONFIG_DIR } else { Join-Path $HOME ".machine_setup" }
+$BinDir = if ($env:MACHINE_SETUP_BIN_DIR) { $env:MACHINE_SETUP_BIN_DIR } else { Join-Path $env:LOCALAPPDATA "machine_setup" }
+
+if (-not (Get-Command git -ErrorAction SilentlyContinue)) {
+    throw "git is required to fetch $Repo"
+}
+
+$Archive = "machine_setup-x86_64-pc-windows-msvc-$Release.zip"
+$TempDir = Join-Path ([System.IO.Path]::GetTempPath()) ([System.Guid]::NewGuid())
+New-Item -ItemType Directory -Path $TempDir | Out-Null
+
+try {
+    Write-Host "Downloading $Archive ..."
+    Invoke-WebRequest -UseBasicParsing -Uri "{{release_url}}/$Release/$Archive" -OutFile (Join-Path $TempDir $Archive)
+    Expand-Archive -Path (Join-Path $TempDir $Archive) -DestinationPath $TempDir -Force
+
+    New-Item -ItemType Directory -Force -Path $BinDir | Out-Null
+    Copy-Item (Join-Path $TempDir "machine_setup.exe") $BinDir -Force
+} finally {
+    Remove-Item -Recurse -Force $TempDir
+}
+
+if (Test-Path (Join-Path $ConfigDir ".git")) {
+    git -C $ConfigDir pull --ff-only
+} else {
+    git clone $Repo $ConfigDir
+}
+if ($LASTEXITCODE -ne 0) {
+    throw "Failed to fetch $Repo"
+}
+
+$Arguments = @("install", "--config", (Join-Path $ConfigDir $ConfigFile))
+if ($Task) {
+    $Arguments += @("--task", $Task)
+}
+& (Join-Path $BinDir "machine_setup.exe") @Arguments
+exit $LASTEXITCODE
+"#;
+
+/**
+ * Values are always quoted, so they can't break out of the generated script.
+ */
+fn quote(value: &str, format: ScriptFormat) -> String {
+    match format {
+        ScriptFormat::Sh => format!("'{}'", value.replace('\'', r"'\''")),
+        ScriptFormat::PowerShell => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+/**
+ * Creates a script that can be piped into a shell on a brand new machine.
+ * It downloads the machine_setup release for the platform, clones the config repository and runs the installation.
+ */
+pub fn get_bootstrap_script(options: &BootstrapOptions, format: ScriptFormat) -> String {
+    let template = match format {
+        ScriptFormat::Sh => SH_TEMPLATE,
+        ScriptFormat::PowerShell => POWERSHELL_TEMPLATE,
+    };
+
+    template
+        .replace("{{release_url}}", RELEASE_URL)
+        .replace("{{release}}", &quote(&options.release, format))
+        .replace("{{repo}}", &quote(&options.repo, format))
+        .replace("{{config_file}}", &quote(&options.config_file, format))
+        .replace(
+            "{{task}}",
+            &quote(options.task.as_deref().unwrap_or_default(), format),
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_options() -> BootstrapOptions {
+        BootstrapOptions {
+            repo: String::from("https://github.com/timopruesse/.dotfiles.git"),
+            config_file: String::from("machine_setup.yaml"),
+            task: None,
+            release: String::from("v1.3.6"),
+        }
+    }
+
+    #[test]
+    fn it_creates_a_posix_script() {
+        let script = get_bootstrap_script(&get_options(), ScriptFormat::Sh);
+
+        assert!(script.starts_with("#!/bin/sh"));
+        assert!(script.contains("REPO='https://github.com/timopruesse/.dotfiles.git'"));
+        assert!(script.contains("RELEASE='v1.3.6'"));
+        assert!(script.contains(&format!("{RELEASE_URL}/$RELEASE/$archive")));
+        assert!(!script.contains("{{"));
+    }
+
+    #[test]
+    fn it_creates_a_powershell_script() {
+        let script = get_bootstrap_script(&get_options(), ScriptFormat::PowerShell);
+
+        assert!(script.contains("$Repo = 'https://github.com/timopruesse/.dotfiles.git'"));
+        assert!(script.contains("machine_setup-x86_64-pc-windows-msvc-$Release.zip"));
+        assert!(!script.contains("{{"));
+    }
+
+    #[test]
+    fn it_quotes_values() {
+        let options = BootstrapOptions {
+            task: Some(String::from("it's")),
+            ..get_options()
+        };
+
+        assert!(get_bootstrap_script(&options, ScriptFormat::Sh).contains(r"TASK='it'\''s'"));
+        assert!(
+            get_bootstrap_script(&options, ScriptFormat::PowerShell).contains("$Task = 'it''s'")
+        );
+    }
+}
@@ -1,50 +1,122 @@
-use ergo_fs::PathDir;
-use indicatif::ProgressBar;
+use ergo_fs::{Path, PathDir};
+use std::sync::Arc;
 
 use crate::{
     commands::{
-        clone::CloneCommand, copy::CopyDirCommand, machine_setup::MachineSetupCommand,
-        run::RunCommand, symlink::SymlinkCommand,
+        assert::AssertCommand,
+        clone::CloneCommand,
+        copy::CopyDirCommand,
+        download::DownloadCommand,
+        machine_setup::MachineSetupCommand,
+        plugin::{find_plugin, PluginCommand},
+        run::RunCommand,
+        script::ScriptCommand,
+        symlink::SymlinkCommand,
+        wait_for::WaitForCommand,
     },
     config::config_value::ConfigValue,
-    utils::shell::Shell,
+    error::{Error, Result},
+    events::Progress,
+    utils::{
+        connections::ConnectionPool, lockfile::Lockfile, network::NetworkConfig,
+        restart::RestartRequirements, shell::Shell, variables::Variables,
+    },
 };
 
+#[cfg(feature = "wasm")]
+use crate::commands::wasm_plugin::{find_wasm_plugin, WasmPluginCommand};
+
 #[derive(Debug, Clone)]
 pub struct CommandConfig {
     pub config_dir: PathDir,
     pub temp_dir: String,
+    pub state_dir: String,
+    pub cache_dir: String,
     pub default_shell: Shell,
+    pub network: NetworkConfig,
+    pub lockfile: Arc<Lockfile>,
+    pub connections: Arc<ConnectionPool>,
+    pub variables: Arc<Variables>,
+    pub restarts: Arc<RestartRequirements>,
+}
+
+#[cfg(test)]
+impl CommandConfig {
+    /**
+     * Keeps everything a test writes inside of its own directory.
+     */
+    pub fn for_dir(dir: &Path) -> Self {
+        let dir_str = dir.to_string_lossy().to_string();
+
+        CommandConfig {
+            config_dir: PathDir::new(dir).unwrap(),
+            temp_dir: dir_str.to_string(),
+            state_dir: dir_str.to_string(),
+            cache_dir: dir_str,
+            default_shell: Shell::Bash,
+            network: NetworkConfig::default(),
+            lockfile: Arc::new(Lockfile::default()),
+            connections: Arc::new(ConnectionPool::default()),
+            variables: Arc::new(Variables::default()),
+            restarts: Arc::new(RestartRequirements::default()),
+        }
+    }
 }
 
 pub trait CommandInterface {
-    fn install(
-        &self,
-        args: ConfigValue,
-        config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String>;
+    fn install(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress)
+        -> Result<()>;
     fn uninstall(
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String>;
-    fn update(
-        &self,
-        args: ConfigValue,
-        config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String>;
+        progress: &Progress,
+    ) -> Result<()>;
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()>;
 }
 
-pub fn get_command(name: &str) -> Result<Box<dyn CommandInterface>, String> {
+pub static COMMAND_NAMES: [&str; 9] = [
+    "copy",
+    "symlink",
+    "clone",
+    "download",
+    "run",
+    "script",
+    "machine_setup",
+    "assert",
+    "wait_for",
+];
+
+/**
+ * Unknown commands are resolved to plugins, see `commands::plugin` and `commands::wasm_plugin`.
+ */
+pub fn get_command(name: &str, config_dir: &Path) -> Result<Box<dyn CommandInterface>> {
     match name {
         "copy" => Ok(Box::new(CopyDirCommand {})),
         "symlink" => Ok(Box::new(SymlinkCommand {})),
         "clone" => Ok(Box::new(CloneCommand {})),
+        "download" => Ok(Box::new(DownloadCommand {})),
         "run" => Ok(Box::new(RunCommand {})),
+        "script" => Ok(Box::new(ScriptCommand {})),
         "machine_setup" => Ok(Box::new(MachineSetupCommand {})),
-        _ => Err(format!("Unknown command: {name}")),
+        "assert" => Ok(Box::new(AssertCommand {})),
+        "wait_for" => Ok(Box::new(WaitForCommand {})),
+        _ => {
+            #[cfg(feature = "wasm")]
+            if let Some(module) = find_wasm_plugin(name, config_dir) {
+                return Ok(Box::new(WasmPluginCommand {
+                    name: name.to_string(),
+                    module,
+                }));
+            }
+
+            match find_plugin(name, config_dir) {
+                Some(executable) => Ok(Box::new(PluginCommand {
+                    name: name.to_string(),
+                    executable,
+                })),
+                None => Err(Error::Command(format!("Unknown command: {name}"))),
+            }
+        }
     }
 }
@@ -0,0 +1,337 @@
+use ansi_term::Color::{Green, White};
+use ergo_fs::PathBuf;
+use std::{env, fs::canonicalize, process::Command};
+use tracing::debug;
+
+use crate::{
+    command::{CommandConfig, CommandInterface},
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+    events::Progress,
+    utils::directory::{expand_path, get_relative_dir},
+};
+
+pub struct AssertCommand {}
+
+static ASSERTION_KINDS: [&str; 4] = ["files", "links", "binaries", "urls"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Assertion {
+    File(String),
+    Link(String),
+    Binary {
+        name: String,
+        version: Option<String>,
+    },
+    Url(String),
+}
+
+fn get_list(args: &ConfigValue, kind: &str) -> Vec<ConfigValue> {
+    match args.as_hash().and_then(|values| values.get(kind)) {
+        Some(ConfigValue::Array(values)) => values.clone(),
+        Some(value) => vec![value.clone()],
+        None => vec![],
+    }
+}
+
+fn get_string(value: &ConfigValue, kind: &str) -> Result<String> {
+    value
+        .as_str()
+        .map(|value| value.to_string())
+        .ok_or_else(|| Error::Validation(format!("{kind}: {value:?} is not a string")))
+}
+
+fn get_binary(value: &ConfigValue) -> Result<Assertion> {
+    let Some(values) = value.as_hash() else {
+        return Ok(Assertion::Binary {
+            name: get_string(value, "binaries")?,
+            version: None,
+        });
+    };
+
+    let name = values
+        .get("name")
+        .ok_or_else(|| Error::Validation(String::from("binaries: \"name\" is missing")))?;
+
+    // versions have to be quoted, otherwise YAML turns "0.10" into a number
+    let version = match values.get("version") {
+        Some(version) => Some(get_string(version, "binaries.version")?),
+        None => None,
+    };
+
+    Ok(Assertion::Binary {
+        name: get_string(name, "binaries.name")?,
+        version,
+    })
+}
+
+fn get_assertions(args: &ConfigValue) -> Result<Vec<Assertion>> {
+    let Some(values) = args.as_hash() else {
+        return Err(Error::Validation(String::from(
+            "Expected named arguments, got positional arguments",
+        )));
+    };
+
+    if let Some(unknown) = values
+        .keys()
+        .find(|key| !ASSERTION_KINDS.contains(&key.as_str()))
+    {
+        return Err(Error::Validation(format!(
+            "Unknown assertion \"{unknown}\", expected one of: {}",
+            ASSERTION_KINDS.join(", ")
+        )));
+    }
+
+    let mut assertions = vec![];
+    for value in get_list(args, "files") {
+        assertions.push(Assertion::File(get_string(&value, "files")?));
+    }
+    for value in get_list(args, "links") {
+        assertions.push(Assertion::Link(get_string(&value, "links")?));
+    }
+    for value in get_list(args, "binaries") {
+        assertions.push(get_binary(&value)?);
+    }
+    for value in get_list(args, "urls") {
+        assertions.push(Assertion::Url(get_string(&value, "urls")?));
+    }
+
+    Ok(assertions)
+}
+
+fn find_binary(name: &str) -> Option<PathBuf> {
+    let names = if cfg!(target_family = "windows") {
+        vec![format!("{name}.exe"), name.to_string()]
+    } else {
+        vec![name.to_string()]
+    };
+
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/**
+ * Returns a description of the failure, if the machine isn't in the expected state.
+ */
+fn check(assertion: &Assertion, config: &CommandConfig) -> Result<Option<String>> {
+    let failure = match assertion {
+        Assertion::File(file) => {
+            let path = expand_path(&get_relative_dir(&config.config_dir, file), false)?;
+            (!path.exists()).then(|| format!("{file} does not exist"))
+        }
+        Assertion::Link(link) => {
+            let path = expand_path(&get_relative_dir(&config.config_dir, link), false)?;
+
+            if path.symlink_metadata().is_err() {
+                Some(format!("{link} does not exist"))
+            } else if !path.is_symlink() {
+                Some(format!("{link} is not a symlink"))
+            } else {
+                canonicalize(&path)
+                    .err()
+                    .map(|_| format!("{link} does not resolve"))
+            }
+        }
+        Assertion::Binary { name, version } => match (find_binary(name), version) {
+            (None, _) => Some(format!("{name} is not on the PATH")),
+            (Some(_), None) => None,
+            (Some(binary), Some(version)) => {
+                let output = Command::new(&binary)
+                    .arg("--version")
+                    .output()
+                    .map_err(|err| Error::Spawn {
+                        program: binary.to_string_lossy().to_string(),
+                        source: err,
+                    })?;
+                let reported = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+                (!reported.contains(version.as_str()))
+                    .then(|| format!("{name} --version reports \"{reported}\", expected {version}"))
+            }
+        },
+        Assertion::Url(url) => {
+            let url = config.network.rewrite_url(url);
+            let _permit = config.connections.acquire(&url);
+
+            match config.connections.get_agent(&url)?.get(&url).call() {
+                Ok(response) if response.status() == 200 => None,
+                Ok(response) => Some(format!("{url} returned {}", response.status())),
+                Err(ureq::Error::Status(status, _)) => Some(format!("{url} returned {status}")),
+                Err(err) => Some(format!("{url} is not reachable: {err}")),
+            }
+        }
+    };
+
+    Ok(failure)
+}
+
+/**
+ * All assertions are checked, so a single run reports everything that is missing.
+ */
+fn verify(args: &ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+    let assertions = get_assertions(args)?;
+
+    let mut failures = vec![];
+    for assertion in &assertions {
+        debug!("Checking {assertion:?} ...");
+
+        if let Some(failure) = check(assertion, config)? {
+            failures.push(failure);
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(Error::Command(format!(
+            "{} of {} assertions failed:\n{}",
+            failures.len(),
+            assertions.len(),
+            failures.join("\n")
+        )));
+    }
+
+    progress.set_message(format!(
+        "{} {} assertions",
+        Green.paint("Verified"),
+        White.bold().paint(assertions.len().to_string())
+    ));
+
+    Ok(())
+}
+
+impl CommandInterface for AssertCommand {
+    fn install(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        verify(&args, config, progress)
+    }
+
+    fn uninstall(
+        &self,
+        args: ConfigValue,
+        _config: &CommandConfig,
+        _progress: &Progress,
+    ) -> Result<()> {
+        // the assertions describe the installed state, there is nothing to check afterwards
+        get_assertions(&args)?;
+
+        Ok(())
+    }
+
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        verify(&args, config, progress)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, fs::write};
+
+    use super::*;
+
+    #[test]
+    fn it_gets_assertions() {
+        let args = ConfigValue::Hash(HashMap::from([
+            (
+                String::from("files"),
+                ConfigValue::String(String::from("~/.zshrc")),
+            ),
+            (
+                String::from("binaries"),
+                ConfigValue::Array(vec![
+                    ConfigValue::String(String::from("git")),
+                    ConfigValue::Hash(HashMap::from([
+                        (
+                            String::from("name"),
+                            ConfigValue::String(String::from("nvim")),
+                        ),
+                        (
+                            String::from("version"),
+                            ConfigValue::String(String::from("0.10")),
+                        ),
+                    ])),
+                ]),
+            ),
+        ]));
+
+        assert_eq!(
+            get_assertions(&args).unwrap(),
+            vec![
+                Assertion::File(String::from("~/.zshrc")),
+                Assertion::Binary {
+                    name: String::from("git"),
+                    version: None
+                },
+                Assertion::Binary {
+                    name: String::from("nvim"),
+                    version: Some(String::from("0.10"))
+                },
+            ]
+        );
+
+        let unknown = ConfigValue::Hash(HashMap::from([(
+            String::from("file"),
+            ConfigValue::String(String::from("~/.zshrc")),
+        )]));
+        get_assertions(&unknown).unwrap_err();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn it_reports_every_failed_assertion() {
+        use std::os::unix::fs::symlink;
+
+        let config_dir = tempfile::tempdir().unwrap();
+        write(config_dir.path().join("zshrc"), "").unwrap();
+        symlink(
+            config_dir.path().join("zshrc"),
+            config_dir.path().join("valid"),
+        )
+        .unwrap();
+        symlink("/does/not/exist", config_dir.path().join("broken")).unwrap();
+        let config = CommandConfig::for_dir(config_dir.path());
+
+        let assertions = [
+            (Assertion::File(String::from("zshrc")), None),
+            (
+                Assertion::File(String::from("missing")),
+                Some(String::from("missing does not exist")),
+            ),
+            (Assertion::Link(String::from("valid")), None),
+            (
+                Assertion::Link(String::from("zshrc")),
+                Some(String::from("zshrc is not a symlink")),
+            ),
+            (
+                Assertion::Link(String::from("broken")),
+                Some(String::from("broken does not resolve")),
+            ),
+            (
+                Assertion::Binary {
+                    name: String::from("sh"),
+                    version: None,
+                },
+                None,
+            ),
+            (
+                Assertion::Binary {
+                    name: String::from("machine_setup_missing_binary"),
+                    version: None,
+                },
+                Some(String::from(
+                    "machine_setup_missing_binary is not on the PATH",
+                )),
+            ),
+        ];
+
+        for (assertion, failure) in assertions {
+            assert_eq!(check(&assertion, &config).unwrap(), failure);
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use ansi_term::Color::{White, Yellow};
-use indicatif::ProgressBar;
 use std::collections::HashMap;
 use tracing::{debug, info};
 
@@ -13,14 +12,18 @@ use crate::{
         validation_rules::required::Required,
         validator::{validate_named_args, ValidationRule},
     },
+    error::{Error, Result},
+    events::Progress,
     utils::directory::{expand_path, get_relative_dir},
 };
 
 pub struct CloneCommand {}
 
-fn get_installed_repo_url(target_dir: &Path) -> Result<PathBuf, String> {
+static LOCK_KIND: &str = "clone";
+
+fn get_installed_repo_url(target_dir: &Path) -> Result<PathBuf> {
     let output = git(&["config", "--get", "remote.origin.url"], target_dir)
-        .map_err(|e| e.to_string())?
+        .map_err(|e| Error::Command(e.to_string()))?
         .stdout;
 
     let url = String::from_utf8_lossy(&output).trim().to_string();
@@ -37,13 +40,58 @@ fn is_repo_installed(url: &str, target_dir: &Path) -> bool {
     }
 }
 
+fn get_url_arg(args: &ConfigValue) -> Option<String> {
+    args.as_hash()
+        .and_then(|values| values.get("url"))
+        .and_then(|url| url.as_str())
+        .map(|url| url.to_string())
+}
+
+fn get_head_commit(target: &Path) -> Result<String> {
+    let output = git(&["rev-parse", "HEAD"], target)
+        .map_err(|e| Error::Command(e.to_string()))?
+        .stdout;
+
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/**
+ * Checks out the locked commit in frozen mode, otherwise the current commit is recorded.
+ */
+fn sync_lockfile(url: &str, target: &Path, config: &CommandConfig) -> Result<()> {
+    match config.lockfile.get_frozen_version(LOCK_KIND, url)? {
+        Some(commit) => {
+            debug!("Checking out locked commit {commit} of {url}");
+            git(&["checkout", "--detach", &commit], target)
+                .map_err(|e| Error::Command(e.to_string()))?;
+        }
+        None => config
+            .lockfile
+            .lock(LOCK_KIND, url, &get_head_commit(target)?),
+    }
+
+    Ok(())
+}
+
+fn set_outputs(target: &Path, config: &CommandConfig, progress: &Progress) -> Result<()> {
+    let task = progress.get_task();
+    config
+        .variables
+        .set_output(task, "path", &target.to_string_lossy());
+    config
+        .variables
+        .set_output(task, "commit", &get_head_commit(target)?);
+
+    Ok(())
+}
+
 impl CommandInterface for CloneCommand {
     fn install(
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+        progress: &Progress,
+    ) -> Result<()> {
         let url_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
         let target_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
 
@@ -74,7 +122,10 @@ impl CommandInterface for CloneCommand {
         let relative_target_dir = get_relative_dir(&config.config_dir, target);
         let expanded_target_dir = expand_path(relative_target_dir.as_str(), true)?;
 
-        if is_repo_installed(url, &expanded_target_dir) {
+        let locked_url = url;
+        let url = config.network.rewrite_url(url);
+
+        if is_repo_installed(&url, &expanded_target_dir) {
             info!(
                 "{} {}",
                 Yellow.paint("The repository was already cloned."),
@@ -83,15 +134,26 @@ impl CommandInterface for CloneCommand {
             return self.update(args, config, progress);
         }
 
-        clone_repository(url, &expanded_target_dir, progress)
+        {
+            let _permit = config.connections.acquire(&url);
+            clone_repository(
+                &url,
+                &expanded_target_dir,
+                &config.network.get_git_args(&url)?,
+                progress,
+            )?;
+        }
+
+        sync_lockfile(locked_url, &expanded_target_dir, config)?;
+        set_outputs(&expanded_target_dir, config, progress)
     }
 
     fn uninstall(
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+        progress: &Progress,
+    ) -> Result<()> {
         let rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
 
         validate_named_args(
@@ -110,15 +172,16 @@ impl CommandInterface for CloneCommand {
         let relative_target_dir = get_relative_dir(&config.config_dir, target);
         let expanded_target_dir = expand_path(relative_target_dir.as_str(), false)?;
 
-        remove_repository(&expanded_target_dir, progress)
+        remove_repository(&expanded_target_dir, progress)?;
+
+        if let Some(url) = get_url_arg(&args) {
+            config.lockfile.unlock(LOCK_KIND, &url);
+        }
+
+        Ok(())
     }
 
-    fn update(
-        &self,
-        args: ConfigValue,
-        config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
         let rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
 
         validate_named_args(
@@ -137,11 +200,54 @@ impl CommandInterface for CloneCommand {
         let relative_target_dir = get_relative_dir(&config.config_dir, target);
         let expanded_target_dir = expand_path(relative_target_dir.as_str(), true)?;
 
-        update_repository(&expanded_target_dir, progress)
+        let url = get_url_arg(&args);
+
+        // the proxy and the connection limit depend on the host the repository is pulled from
+        let remote_url = match get_installed_repo_url(&expanded_target_dir) {
+            Ok(installed_url) if !installed_url.as_os_str().is_empty() => {
+                installed_url.to_string_lossy().to_string()
+            }
+            _ => url.clone().ok_or_else(|| {
+                Error::Command(format!(
+                    "{} has no origin url to update from",
+                    expanded_target_dir.display()
+                ))
+            })?,
+        };
+        let git_args = config.network.get_git_args(&remote_url)?;
+        let permit = config.connections.acquire(&remote_url);
+
+        // the locked commit is checked out instead of pulling the latest changes
+        if config.lockfile.is_frozen() && url.is_some() {
+            git(&with_git_args(&git_args, &["fetch"]), &expanded_target_dir)
+                .map_err(|e| Error::Command(e.to_string()))?;
+        } else {
+            update_repository(&expanded_target_dir, &git_args, progress)?;
+        }
+        drop(permit);
+
+        if let Some(url) = url {
+            sync_lockfile(&url, &expanded_target_dir, config)?;
+        }
+
+        set_outputs(&expanded_target_dir, config, progress)
     }
 }
 
-pub fn clone_repository(url: &str, target: &Path, progress: &ProgressBar) -> Result<(), String> {
+fn with_git_args<'a>(git_args: &'a [String], args: &[&'a str]) -> Vec<&'a str> {
+    git_args
+        .iter()
+        .map(String::as_str)
+        .chain(args.iter().copied())
+        .collect()
+}
+
+pub fn clone_repository(
+    url: &str,
+    target: &Path,
+    git_args: &[String],
+    progress: &Progress,
+) -> Result<()> {
     let message = format!(
         "Cloning {} into {} ...",
         White.bold().paint(url),
@@ -151,15 +257,15 @@ pub fn clone_repository(url: &str, target: &Path, progress: &ProgressBar) -> Res
     debug!(message);
     progress.set_message(message);
 
-    let clone_result = git(&["clone", url, "."], target);
+    let clone_result = git(&with_git_args(git_args, &["clone", url, "."]), target);
     if let Err(err_clone) = clone_result {
-        return Err(err_clone.to_string());
+        return Err(Error::Command(err_clone.to_string()));
     }
 
     Ok(())
 }
 
-pub fn remove_repository(target: &PathBuf, progress: &ProgressBar) -> Result<(), String> {
+pub fn remove_repository(target: &PathBuf, progress: &Progress) -> Result<()> {
     let message = format!(
         "Removing {} ...",
         White.bold().paint(target.display().to_string())
@@ -168,11 +274,35 @@ pub fn remove_repository(target: &PathBuf, progress: &ProgressBar) -> Result<(),
     debug!(message);
     progress.set_message(message);
 
-    std::fs::remove_dir_all(target).map_err(|err| err.to_string())?;
+    std::fs::remove_dir_all(target).map_err(|err| Error::io(target, err))?;
     Ok(())
 }
 
-pub fn update_repository(target: &Path, progress: &ProgressBar) -> Result<(), String> {
+/**
+ * A frozen run checks out the locked commit without a branch, which `git pull` can't update,
+ * so the default branch of the remote is checked out again first.
+ */
+fn checkout_default_branch(target: &Path) -> Result<()> {
+    if git(&["symbolic-ref", "-q", "HEAD"], target).is_ok() {
+        return Ok(());
+    }
+
+    let output = git(
+        &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"],
+        target,
+    )
+    .map_err(|e| Error::Command(e.to_string()))?
+    .stdout;
+    let remote_head = String::from_utf8_lossy(&output).trim().to_string();
+    let branch = remote_head.strip_prefix("origin/").unwrap_or(&remote_head);
+
+    debug!("Checking out branch {branch} of the detached repository");
+    git(&["checkout", branch], target).map_err(|e| Error::Command(e.to_string()))?;
+
+    Ok(())
+}
+
+pub fn update_repository(target: &Path, git_args: &[String], progress: &Progress) -> Result<()> {
     let message = format!(
         "Updating {} ...",
         White.bold().paint(target.display().to_string())
@@ -181,9 +311,11 @@ pub fn update_repository(target: &Path, progress: &ProgressBar) -> Result<(), St
     debug!(message);
     progress.set_message(message);
 
-    let update_result = git(&["pull"], target);
+    checkout_default_branch(target)?;
+
+    let update_result = git(&with_git_args(git_args, &["pull"]), target);
     if let Err(err_update) = update_result {
-        return Err(err_update.to_string());
+        return Err(Error::Command(err_update.to_string()));
     }
     Ok(())
 }
@@ -197,10 +329,64 @@ mod test {
         let target = tempfile::tempdir().unwrap();
         let target_path = target.path().to_str().unwrap();
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
         let result = remove_repository(&PathBuf::from(target_path), &pb);
         result.unwrap();
         assert!(!target.path().exists());
     }
+
+    #[test]
+    fn it_updates_repositories_with_a_detached_head() {
+        let origin = tempfile::tempdir().unwrap();
+        let git_in = |args: &[&str], dir: &std::path::Path| {
+            git(args, &PathBuf::from(dir)).unwrap();
+        };
+        git_in(&["init", "-q", "-b", "main"], origin.path());
+        git_in(
+            &[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+            origin.path(),
+        );
+
+        let clone = tempfile::tempdir().unwrap();
+        let target = PathBuf::from(clone.path());
+        let pb = Progress::default();
+        clone_repository(origin.path().to_str().unwrap(), &target, &[], &pb).unwrap();
+        git_in(&["checkout", "-q", "--detach", "HEAD"], clone.path());
+
+        update_repository(&target, &[], &pb).unwrap();
+
+        let branch = git(&["symbolic-ref", "--short", "HEAD"], &target).unwrap();
+        assert_eq!(String::from_utf8_lossy(&branch.stdout).trim(), "main");
+    }
+
+    #[test]
+    fn it_fails_to_update_repositories_without_origin() {
+        let target = tempfile::tempdir().unwrap();
+        git(&["init", "-q"], &PathBuf::from(target.path())).unwrap();
+
+        let args = ConfigValue::Hash(HashMap::from([(
+            String::from("target"),
+            ConfigValue::String(target.path().to_str().unwrap().to_string()),
+        )]));
+        let err = CloneCommand {}
+            .update(
+                args,
+                &CommandConfig::for_dir(target.path()),
+                &Progress::default(),
+            )
+            .unwrap_err();
+
+        assert!(err.to_string().contains("no origin url"), "{err}");
+    }
 }
@@ -1,9 +1,11 @@
-use ansi_term::Color::{Red, White, Yellow};
-use ergo_fs::{Path, PathBuf};
-use indicatif::ProgressBar;
+use ansi_term::Color::{Green, Red, White, Yellow};
+use core::fmt;
+use ergo_fs::{Path, PathBuf, PathDir};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fs::{self, canonicalize},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
     time::SystemTime,
 };
 use tracing::{debug, info, warn};
@@ -12,34 +14,153 @@ use crate::{
     command::{CommandConfig, CommandInterface},
     config::{
         config_value::ConfigValue,
-        validation_rules::required::Required,
+        validation_rules::{is_bool::IsBool, is_string::IsString, required::Required},
         validator::{validate_named_args, ValidationRule},
     },
-    utils::directory::{
-        expand_path, get_relative_dir, get_source_and_target, walk_files, DIR_TARGET,
+    error::{Error, Result},
+    events::Progress,
+    utils::{
+        directory::{
+            expand_file_target, expand_path, get_bool_arg, get_file_mappings, get_relative_dir,
+            get_source_and_target, get_walk_options, join_errors, walk_files, FileMapping,
+            WalkOptions, DIR_TARGET,
+        },
+        hard_link::create_hard_link,
+        hash::files_are_equal,
+        metadata::{parse_mode, preserve_metadata, set_mode},
     },
 };
 
 pub struct CopyDirCommand {}
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overwrite {
+    Always,
+    Never,
+    IfNewer,
+    IfDifferent,
+}
+
+impl fmt::Display for Overwrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Overwrite::Always => write!(f, "always"),
+            Overwrite::Never => write!(f, "never"),
+            Overwrite::IfNewer => write!(f, "if_newer"),
+            Overwrite::IfDifferent => write!(f, "if_different"),
+        }
+    }
+}
+
+impl FromStr for Overwrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Overwrite::Always),
+            "never" => Ok(Overwrite::Never),
+            "if_newer" => Ok(Overwrite::IfNewer),
+            "if_different" => Ok(Overwrite::IfDifferent),
+            _ => Err(format!(
+                "overwrite: {s} is invalid (always, never, if_newer, if_different)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CopyOptions {
+    pub hard: bool,
+    pub preserve: bool,
+    pub mode: Option<u32>,
+    pub overwrite: Overwrite,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            hard: false,
+            preserve: true,
+            mode: None,
+            overwrite: Overwrite::IfNewer,
+        }
+    }
+}
+
+fn get_options(args: &ConfigValue, default_overwrite: Overwrite) -> Result<CopyOptions> {
+    let hard_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let preserve_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let overwrite_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsString {})];
+
+    validate_named_args(
+        args.to_owned(),
+        HashMap::from([
+            (String::from("hard"), hard_rules),
+            (String::from("preserve"), preserve_rules),
+            (String::from("overwrite"), overwrite_rules),
+        ]),
+    )?;
+
+    let arg_values = args.as_hash().unwrap();
+
+    let preserve = arg_values
+        .get("preserve")
+        .and_then(|preserve| preserve.as_bool())
+        .unwrap_or(true);
+
+    let mode = match arg_values.get("mode") {
+        Some(mode) => Some(parse_mode(mode)?),
+        None => None,
+    };
+
+    let overwrite = match arg_values.get("overwrite").and_then(|o| o.as_str()) {
+        Some(overwrite) => Overwrite::from_str(overwrite).map_err(Error::Validation)?,
+        None => default_overwrite,
+    };
+
+    Ok(CopyOptions {
+        hard: get_bool_arg(args, "hard"),
+        preserve,
+        mode,
+        overwrite,
+    })
+}
+
 impl CommandInterface for CopyDirCommand {
     fn install(
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
-        let dirs = get_source_and_target(args, &config.config_dir)?;
+        progress: &Progress,
+    ) -> Result<()> {
+        let options = get_options(&args, Overwrite::IfNewer)?;
+
+        if let Some(mappings) = get_file_mappings(&args, &config.config_dir)? {
+            let walk_options = get_walk_options(&args)?;
+            return copy_file_mappings(&mappings, &walk_options, options, progress);
+        }
+
+        let dirs = get_source_and_target(args.clone(), &config.config_dir)?;
 
-        copy_dir(&dirs.src, &dirs.target, dirs.ignore, progress)
+        copy_dir(
+            &dirs.src,
+            &dirs.target,
+            &dirs.walk_options,
+            options,
+            progress,
+        )
     }
 
     fn uninstall(
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        _progress: &ProgressBar,
-    ) -> Result<(), String> {
+        _progress: &Progress,
+    ) -> Result<()> {
+        if let Some(mappings) = get_file_mappings(&args, &config.config_dir)? {
+            return remove_file_mappings(&mappings, &config.config_dir);
+        }
+
         let rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
 
         validate_named_args(
@@ -56,35 +177,44 @@ impl CommandInterface for CopyDirCommand {
             .unwrap();
 
         let relative_target_path = get_relative_dir(&config.config_dir, target_dir);
-        let abs_target_path = canonicalize(relative_target_path);
+        let abs_target_path = canonicalize(&relative_target_path);
         if let Err(target_err) = abs_target_path {
             if target_err.raw_os_error().unwrap() == 2 {
                 warn!("{}", Yellow.paint("The file(s) were already removed..."));
                 return Ok(());
             }
 
-            return Err(format!("{target_err}"));
+            return Err(Error::io(relative_target_path, target_err));
         }
         let abs_target_path = abs_target_path.unwrap();
 
         if abs_target_path.as_os_str() == config.config_dir.as_os_str() {
-            return Err(format!("{}", Red.paint("cannot delete config_dir")));
+            return Err(Error::Command(format!(
+                "{}",
+                Red.paint("cannot delete config_dir")
+            )));
         }
 
         remove_dir(&abs_target_path)
     }
 
-    fn update(
-        &self,
-        _args: ConfigValue,
-        _config: &CommandConfig,
-        _progress: &ProgressBar,
-    ) -> Result<(), String> {
-        warn!(
-            "{}",
-            Yellow.paint("update not implemented for copy command")
-        );
-        Ok(())
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        let options = get_options(&args, Overwrite::IfDifferent)?;
+
+        if let Some(mappings) = get_file_mappings(&args, &config.config_dir)? {
+            let walk_options = get_walk_options(&args)?;
+            return copy_file_mappings(&mappings, &walk_options, options, progress);
+        }
+
+        let dirs = get_source_and_target(args.clone(), &config.config_dir)?;
+
+        copy_dir(
+            &dirs.src,
+            &dirs.target,
+            &dirs.walk_options,
+            options,
+            progress,
+        )
     }
 }
 
@@ -104,12 +234,91 @@ fn target_file_is_newer(file_src: &Path, file_target: &Path) -> bool {
     }
 }
 
+/**
+ * Ok(false) means that the target is already up to date.
+ * Targets that differ from the source but are kept because of `overwrite` are reported as errors,
+ * so they don't silently diverge from the source.
+ */
+fn should_write_file(src: &Path, target: &Path, overwrite: Overwrite) -> Result<bool> {
+    if !target.exists() {
+        return Ok(true);
+    }
+
+    if overwrite == Overwrite::Always {
+        return Ok(true);
+    }
+
+    if files_are_equal(src, target) {
+        return Ok(false);
+    }
+
+    let skipped = |reason: &str| {
+        Err(Error::Command(format!(
+            "{} {}: {} (overwrite: {})",
+            Yellow.paint("Skipped"),
+            Yellow.bold().paint(target.display().to_string()),
+            reason,
+            overwrite
+        )))
+    };
+
+    match overwrite {
+        Overwrite::Never => skipped("the destination already exists"),
+        Overwrite::IfNewer if target_file_is_newer(src, target) => {
+            skipped("the target file is newer than the source file")
+        }
+        _ => Ok(true),
+    }
+}
+
+fn write_file(src: &Path, target: &Path, options: CopyOptions) -> Result<()> {
+    if options.hard {
+        debug!(
+            "Hard linking {} to {} ...",
+            White.bold().paint(src.to_str().unwrap()),
+            White.bold().paint(target.to_str().unwrap())
+        );
+
+        return create_hard_link(src, target, true);
+    }
+
+    debug!(
+        "Copying {} to {} ...",
+        White.bold().paint(src.to_str().unwrap()),
+        White.bold().paint(target.to_str().unwrap())
+    );
+
+    fs::copy(src, target).map_err(|e| Error::io(src, e))?;
+
+    if options.preserve {
+        preserve_metadata(src, target)?;
+    }
+
+    if let Some(mode) = options.mode {
+        set_mode(target, mode)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Files that aren't written still get the configured mode.
+ */
+fn keep_file(target: &Path, options: CopyOptions) -> Result<()> {
+    if let Some(mode) = options.mode {
+        set_mode(target, mode)?;
+    }
+
+    Ok(())
+}
+
 fn copy_files(
     source_dir: &PathBuf,
     destination_dir: &Path,
-    ignore: HashSet<String>,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    walk_options: &WalkOptions,
+    options: CopyOptions,
+    progress: &Progress,
+) -> Result<()> {
     let message = format!(
         "Copying files from {} to {} ...",
         White.bold().paint(source_dir.to_str().unwrap()),
@@ -119,63 +328,152 @@ fn copy_files(
     debug!(message);
     progress.set_message(message);
 
-    walk_files(source_dir, destination_dir, ignore, |src, target| {
-        if target_file_is_newer(src, target) {
-            let target_file_name = target.file_name().unwrap().to_str().unwrap();
-            info!(
-                "{}: {}: {}",
-                Yellow.paint("Skipping"),
-                Yellow.bold().paint(target_file_name),
-                Yellow
-                    .bold()
-                    .paint("The target file is newer than the source file.")
-            );
-            return;
-        }
+    let num_changed = AtomicUsize::new(0);
+    let num_unchanged = AtomicUsize::new(0);
+    let num_skipped = AtomicUsize::new(0);
+
+    let result = walk_files(
+        source_dir,
+        destination_dir,
+        walk_options,
+        progress,
+        |src, target| {
+            let should_write = match should_write_file(src, target, options.overwrite) {
+                Ok(should_write) => should_write,
+                Err(err) => {
+                    num_skipped.fetch_add(1, Ordering::Relaxed);
+                    return Err(err);
+                }
+            };
+
+            if !should_write {
+                debug!(
+                    "{} {}",
+                    White.bold().paint(target.to_str().unwrap()),
+                    Green.paint("is up to date")
+                );
+                num_unchanged.fetch_add(1, Ordering::Relaxed);
+                return keep_file(target, options);
+            }
 
-        debug!(
-            "Copying {} to {} ...",
-            White.bold().paint(src.to_str().unwrap()),
-            White.bold().paint(target.to_str().unwrap())
-        );
+            write_file(src, target, options)?;
+            num_changed.fetch_add(1, Ordering::Relaxed);
 
-        fs::copy(src, target)
-            .map_err(|e| format!("Failed to copy file: {}", Red.paint(e.to_string())))
-            .ok();
-    })
+            Ok(())
+        },
+    );
+
+    let summary = format!(
+        "{} {} {} {} {}",
+        Green.paint(format!("{} changed", num_changed.into_inner())),
+        White.paint("/"),
+        White.paint(format!("{} unchanged", num_unchanged.into_inner())),
+        White.paint("/"),
+        Yellow.paint(format!("{} skipped", num_skipped.into_inner()))
+    );
+
+    info!("{}", summary);
+    progress.set_message(summary);
+
+    result
 }
 
 pub fn copy_dir(
     source: &str,
     destination: &str,
-    ignore: HashSet<String>,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    walk_options: &WalkOptions,
+    options: CopyOptions,
+    progress: &Progress,
+) -> Result<()> {
     let source_dir = expand_path(source, false)?;
     let destination_dir = expand_path(destination, true)?;
 
     if source_dir == destination_dir {
-        return Err(format!(
+        return Err(Error::Command(format!(
             "{} {}",
             Red.paint("Source and destination directories are the same:"),
             Red.paint(source)
-        ));
+        )));
+    }
+
+    copy_files(
+        &source_dir,
+        &destination_dir,
+        walk_options,
+        options,
+        progress,
+    )
+}
+
+fn copy_file_mappings(
+    mappings: &[FileMapping],
+    walk_options: &WalkOptions,
+    options: CopyOptions,
+    progress: &Progress,
+) -> Result<()> {
+    let mut errors: Vec<Error> = vec![];
+
+    for mapping in mappings {
+        let result = match expand_path(&mapping.src, false)? {
+            src if src.is_dir() => copy_dir(
+                &mapping.src,
+                &mapping.target,
+                walk_options,
+                options,
+                progress,
+            ),
+            src if src.exists() => expand_file_target(&mapping.target).and_then(|target| {
+                if !should_write_file(&src, &target, options.overwrite)? {
+                    return keep_file(&target, options);
+                }
+                write_file(&src, &target, options)
+            }),
+            _ => Err(Error::Command(format!(
+                "Source file does not exist: {}",
+                mapping.src
+            ))),
+        };
+
+        if let Err(err) = result {
+            errors.push(err);
+        }
     }
 
-    copy_files(&source_dir, &destination_dir, ignore, progress)
+    join_errors(errors)
+}
+
+fn remove_file_mappings(mappings: &[FileMapping], config_dir: &PathDir) -> Result<()> {
+    for mapping in mappings {
+        let target = expand_path(&mapping.target, false)?;
+
+        if target.as_os_str() == config_dir.as_os_str() {
+            return Err(Error::Command(format!(
+                "{}",
+                Red.paint("cannot delete config_dir")
+            )));
+        }
+
+        if target.is_dir() {
+            remove_dir(&target)?;
+        } else if target.exists() {
+            fs::remove_file(&target).map_err(|e| Error::io(&target, e))?;
+        }
+    }
+
+    Ok(())
 }
 
-pub fn remove_dir(target: &Path) -> Result<(), String> {
+pub fn remove_dir(target: &Path) -> Result<()> {
     let expanded_target_dir = expand_path(target.to_str().unwrap(), false);
     if expanded_target_dir.is_err() {
         return Err(expanded_target_dir.err().unwrap());
     }
     let expanded_target_dir = expanded_target_dir.unwrap();
 
-    let result = fs::remove_dir_all(expanded_target_dir);
+    let result = fs::remove_dir_all(&expanded_target_dir);
 
-    if result.is_err() {
-        return Err(result.err().unwrap().to_string());
+    if let Err(err_remove) = result {
+        return Err(Error::io(expanded_target_dir, err_remove));
     }
 
     Ok(())
@@ -183,10 +481,9 @@ pub fn remove_dir(target: &Path) -> Result<(), String> {
 
 #[cfg(test)]
 mod test {
-    use crate::utils::shell::Shell;
+    use crate::utils::hard_link::is_same_file;
 
     use super::*;
-    use ergo_fs::PathDir;
     use tempfile::{tempdir, tempfile_in, NamedTempFile};
 
     #[test]
@@ -196,11 +493,18 @@ mod test {
         tempfile_in(src_path).unwrap();
         let src = src_path.to_str().unwrap();
 
-        let pb = ProgressBar::new(0);
-
-        assert!(copy_dir(src, src, HashSet::new(), &pb)
-            .unwrap_err()
-            .contains("Source and destination directories are the same"));
+        let pb = Progress::default();
+
+        assert!(copy_dir(
+            src,
+            src,
+            &WalkOptions::default(),
+            CopyOptions::default(),
+            &pb
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("Source and destination directories are the same"));
     }
 
     #[test]
@@ -213,15 +517,254 @@ mod test {
         let dest_dir = tempdir().unwrap();
         let dest = dest_dir.path().to_str().unwrap();
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
-        copy_dir(src, dest, HashSet::new(), &pb).unwrap();
+        copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions::default(),
+            &pb,
+        )
+        .unwrap();
 
         let dest_file = dest_dir.path().join(src_file.path().file_name().unwrap());
 
         assert!(dest_file.exists());
     }
 
+    #[test]
+    fn it_hard_links_files() {
+        let src_dir = tempdir().unwrap();
+        let src = src_dir.path().to_str().unwrap();
+        let src_file = NamedTempFile::new_in(src_dir.path()).unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().to_str().unwrap();
+
+        let pb = Progress::default();
+
+        copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions {
+                hard: true,
+                ..Default::default()
+            },
+            &pb,
+        )
+        .unwrap();
+
+        let dest_file = dest_dir.path().join(src_file.path().file_name().unwrap());
+
+        assert!(is_same_file(src_file.path(), &dest_file));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn it_keeps_the_executable_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let src_dir = tempdir().unwrap();
+        let src = src_dir.path().to_str().unwrap();
+        let src_file = src_dir.path().join("script.sh");
+        fs::File::create(&src_file).unwrap();
+        set_mode(&src_file, 0o755).unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().to_str().unwrap();
+
+        let pb = Progress::default();
+
+        copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions::default(),
+            &pb,
+        )
+        .unwrap();
+
+        let dest_mode = dest_dir
+            .path()
+            .join("script.sh")
+            .metadata()
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(dest_mode & 0o777, 0o755);
+
+        copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions {
+                mode: Some(0o600),
+                ..Default::default()
+            },
+            &pb,
+        )
+        .unwrap();
+
+        let dest_mode = dest_dir
+            .path()
+            .join("script.sh")
+            .metadata()
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(dest_mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn it_only_updates_changed_files() {
+        let src_dir = tempdir().unwrap();
+        let src = src_dir.path().to_str().unwrap();
+        fs::write(src_dir.path().join("changed.txt"), "new content").unwrap();
+        fs::write(src_dir.path().join("unchanged.txt"), "same content").unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().to_str().unwrap();
+        fs::write(dest_dir.path().join("changed.txt"), "old content").unwrap();
+        fs::write(dest_dir.path().join("unchanged.txt"), "same content").unwrap();
+
+        let pb = Progress::default();
+
+        copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions {
+                overwrite: Overwrite::IfDifferent,
+                ..Default::default()
+            },
+            &pb,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dest_dir.path().join("changed.txt")).unwrap(),
+            "new content"
+        );
+        assert!(pb.message().contains("1 changed"));
+        assert!(pb.message().contains("1 unchanged"));
+    }
+
+    #[test]
+    fn it_reports_files_that_were_skipped() {
+        let src_dir = tempdir().unwrap();
+        let src = src_dir.path().to_str().unwrap();
+        fs::write(src_dir.path().join("example.txt"), "new content").unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().to_str().unwrap();
+        fs::write(dest_dir.path().join("example.txt"), "old content").unwrap();
+
+        let pb = Progress::default();
+
+        let err = copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions {
+                overwrite: Overwrite::Never,
+                ..Default::default()
+            },
+            &pb,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("example.txt"));
+        assert!(err.contains("overwrite: never"));
+        assert!(pb.message().contains("0 unchanged"));
+        assert!(pb.message().contains("1 skipped"));
+        assert_eq!(
+            fs::read_to_string(dest_dir.path().join("example.txt")).unwrap(),
+            "old content"
+        );
+
+        copy_dir(
+            src,
+            dest,
+            &WalkOptions::default(),
+            CopyOptions {
+                overwrite: Overwrite::Always,
+                ..Default::default()
+            },
+            &pb,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dest_dir.path().join("example.txt")).unwrap(),
+            "new content"
+        );
+    }
+
+    #[test]
+    fn it_copies_files_to_different_names() {
+        let src_dir = tempdir().unwrap();
+        fs::write(src_dir.path().join("gitconfig-work"), "[user]").unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join("config").join(".gitconfig");
+
+        let mappings = vec![FileMapping {
+            src: src_dir
+                .path()
+                .join("gitconfig-work")
+                .to_string_lossy()
+                .to_string(),
+            target: dest_path.to_string_lossy().to_string(),
+        }];
+
+        let pb = Progress::default();
+
+        copy_file_mappings(
+            &mappings,
+            &WalkOptions::default(),
+            CopyOptions::default(),
+            &pb,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&dest_path).unwrap(), "[user]");
+
+        remove_file_mappings(&mappings, &PathDir::new(src_dir.path()).unwrap()).unwrap();
+
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn it_ignores_files_of_mapped_directories() {
+        let src_dir = tempdir().unwrap();
+        let nvim_path = src_dir.path().join("nvim");
+        fs::create_dir(&nvim_path).unwrap();
+        fs::write(nvim_path.join("init.lua"), "-- init").unwrap();
+        fs::write(nvim_path.join("README.md"), "# nvim").unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join("nvim");
+
+        let mappings = vec![FileMapping {
+            src: nvim_path.to_string_lossy().to_string(),
+            target: dest_path.to_string_lossy().to_string(),
+        }];
+        let walk_options = WalkOptions {
+            ignore: vec![String::from("*.md")],
+            ..Default::default()
+        };
+
+        let pb = Progress::default();
+
+        copy_file_mappings(&mappings, &walk_options, CopyOptions::default(), &pb).unwrap();
+
+        assert!(dest_path.join("init.lua").is_file());
+        assert!(!dest_path.join("README.md").exists());
+    }
+
     #[test]
     fn it_removes_dir() {
         let dir = tempdir().unwrap();
@@ -236,7 +779,6 @@ mod test {
         let copy = CopyDirCommand {};
 
         let dir = tempdir().unwrap();
-        let config_dir = PathDir::new(&dir).unwrap();
 
         let mut args = HashMap::new();
         args.insert(
@@ -248,20 +790,19 @@ mod test {
             ConfigValue::String(String::from(".")),
         );
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
         let result = copy.uninstall(
             ConfigValue::Hash(args),
-            &CommandConfig {
-                config_dir,
-                temp_dir: tempdir().unwrap().path().to_str().unwrap().to_string(),
-                default_shell: Shell::Bash,
-            },
+            &CommandConfig::for_dir(dir.path()),
             &pb,
         );
 
         assert!(dir.path().exists());
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("cannot delete config_dir"))
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("cannot delete config_dir"))
     }
 }
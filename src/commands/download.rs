@@ -0,0 +1,235 @@
+use ansi_term::Color::{Green, White};
+use std::{collections::HashMap, fs, thread};
+use tracing::debug;
+
+use crate::{
+    command::{CommandConfig, CommandInterface},
+    config::{
+        config_value::ConfigValue,
+        validation_rules::{is_string::IsString, required::Required},
+        validator::{validate_named_args, ValidationRule},
+    },
+    error::{Error, Result},
+    events::Progress,
+    utils::{
+        directory::{expand_file_target, expand_path, get_relative_dir},
+        download::{download, DownloadCache},
+        hash::get_file_hash,
+        metadata::{parse_mode, set_mode},
+        variables::PLACEHOLDER,
+        verify::{get_verification, Checksum, ChecksumAlgorithm, Verification},
+    },
+};
+
+pub struct DownloadCommand {}
+
+static LOCK_KIND: &str = "download";
+
+struct DownloadArgs {
+    url: String,
+    target: String,
+    verification: Verification,
+    mode: Option<u32>,
+}
+
+fn get_args(args: &ConfigValue, config: &CommandConfig) -> Result<DownloadArgs> {
+    let url_rules: Vec<Box<dyn ValidationRule>> =
+        vec![Box::new(Required {}), Box::new(IsString {})];
+    let target_rules: Vec<Box<dyn ValidationRule>> =
+        vec![Box::new(Required {}), Box::new(IsString {})];
+
+    validate_named_args(
+        args.to_owned(),
+        HashMap::from([
+            (String::from("url"), url_rules),
+            (String::from("target"), target_rules),
+        ]),
+    )?;
+
+    let arg_values = args.as_hash().unwrap();
+    let get_str = |name: &str| {
+        arg_values
+            .get(name)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+    };
+
+    let mode = match arg_values.get("mode") {
+        Some(mode) => Some(parse_mode(mode)?),
+        None => None,
+    };
+
+    Ok(DownloadArgs {
+        url: get_str("url").unwrap(),
+        target: get_relative_dir(&config.config_dir, &get_str("target").unwrap()),
+        verification: get_verification(args, &config.config_dir)?,
+        mode,
+    })
+}
+
+/**
+ * Adds the checksum of a frozen lockfile to the configured verification.
+ */
+fn get_locked_verification(args: &DownloadArgs, config: &CommandConfig) -> Result<Verification> {
+    let mut verification = args.verification.clone();
+    if let Some(sha256) = config.lockfile.get_frozen_version(LOCK_KIND, &args.url)? {
+        verification.checksums.push(Checksum {
+            algorithm: ChecksumAlgorithm::Sha256,
+            value: sha256,
+        });
+    }
+
+    Ok(verification)
+}
+
+fn install_file(
+    args: &DownloadArgs,
+    config: &CommandConfig,
+    refresh: bool,
+    progress: &Progress,
+) -> Result<()> {
+    let message = format!(
+        "Downloading {} to {} ...",
+        White.bold().paint(&args.url),
+        White.bold().paint(&args.target)
+    );
+
+    debug!(message);
+    progress.set_message(message);
+
+    let verification = get_locked_verification(args, config)?;
+    let cache = DownloadCache::new(&expand_path(&config.cache_dir, false)?);
+
+    // the file and its signature are fetched at the same time
+    let (cached_file, signature_file) = thread::scope(|scope| {
+        // signatures are small and might be rotated, so they are always fetched again
+        let signature_file = args.verification.signature.as_ref().map(|signature| {
+            scope.spawn(|| download(&cache, &config.connections, &signature.url, None, true))
+        });
+
+        let cached_file = download(
+            &cache,
+            &config.connections,
+            &args.url,
+            verification.get_sha256(),
+            refresh,
+        );
+
+        let signature_file = signature_file
+            .map(|handle| {
+                handle.join().unwrap_or_else(|_| {
+                    Err(Error::Command(String::from(
+                        "Downloading the signature panicked",
+                    )))
+                })
+            })
+            .transpose();
+
+        (cached_file, signature_file)
+    });
+    let cached_file = cached_file?;
+    let signature_file = signature_file?;
+
+    verification.verify(&cached_file, signature_file.as_deref())?;
+    let sha256 = get_file_hash(&cached_file)?;
+    config.lockfile.lock(LOCK_KIND, &args.url, &sha256);
+
+    let target = expand_file_target(&args.target)?;
+    fs::copy(&cached_file, &target).map_err(|e| Error::io(&target, e))?;
+
+    if let Some(mode) = args.mode {
+        set_mode(&target, mode)?;
+    }
+
+    let task = progress.get_task();
+    config
+        .variables
+        .set_output(task, "path", &target.to_string_lossy());
+    config.variables.set_output(task, "sha256", &sha256);
+
+    progress.set_message(format!(
+        "{} {}",
+        Green.paint("Downloaded"),
+        White.bold().paint(&args.target)
+    ));
+
+    Ok(())
+}
+
+/**
+ * Fills the download cache for several `download` commands at once,
+ * so they don't wait for the network one after another when they run.
+ * Errors are ignored, the commands report them themselves.
+ */
+pub fn prefetch(commands: &[ConfigValue], config: &CommandConfig) {
+    let Ok(cache_dir) = expand_path(&config.cache_dir, false) else {
+        return;
+    };
+    let cache = DownloadCache::new(&cache_dir);
+
+    thread::scope(|scope| {
+        for args in commands {
+            let Ok(args) = get_args(&config.variables.interpolate(args), config) else {
+                continue;
+            };
+            // the URL depends on a command that didn't run yet
+            if PLACEHOLDER.is_match(&args.url) {
+                continue;
+            }
+
+            let Ok(verification) = get_locked_verification(&args, config) else {
+                continue;
+            };
+
+            let cache = &cache;
+            scope.spawn(move || {
+                if let Err(err) = download(
+                    cache,
+                    &config.connections,
+                    &args.url,
+                    verification.get_sha256(),
+                    false,
+                ) {
+                    debug!("Failed to prefetch {}: {}", args.url, err);
+                }
+            });
+        }
+    });
+}
+
+impl CommandInterface for DownloadCommand {
+    fn install(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        let args = get_args(&args, config)?;
+
+        install_file(&args, config, false, progress)
+    }
+
+    fn uninstall(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        _progress: &Progress,
+    ) -> Result<()> {
+        let args = get_args(&args, config)?;
+        let target = expand_path(&args.target, false)?;
+
+        if target.is_file() {
+            fs::remove_file(&target).map_err(|e| Error::io(&target, e))?;
+        }
+
+        config.lockfile.unlock(LOCK_KIND, &args.url);
+
+        Ok(())
+    }
+
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        let args = get_args(&args, config)?;
+
+        install_file(&args, config, true, progress)
+    }
+}
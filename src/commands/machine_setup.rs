@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 
-use indicatif::ProgressBar;
 use tracing::Level;
 
 use crate::{
@@ -10,6 +9,8 @@ use crate::{
         validation_rules::{is_string::IsString, required::Required},
         validator::{validate_named_args, ValidationRule},
     },
+    error::{Error, Result},
+    events::Progress,
     terminal::{
         cli::{Args, SubCommand},
         command::execute_command,
@@ -18,10 +19,10 @@ use crate::{
 
 pub struct MachineSetupCommand {}
 
-fn execute_config(command: SubCommand, args: ConfigValue) -> Result<(), String> {
+fn execute_config(command: SubCommand, args: ConfigValue, config: &CommandConfig) -> Result<()> {
     let parameters = args.as_hash();
     if parameters.is_none() {
-        return Err(String::from("args is not an object"));
+        return Err(Error::Validation(String::from("args is not an object")));
     }
     let parameters = parameters.unwrap();
 
@@ -36,7 +37,7 @@ fn execute_config(command: SubCommand, args: ConfigValue) -> Result<(), String>
         ]),
     )?;
 
-    let config = parameters
+    let config_path = parameters
         .get("config")
         .unwrap()
         .as_str()
@@ -53,42 +54,50 @@ fn execute_config(command: SubCommand, args: ConfigValue) -> Result<(), String>
     let args = Args {
         command,
         select: false,
-        config,
+        config: config_path,
         task,
+        tag: None,
         debug: false,
         level: Level::WARN,
+        frozen: config.lockfile.is_frozen(),
+        purge: false,
+        force: false,
+        ui: false,
+        reboot: false,
+        host: None,
+        all: false,
+        group: None,
+        vars: vec![],
     };
 
-    execute_command(args);
-
-    Ok(())
+    execute_command(args)
 }
 
 impl CommandInterface for MachineSetupCommand {
     fn install(
         &self,
         args: ConfigValue,
-        _config: &CommandConfig,
-        _progress: &ProgressBar,
-    ) -> Result<(), String> {
-        execute_config(SubCommand::Install, args)
+        config: &CommandConfig,
+        _progress: &Progress,
+    ) -> Result<()> {
+        execute_config(SubCommand::Install, args, config)
     }
 
     fn uninstall(
         &self,
         args: ConfigValue,
-        _config: &CommandConfig,
-        _progress: &ProgressBar,
-    ) -> Result<(), String> {
-        execute_config(SubCommand::Uninstall, args)
+        config: &CommandConfig,
+        _progress: &Progress,
+    ) -> Result<()> {
+        execute_config(SubCommand::Uninstall, args, config)
     }
 
     fn update(
         &self,
         args: ConfigValue,
-        _config: &CommandConfig,
-        _progress: &ProgressBar,
-    ) -> Result<(), String> {
-        execute_config(SubCommand::Update, args)
+        config: &CommandConfig,
+        _progress: &Progress,
+    ) -> Result<()> {
+        execute_config(SubCommand::Update, args, config)
     }
 }
@@ -1,5 +1,12 @@
+pub mod assert;
 pub mod clone;
 pub mod copy;
+pub mod download;
 pub mod machine_setup;
+pub mod plugin;
 pub mod run;
+pub mod script;
 pub mod symlink;
+pub mod wait_for;
+#[cfg(feature = "wasm")]
+pub mod wasm_plugin;
@@ -0,0 +1,334 @@
+use ansi_term::Color::White;
+use ergo_fs::{Path, PathBuf};
+use serde_json::{json, Value};
+use std::{
+    env,
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
+};
+use tracing::debug;
+
+use crate::{
+    command::{CommandConfig, CommandInterface},
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+    events::Progress,
+    task_runner::TaskRunnerMode,
+    utils::restart::Restart,
+};
+
+pub static PLUGIN_PREFIX: &str = "machine-setup-plugin-";
+pub static PLUGIN_DIR: &str = "plugins";
+
+/**
+ * Bumped whenever the messages change in a way that breaks existing plugins.
+ */
+pub const PLUGIN_PROTOCOL_VERSION: u64 = 1;
+
+/**
+ * Runs an external executable for commands that aren't built in.
+ * The plugin receives a single JSON request on stdin and answers with one JSON message per line on stdout,
+ * see the README for the protocol.
+ */
+pub struct PluginCommand {
+    pub name: String,
+    pub executable: PathBuf,
+}
+
+fn get_executable_names(name: &str) -> Vec<String> {
+    let executable = format!("{PLUGIN_PREFIX}{name}");
+
+    if cfg!(target_family = "windows") {
+        vec![format!("{executable}.exe"), executable]
+    } else {
+        vec![executable]
+    }
+}
+
+/**
+ * Plugins next to the config take precedence over the ones on the PATH,
+ * so a dotfiles repository can ship its own plugins.
+ */
+pub fn find_plugin(name: &str, config_dir: &Path) -> Option<PathBuf> {
+    let path_dirs = env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let search_dirs = std::iter::once(config_dir.join(PLUGIN_DIR)).chain(path_dirs);
+
+    for dir in search_dirs {
+        for executable in get_executable_names(name) {
+            let path = dir.join(executable);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+
+    None
+}
+
+pub(crate) fn get_request(
+    mode: TaskRunnerMode,
+    args: &ConfigValue,
+    config: &CommandConfig,
+) -> Value {
+    json!({
+        "protocol": PLUGIN_PROTOCOL_VERSION,
+        "mode": mode.to_string(),
+        "args": args.to_json(),
+        "config": {
+            "config_dir": config.config_dir.to_string_lossy(),
+            "temp_dir": config.temp_dir,
+            "state_dir": config.state_dir,
+            "cache_dir": config.cache_dir,
+        },
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum PluginMessage {
+    Progress(String),
+    Restart(Restart, Option<String>),
+    Result(std::result::Result<(), String>),
+    Output(String),
+}
+
+fn parse_message(line: &str) -> PluginMessage {
+    let Ok(message) = serde_json::from_str::<Value>(line) else {
+        return PluginMessage::Output(line.to_string());
+    };
+
+    let text = message["message"].as_str().unwrap_or_default().to_string();
+
+    match message["type"].as_str() {
+        Some("progress") => PluginMessage::Progress(text),
+        Some("restart") => {
+            let restart = message["restart"].as_str().unwrap_or_default();
+            let reason = message["reason"].as_str().map(|reason| reason.to_string());
+
+            match Restart::from_str(restart) {
+                Ok(restart) => PluginMessage::Restart(restart, reason),
+                Err(_) => PluginMessage::Output(line.to_string()),
+            }
+        }
+        Some("result") if message["ok"].as_bool().unwrap_or(false) => PluginMessage::Result(Ok(())),
+        Some("result") => PluginMessage::Result(Err(message["error"]
+            .as_str()
+            .unwrap_or("unknown error")
+            .to_string())),
+        _ => PluginMessage::Output(line.to_string()),
+    }
+}
+
+/**
+ * Returns the result once the plugin reported one, all other messages are only displayed.
+ */
+pub(crate) fn handle_output_line(
+    name: &str,
+    line: &str,
+    config: &CommandConfig,
+    progress: &Progress,
+) -> Option<std::result::Result<(), String>> {
+    match parse_message(line) {
+        PluginMessage::Progress(message) => progress.set_message(format!("▶️ {message}")),
+        PluginMessage::Restart(restart, reason) => {
+            config
+                .restarts
+                .require(progress.get_task(), restart, reason.as_deref())
+        }
+        PluginMessage::Output(output) => debug!("{name}: {output}"),
+        PluginMessage::Result(plugin_result) => return Some(plugin_result),
+    }
+
+    None
+}
+
+impl PluginCommand {
+    fn run(
+        &self,
+        mode: TaskRunnerMode,
+        args: &ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        let program = self.executable.display().to_string();
+        debug!("Running plugin {program} ({mode}) ...");
+        progress.set_message(format!("🔌 {}", White.bold().paint(&self.name)));
+
+        let mut child = Command::new(&self.executable)
+            .current_dir(&config.config_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::Spawn {
+                program: program.to_string(),
+                source: e,
+            })?;
+
+        let request = get_request(mode, args, config).to_string();
+        {
+            // stdin is closed at the end of the block, so the plugin knows that the request is complete
+            let mut stdin = child.stdin.take().unwrap();
+            stdin
+                .write_all(request.as_bytes())
+                .and_then(|_| stdin.write_all(b"\n"))
+                .map_err(|e| Error::Command(format!("{program}: {e}")))?;
+        }
+
+        let stdout_reader = BufReader::new(child.stdout.take().unwrap());
+        let stderr_reader = BufReader::new(child.stderr.take().unwrap());
+
+        let mut result = None;
+        let mut errors: Vec<String> = vec![];
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for line in stdout_reader.lines().map_while(|line| line.ok()) {
+                    if let Some(plugin_result) =
+                        handle_output_line(&self.name, &line, config, progress)
+                    {
+                        result = Some(plugin_result);
+                    }
+                }
+            });
+            s.spawn(|| {
+                errors.extend(
+                    stderr_reader
+                        .lines()
+                        .map_while(|line| line.ok())
+                        .filter(|line| !line.trim().is_empty()),
+                );
+            });
+        });
+
+        let status = child.wait().map_err(|e| Error::Spawn {
+            program: program.to_string(),
+            source: e,
+        })?;
+
+        if let Some(Err(err_plugin)) = result {
+            return Err(Error::Command(err_plugin));
+        }
+
+        if !status.success() {
+            for error in errors {
+                debug!("{}: {error}", self.name);
+            }
+
+            return Err(Error::ExitStatus {
+                command: program,
+                code: status.code(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl CommandInterface for PluginCommand {
+    fn install(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        self.run(TaskRunnerMode::Install, &args, config, progress)
+    }
+
+    fn uninstall(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        self.run(TaskRunnerMode::Uninstall, &args, config, progress)
+    }
+
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        self.run(TaskRunnerMode::Update, &args, config, progress)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_parses_plugin_messages() {
+        assert_eq!(
+            parse_message(r#"{"type": "progress", "message": "installing"}"#),
+            PluginMessage::Progress(String::from("installing"))
+        );
+        assert_eq!(
+            parse_message(r#"{"type": "result", "ok": true}"#),
+            PluginMessage::Result(Ok(()))
+        );
+        assert_eq!(
+            parse_message(r#"{"type": "result", "ok": false, "error": "nope"}"#),
+            PluginMessage::Result(Err(String::from("nope")))
+        );
+        assert_eq!(
+            parse_message(r#"{"type": "restart", "restart": "relogin", "reason": "group docker"}"#),
+            PluginMessage::Restart(Restart::Relogin, Some(String::from("group docker")))
+        );
+        assert_eq!(
+            parse_message("plain output"),
+            PluginMessage::Output(String::from("plain output"))
+        );
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn it_runs_plugins_from_the_config_dir() {
+        use crate::utils::metadata::set_mode;
+        use std::{collections::HashMap, fs};
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join(PLUGIN_DIR)).unwrap();
+
+        let plugin_path = dir
+            .path()
+            .join(PLUGIN_DIR)
+            .join("machine-setup-plugin-greet");
+        fs::write(
+            &plugin_path,
+            "#!/bin/sh\nread request\necho \"$request\" > request.json\necho '{\"type\": \"result\", \"ok\": true}'\n",
+        )
+        .unwrap();
+        set_mode(&plugin_path, 0o755).unwrap();
+
+        let executable = find_plugin("greet", dir.path()).unwrap();
+        assert_eq!(executable, plugin_path);
+        assert!(find_plugin("missing", dir.path()).is_none());
+
+        let plugin = PluginCommand {
+            name: String::from("greet"),
+            executable,
+        };
+
+        let config = CommandConfig::for_dir(dir.path());
+
+        plugin
+            .install(
+                ConfigValue::Hash(HashMap::from([(
+                    String::from("name"),
+                    ConfigValue::String(String::from("world")),
+                )])),
+                &config,
+                &Progress::default(),
+            )
+            .unwrap();
+
+        let request: Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("request.json")).unwrap())
+                .unwrap();
+        assert_eq!(request["protocol"], PLUGIN_PROTOCOL_VERSION);
+        assert_eq!(request["mode"], "install");
+        assert_eq!(request["args"]["name"], "world");
+    }
+}
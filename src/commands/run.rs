@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    env,
     fs::remove_file,
     io::{BufRead, BufReader},
     process::{Command, Stdio},
@@ -8,20 +8,26 @@ use std::{
 };
 
 use ansi_term::Color::White;
-use indicatif::ProgressBar;
 use tracing::info;
 
 use crate::{
     command::{CommandConfig, CommandInterface},
     config::{
         config_value::ConfigValue,
-        validation_rules::{is_array::IsArray, is_string::IsString, one_of::OneOf},
-        validator::{arguments_are_named, validate_args, validate_named_args, ValidationRule},
+        os::Os,
+        validation_rules::{
+            is_array::IsArray, is_hash::IsHash, is_string::IsString, one_of::OneOf,
+        },
+        validator::{arguments_are_named, validate_args, ValidationRule},
     },
+    error::{Error, Result},
+    events::Progress,
     task_runner::TaskRunnerMode,
     utils::{
+        restart::Restart,
         shell::{create_script_file, strip_line_err_info, Shell},
         terminal::set_environment_variables,
+        variables::is_variable_name,
     },
 };
 
@@ -39,36 +45,80 @@ fn get_commands_from_yaml(args: ConfigValue) -> Vec<String> {
     };
 }
 
-fn get_commands(args: ConfigValue, mode: TaskRunnerMode) -> Result<Vec<String>, String> {
-    let is_str_or_array = Box::new(OneOf {
+fn get_command_rules() -> Vec<Box<dyn ValidationRule>> {
+    vec![Box::new(OneOf {
         rules: vec![Box::new(IsArray {}), Box::new(IsString {})],
-    });
-    let rules: Vec<Box<dyn ValidationRule>> = vec![is_str_or_array];
+    })]
+}
+
+/**
+ * The commands of a mode might be keyed by platform, e.g. `install: { linux: [...], macos: [...] }`.
+ * Platforms without a variant don't run anything.
+ */
+fn select_platform_commands(commands: ConfigValue, os: Option<&Os>) -> Result<Option<ConfigValue>> {
+    let Some(variants) = commands.as_hash() else {
+        return Ok(Some(commands));
+    };
+
+    for platform in variants.keys() {
+        Os::from_str(platform).map_err(Error::Validation)?;
+    }
 
+    let variant = os.and_then(|os| variants.get(&os.to_string())).cloned();
+    if let Some(variant) = &variant {
+        validate_args(Some(variant), get_command_rules())?;
+    }
+
+    Ok(variant)
+}
+
+fn get_commands(args: ConfigValue, mode: TaskRunnerMode) -> Result<Vec<String>> {
+    get_commands_for_os(args, mode, Os::from_str(env::consts::OS).ok().as_ref())
+}
+
+fn get_commands_for_os(
+    args: ConfigValue,
+    mode: TaskRunnerMode,
+    os: Option<&Os>,
+) -> Result<Vec<String>> {
     let method_name = mode.to_string();
 
     if arguments_are_named(Some(&args)) {
-        let named_args = args.clone();
-        let method = method_name.clone();
-
-        if !named_args.as_hash().unwrap().contains_key(&method) {
-            info!("{} is not defined...", White.bold().paint(&method));
+        let Some(commands) = args.as_hash().unwrap().get(&method_name).cloned() else {
+            info!("{} is not defined...", White.bold().paint(&method_name));
 
             return Ok(vec![]);
+        };
+
+        // the commands might be keyed by platform, so they can't be validated as positional arguments
+        let rule = OneOf {
+            rules: vec![
+                Box::new(IsArray {}),
+                Box::new(IsString {}),
+                Box::new(IsHash {}),
+            ],
+        };
+        if !rule.validate(Some(&commands)) {
+            return Err(Error::Validation(format!(
+                "{method_name}: {}",
+                rule.to_string()
+            )));
         }
 
-        validate_named_args(named_args, HashMap::from([(method, rules)]))?;
-
-        return Ok(get_commands_from_yaml(
-            args.as_hash()
-                .unwrap()
-                .get(&method_name)
-                .unwrap()
-                .to_owned(),
-        ));
+        return match select_platform_commands(commands, os)? {
+            Some(commands) => Ok(get_commands_from_yaml(commands)),
+            None => {
+                info!(
+                    "{} is not defined for this platform...",
+                    White.bold().paint(&method_name)
+                );
+
+                Ok(vec![])
+            }
+        };
     }
 
-    validate_args(Some(&args), rules)?;
+    validate_args(Some(&args), get_command_rules())?;
 
     Ok(get_commands_from_yaml(args))
 }
@@ -78,43 +128,63 @@ fn run_commands(
     shell: &str,
     mode: TaskRunnerMode,
     temp_dir: &str,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    progress: &Progress,
+) -> Result<Option<String>> {
     let parsed_commands = get_commands(commands.clone(), mode)?;
+    if parsed_commands.is_empty() {
+        return Ok(None);
+    }
+
+    let known_shell = Shell::from_str(shell).ok();
     let temp_script = create_script_file(
-        Shell::from_str(shell).unwrap_or(Shell::Bash),
+        known_shell.unwrap_or(Shell::Bash),
         parsed_commands,
         temp_dir,
     )?;
 
-    let command = Command::new(shell)
-        .arg("-c")
-        .arg(&temp_script)
+    // unknown shells are passed the script like bash, e.g. "sh"
+    let (program, args) = match known_shell {
+        Some(known_shell) => (
+            known_shell.get_program().to_string(),
+            known_shell.get_script_args(&temp_script),
+        ),
+        None => (shell.to_string(), Shell::Bash.get_script_args(&temp_script)),
+    };
+
+    let command = Command::new(&program)
+        .args(args)
         .stderr(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn();
 
     if let Err(err_command) = command {
-        return Err(err_command.to_string());
+        return Err(Error::Spawn {
+            program,
+            source: err_command,
+        });
     }
 
     let mut command = command.unwrap();
     let stdout_reader = BufReader::new(command.stdout.take().unwrap());
     let stderr_reader = BufReader::new(command.stderr.take().unwrap());
 
+    let mut output: Vec<String> = vec![];
     let mut errors: Vec<String> = vec![];
 
     thread::scope(|s| {
         s.spawn(|| {
             stdout_reader
                 .lines()
-                .map_while(Result::ok)
-                .for_each(|line| progress.set_message(format!("▶️ {line}")));
+                .map_while(|line| line.ok())
+                .for_each(|line| {
+                    progress.set_message(format!("▶️ {line}"));
+                    output.push(line);
+                });
         });
         s.spawn(|| {
             stderr_reader
                 .lines()
-                .map_while(Result::ok)
+                .map_while(|line| line.ok())
                 .for_each(|line| {
                     let raw_err = strip_line_err_info(&line);
                     if !raw_err.is_empty() {
@@ -130,34 +200,39 @@ fn run_commands(
     let status = command.wait().unwrap();
 
     if !status.success() {
-        return Err(format!("Err: Exited with {}", status.code().unwrap_or(-1)));
+        return Err(Error::ExitStatus {
+            command: program,
+            code: status.code(),
+        });
     }
 
     if !errors.is_empty() {
-        return Err(format!(
+        return Err(Error::Command(format!(
             "Command exited with errors: \n{}",
             errors.join("\n")
-        ));
+        )));
     }
 
-    Ok(())
+    Ok(Some(output.join("\n")))
 }
 
 fn run_task(
     mode: TaskRunnerMode,
     args: ConfigValue,
     config: &CommandConfig,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    progress: &Progress,
+) -> Result<()> {
     let parameters = args.as_hash();
     if parameters.is_none() {
-        return Err(String::from("args is not an object"));
+        return Err(Error::Validation(String::from("args is not an object")));
     }
     let parameters = parameters.unwrap();
 
     let param_commands = parameters.get("commands");
     if param_commands.is_none() {
-        return Err(String::from("\"commands\" key is missing in args"));
+        return Err(Error::Validation(String::from(
+            "\"commands\" key is missing in args",
+        )));
     }
     let param_commands = param_commands.unwrap();
 
@@ -168,15 +243,54 @@ fn run_task(
         .as_str()
         .unwrap();
 
+    let param_register = match parameters.get("register") {
+        Some(ConfigValue::String(name)) if is_variable_name(name) => Some(name),
+        Some(_) => {
+            return Err(Error::Validation(String::from(
+                "\"register\" has to be a variable name",
+            )))
+        }
+        None => None,
+    };
+
+    let param_restart = match parameters.get("restart") {
+        Some(restart) => Some(
+            restart
+                .as_str()
+                .ok_or_else(|| Error::Validation(String::from("\"restart\" has to be a string")))
+                .and_then(|restart| Restart::from_str(restart).map_err(Error::Validation))?,
+        ),
+        None => None,
+    };
+
     set_environment_variables(&args)?;
 
-    run_commands(
+    let output = run_commands(
         param_commands,
         param_shell,
         mode,
         &config.temp_dir,
         progress,
-    )
+    )?;
+
+    // modes without commands don't overwrite the variable, e.g. an update after the install
+    if let Some(output) = output {
+        let output = output.trim();
+        config
+            .variables
+            .set_output(progress.get_task(), "stdout", output);
+
+        if let Some(name) = param_register {
+            config.variables.set(name, output);
+        }
+
+        // only commands that actually ran change the system
+        if let Some(restart) = param_restart {
+            config.restarts.require(progress.get_task(), restart, None);
+        }
+    }
+
+    Ok(())
 }
 
 impl CommandInterface for RunCommand {
@@ -184,8 +298,8 @@ impl CommandInterface for RunCommand {
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+        progress: &Progress,
+    ) -> Result<()> {
         run_task(TaskRunnerMode::Install, args, config, progress)
     }
 
@@ -193,23 +307,19 @@ impl CommandInterface for RunCommand {
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+        progress: &Progress,
+    ) -> Result<()> {
         run_task(TaskRunnerMode::Uninstall, args, config, progress)
     }
 
-    fn update(
-        &self,
-        args: ConfigValue,
-        config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
         run_task(TaskRunnerMode::Update, args, config, progress)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
     use tempfile::tempdir;
 
     use super::*;
@@ -344,6 +454,41 @@ mod test {
         assert_eq!(commands.unwrap(), vec![String::from("command1")]);
     }
 
+    #[test]
+    fn it_gets_install_commands_for_the_platform() {
+        let commands = ConfigValue::Hash(HashMap::from([(
+            "install".to_string(),
+            ConfigValue::Hash(HashMap::from([
+                (
+                    "linux".to_string(),
+                    ConfigValue::String(String::from("sudo apt-get -y install git")),
+                ),
+                (
+                    "macos".to_string(),
+                    ConfigValue::Array(vec![ConfigValue::String(String::from("brew install git"))]),
+                ),
+            ])),
+        )]));
+
+        assert_eq!(
+            get_commands_for_os(commands.clone(), TaskRunnerMode::Install, Some(&Os::Mac)).unwrap(),
+            vec![String::from("brew install git")]
+        );
+        assert_eq!(
+            get_commands_for_os(commands, TaskRunnerMode::Install, Some(&Os::Windows)).unwrap(),
+            vec![] as Vec<String>
+        );
+
+        let invalid = ConfigValue::Hash(HashMap::from([(
+            "install".to_string(),
+            ConfigValue::Hash(HashMap::from([(
+                "linucks".to_string(),
+                ConfigValue::String(String::from("echo")),
+            )])),
+        )]));
+        get_commands_for_os(invalid, TaskRunnerMode::Install, Some(&Os::Linux)).unwrap_err();
+    }
+
     #[test]
     fn it_skips_command_when_method_is_not_defined() {
         let mut commands = HashMap::new();
@@ -360,7 +505,7 @@ mod test {
     #[test]
     fn it_runs_command() {
         let command = "echo success";
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
         let temp_dir = tempdir().unwrap();
 
         let result = run_commands(
@@ -374,10 +519,69 @@ mod test {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn it_returns_the_output_of_the_commands() {
+        let pb = Progress::default();
+        let temp_dir = tempdir().unwrap();
+
+        let output = run_commands(
+            &ConfigValue::Array(vec![
+                ConfigValue::String(String::from("echo nvidia")),
+                ConfigValue::String(String::from("echo")),
+            ]),
+            &Shell::Bash.to_string(),
+            TaskRunnerMode::Install,
+            temp_dir.path().to_str().unwrap(),
+            &pb,
+        );
+
+        assert_eq!(
+            output.unwrap().map(|o| o.trim().to_string()),
+            Some(String::from("nvidia"))
+        );
+
+        let output = run_commands(
+            &ConfigValue::Hash(HashMap::from([(
+                String::from("install"),
+                ConfigValue::String(String::from("echo nvidia")),
+            )])),
+            &Shell::Bash.to_string(),
+            TaskRunnerMode::Update,
+            temp_dir.path().to_str().unwrap(),
+            &pb,
+        );
+
+        assert_eq!(output.unwrap(), None);
+    }
+
+    #[test]
+    fn it_only_registers_variable_names() {
+        let temp_dir = tempdir().unwrap();
+        let config = CommandConfig::for_dir(temp_dir.path());
+
+        for name in ["", "a b", "tasks.x.y"] {
+            let args = ConfigValue::Hash(HashMap::from([
+                (
+                    String::from("commands"),
+                    ConfigValue::String(String::from("echo nvidia")),
+                ),
+                (
+                    String::from("register"),
+                    ConfigValue::String(name.to_string()),
+                ),
+            ]));
+
+            let err =
+                run_task(TaskRunnerMode::Install, args, &config, &Progress::default()).unwrap_err();
+
+            assert!(err.to_string().contains("has to be a variable name"));
+        }
+    }
+
     #[test]
     fn it_exits_with_error_code() {
         let command = "nananana";
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
         let temp_dir = tempdir().unwrap();
 
         let result = run_commands(
@@ -389,14 +593,20 @@ mod test {
         );
 
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), String::from("Err: Exited with 127"))
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::ExitStatus {
+                code: Some(127),
+                ..
+            }
+        ))
     }
 
     #[test]
     fn it_logs_command_errors() {
         let command_fail = "nananana";
         let command_success = "echo end";
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
         let temp_dir = tempdir().unwrap();
 
         let result = run_commands(
@@ -412,7 +622,7 @@ mod test {
 
         assert!(result.is_err());
         assert_eq!(
-            result.unwrap_err(),
+            result.unwrap_err().to_string(),
             format!("Command exited with errors: \n{command_fail}: command not found")
         )
     }
@@ -0,0 +1,331 @@
+use ansi_term::Color::White;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope};
+use std::{
+    collections::HashMap,
+    env::consts::{ARCH, OS},
+    fs,
+    process::{Command, Output},
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+use crate::{
+    command::{CommandConfig, CommandInterface},
+    config::{
+        config_value::ConfigValue,
+        validation_rules::is_string::IsString,
+        validator::{validate_named_args, ValidationRule},
+    },
+    error::{Error, Result},
+    events::Progress,
+    task_runner::TaskRunnerMode,
+    utils::directory::{expand_path, get_relative_dir},
+};
+
+use super::wait_for::get_duration;
+
+static DEFAULT_TIMEOUT_SECS: f32 = 300.0;
+static MAX_CALL_LEVELS: usize = 64;
+
+pub struct ScriptCommand {}
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+fn to_dynamic(value: &ConfigValue) -> Dynamic {
+    match value {
+        ConfigValue::Float(f) => Dynamic::from_float(*f as f64),
+        ConfigValue::Integer(i) => Dynamic::from_int(*i as i64),
+        ConfigValue::String(s) => Dynamic::from(s.to_string()),
+        ConfigValue::Boolean(b) => Dynamic::from_bool(*b),
+        ConfigValue::Array(a) => Dynamic::from_array(a.iter().map(to_dynamic).collect::<Array>()),
+        ConfigValue::Hash(h) => Dynamic::from_map(
+            h.iter()
+                .map(|(key, value)| (key.as_str().into(), to_dynamic(value)))
+                .collect::<Map>(),
+        ),
+        ConfigValue::Null | ConfigValue::Invalid => Dynamic::UNIT,
+    }
+}
+
+fn get_code(args: &ConfigValue, config: &CommandConfig) -> Result<String> {
+    let arg_values = args.as_hash().unwrap();
+
+    if let Some(code) = arg_values.get("code").and_then(|code| code.as_str()) {
+        return Ok(code.to_string());
+    }
+
+    match arg_values.get("file").and_then(|file| file.as_str()) {
+        Some(file) => {
+            let path = expand_path(&get_relative_dir(&config.config_dir, file), false)?;
+            fs::read_to_string(&path).map_err(|e| Error::io(&path, e))
+        }
+        None => Err(Error::Validation(String::from(
+            "either \"code\" or \"file\" is required",
+        ))),
+    }
+}
+
+fn resolve_path(config: &CommandConfig, path: &str) -> ScriptResult<String> {
+    expand_path(&get_relative_dir(&config.config_dir, path), false)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string().into())
+}
+
+fn run_shell(config: &CommandConfig, command: &str) -> ScriptResult<Output> {
+    let shell = config.default_shell.get_program();
+
+    Command::new(shell)
+        .args(config.default_shell.get_command_args(command))
+        .current_dir(&config.config_dir)
+        .output()
+        .map_err(|e| format!("Failed to spawn {shell}: {e}").into())
+}
+
+/**
+ * The host API is kept small on purpose, anything bigger should be a plugin.
+ * Scripts are stopped once the timeout is reached, so an endless loop can't hang the run.
+ */
+fn create_engine(config: &CommandConfig, progress: &Progress, timeout: Duration) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        (started.elapsed() >= timeout).then(|| Dynamic::from(timeout.as_secs_f32()))
+    });
+
+    let pb = progress.clone();
+    engine.on_print(move |message| pb.set_message(format!("▶️ {message}")));
+    engine.on_debug(|message, _, _| debug!("{message}"));
+
+    let c = config.clone();
+    engine.register_fn("read_file", move |path: &str| -> ScriptResult<String> {
+        let path = resolve_path(&c, path)?;
+        fs::read_to_string(&path).map_err(|e| format!("{path}: {e}").into())
+    });
+
+    let c = config.clone();
+    engine.register_fn(
+        "write_file",
+        move |path: &str, content: &str| -> ScriptResult<()> {
+            let path = resolve_path(&c, path)?;
+            fs::write(&path, content).map_err(|e| format!("{path}: {e}").into())
+        },
+    );
+
+    let c = config.clone();
+    engine.register_fn("file_exists", move |path: &str| -> ScriptResult<bool> {
+        Ok(ergo_fs::Path::new(&resolve_path(&c, path)?).exists())
+    });
+
+    let c = config.clone();
+    engine.register_fn("run", move |command: &str| -> ScriptResult<i64> {
+        let output = run_shell(&c, command)?;
+        Ok(output.status.code().unwrap_or(-1) as i64)
+    });
+
+    let c = config.clone();
+    engine.register_fn("output", move |command: &str| -> ScriptResult<String> {
+        let output = run_shell(&c, command)?;
+        if !output.status.success() {
+            return Err(format!(
+                "{command} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    });
+
+    engine.register_fn("env", |name: &str| -> String {
+        std::env::var(name).unwrap_or_default()
+    });
+
+    engine
+}
+
+fn get_scope(mode: TaskRunnerMode, args: &ConfigValue, config: &CommandConfig) -> Scope<'static> {
+    let mut scope = Scope::new();
+
+    scope.push_constant("mode", mode.to_string());
+    scope.push_constant("os", OS.to_string());
+    scope.push_constant("arch", ARCH.to_string());
+    scope.push_constant(
+        "config_dir",
+        config.config_dir.to_string_lossy().to_string(),
+    );
+    scope.push_constant_dynamic(
+        "vars",
+        args.as_hash()
+            .and_then(|values| values.get("vars"))
+            .map(to_dynamic)
+            .unwrap_or_else(|| Dynamic::from_map(Map::new())),
+    );
+
+    scope
+}
+
+fn run_script(
+    mode: TaskRunnerMode,
+    args: ConfigValue,
+    config: &CommandConfig,
+    progress: &Progress,
+) -> Result<()> {
+    let code_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsString {})];
+    let file_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsString {})];
+
+    validate_named_args(
+        args.to_owned(),
+        HashMap::from([
+            (String::from("code"), code_rules),
+            (String::from("file"), file_rules),
+        ]),
+    )?;
+
+    let code = get_code(&args, config)?;
+    let timeout = get_duration(&args, "timeout", DEFAULT_TIMEOUT_SECS)?;
+
+    progress.set_message(format!("📜 {}", White.bold().paint("script")));
+
+    let engine = create_engine(config, progress, timeout);
+    let mut scope = get_scope(mode, &args, config);
+
+    engine
+        .run_with_scope(&mut scope, &code)
+        .map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => Error::Command(format!(
+                "script: timed out after {}s",
+                timeout.as_secs_f32()
+            )),
+            e => Error::Command(format!("script: {e}")),
+        })
+}
+
+impl CommandInterface for ScriptCommand {
+    fn install(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        run_script(TaskRunnerMode::Install, args, config, progress)
+    }
+
+    fn uninstall(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        run_script(TaskRunnerMode::Uninstall, args, config, progress)
+    }
+
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        run_script(TaskRunnerMode::Update, args, config, progress)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_runs_scripts_with_the_host_api() {
+        let dir = tempdir().unwrap();
+
+        let args = ConfigValue::Hash(HashMap::from([
+            (
+                String::from("code"),
+                ConfigValue::String(String::from(
+                    r#"
+                    if !file_exists("greeting.txt") {
+                        write_file("greeting.txt", `hello ${vars.name} (${mode})`);
+                    }
+                    "#,
+                )),
+            ),
+            (
+                String::from("vars"),
+                ConfigValue::Hash(HashMap::from([(
+                    String::from("name"),
+                    ConfigValue::String(String::from("world")),
+                )])),
+            ),
+        ]));
+
+        ScriptCommand {}
+            .install(
+                args,
+                &CommandConfig::for_dir(dir.path()),
+                &Progress::default(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("greeting.txt")).unwrap(),
+            "hello world (install)"
+        );
+    }
+
+    #[test]
+    fn it_fails_when_the_script_throws() {
+        let dir = tempdir().unwrap();
+
+        let args = ConfigValue::Hash(HashMap::from([(
+            String::from("code"),
+            ConfigValue::String(String::from(r#"throw "broken";"#)),
+        )]));
+
+        let err = ScriptCommand {}
+            .update(
+                args,
+                &CommandConfig::for_dir(dir.path()),
+                &Progress::default(),
+            )
+            .unwrap_err();
+
+        assert!(err.to_string().contains("broken"));
+    }
+
+    #[test]
+    fn it_stops_endless_scripts() {
+        let dir = tempdir().unwrap();
+
+        let args = ConfigValue::Hash(HashMap::from([
+            (
+                String::from("code"),
+                ConfigValue::String(String::from("loop {}")),
+            ),
+            (String::from("timeout"), ConfigValue::Float(0.1)),
+        ]));
+
+        let err = ScriptCommand {}
+            .install(
+                args,
+                &CommandConfig::for_dir(dir.path()),
+                &Progress::default(),
+            )
+            .unwrap_err();
+
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[test]
+    fn it_requires_code_or_file() {
+        let dir = tempdir().unwrap();
+
+        let err = ScriptCommand {}
+            .install(
+                ConfigValue::Hash(HashMap::new()),
+                &CommandConfig::for_dir(dir.path()),
+                &Progress::default(),
+            )
+            .unwrap_err();
+
+        assert!(err.to_string().contains("\"code\" or \"file\""));
+    }
+}
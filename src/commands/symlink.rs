@@ -1,30 +1,155 @@
-use ansi_term::Color::{Green, Red, White, Yellow};
-use ergo_fs::{Path, PathBuf};
-use indicatif::ProgressBar;
-use std::{collections::HashSet, fs::remove_file};
-use symlink::{remove_symlink_file, symlink_file};
+use ansi_term::Color::{Green, White, Yellow};
+use ergo_fs::{Path, PathBuf, PathDir};
+use std::{
+    collections::HashMap,
+    fs::{canonicalize, copy, create_dir_all, read_link, remove_dir_all, remove_file, rename},
+    io::ErrorKind,
+};
+use symlink::{remove_symlink_dir, remove_symlink_file, symlink_dir, symlink_file};
 use tracing::debug;
 
 use crate::{
     command::{CommandConfig, CommandInterface},
-    config::config_value::ConfigValue,
-    utils::directory::{expand_path, get_source_and_target, walk_files},
+    config::{
+        config_value::ConfigValue,
+        validation_rules::is_bool::IsBool,
+        validator::{validate_named_args, ValidationRule},
+    },
+    error::{Error, Result},
+    events::Progress,
+    utils::{
+        directory::{
+            expand_file_target, expand_path, get_bool_arg, get_file_mappings, get_relative_dir,
+            get_relative_path, get_source_and_target, get_walk_options, join_errors, walk_files,
+            FileMapping, WalkOptions, DIR_SRC, DIR_TARGET,
+        },
+        hard_link::{create_hard_link, is_same_file},
+    },
 };
 
 pub struct SymlinkCommand {}
 
-fn should_force(args: ConfigValue) -> bool {
-    if !args.is_hash() {
-        return false;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SymlinkOptions {
+    pub force: bool,
+    pub link_dir: bool,
+    pub hard: bool,
+    pub relative: bool,
+    pub adopt: bool,
+}
+
+fn get_options(args: &ConfigValue) -> Result<SymlinkOptions> {
+    let force_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let link_dir_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let hard_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let relative_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let adopt_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+
+    validate_named_args(
+        args.to_owned(),
+        HashMap::from([
+            (String::from("force"), force_rules),
+            (String::from("link_dir"), link_dir_rules),
+            (String::from("hard"), hard_rules),
+            (String::from("relative"), relative_rules),
+            (String::from("adopt"), adopt_rules),
+        ]),
+    )?;
+
+    Ok(SymlinkOptions {
+        force: get_bool_arg(args, "force"),
+        link_dir: get_bool_arg(args, "link_dir"),
+        hard: get_bool_arg(args, "hard"),
+        relative: get_bool_arg(args, "relative"),
+        adopt: get_bool_arg(args, "adopt"),
+    })
+}
+
+static PACKAGES: &str = "packages";
+static DEFAULT_PACKAGE_DIR: &str = "packages";
+static DEFAULT_PACKAGE_TARGET: &str = "~";
+
+pub struct Packages {
+    pub dirs: Vec<String>,
+    pub target: String,
+    pub walk_options: WalkOptions,
+}
+
+/**
+ * GNU stow compatible layout: every package is a directory inside of `src` (default: "packages"),
+ * which mirrors the structure of `target` (default: home directory), e.g.:
+ *   packages/git/.gitconfig -> ~/.gitconfig
+ *   packages/nvim/.config/nvim/init.lua -> ~/.config/nvim/init.lua
+ */
+fn get_packages(args: &ConfigValue, root: &PathDir) -> Result<Option<Packages>> {
+    let arg_values = match args.as_hash() {
+        Some(arg_values) => arg_values,
+        None => return Ok(None),
+    };
+
+    let packages = match arg_values.get(PACKAGES) {
+        Some(packages) => packages,
+        None => return Ok(None),
+    };
+
+    let packages = packages.as_vec().ok_or_else(|| {
+        Error::Validation(format!(
+            "{PACKAGES}: argument must be a list of package names"
+        ))
+    })?;
+
+    let package_dir = arg_values
+        .get(DIR_SRC)
+        .and_then(|src| src.as_str())
+        .unwrap_or(DEFAULT_PACKAGE_DIR);
+    let package_dir = get_relative_dir(root, package_dir);
+
+    let target = arg_values
+        .get(DIR_TARGET)
+        .and_then(|target| target.as_str())
+        .unwrap_or(DEFAULT_PACKAGE_TARGET);
+
+    let mut dirs = vec![];
+    for package in packages {
+        let package = package
+            .as_str()
+            .filter(|package| !package.is_empty())
+            .ok_or_else(|| {
+                Error::Validation(format!("{PACKAGES}: package names must be strings"))
+            })?;
+
+        dirs.push(
+            Path::new(&package_dir)
+                .join(package)
+                .to_string_lossy()
+                .to_string(),
+        );
     }
 
-    let arg_values = args.as_hash().unwrap();
+    Ok(Some(Packages {
+        dirs,
+        target: get_relative_dir(root, target),
+        walk_options: get_walk_options(args)?,
+    }))
+}
 
-    if let Some(force) = arg_values.get("force") {
-        return force.as_bool().unwrap_or(false);
+/**
+ * Relative links are resolved from the directory that contains the link.
+ * Both paths are canonicalized first so that ".." components can't mess up the result.
+ */
+fn get_link_source(src: &Path, target: &Path, relative: bool) -> Result<PathBuf> {
+    if !relative {
+        return Ok(src.to_path_buf());
     }
 
-    false
+    let target_parent = target
+        .parent()
+        .ok_or_else(|| Error::Validation(format!("Invalid target path: {}", target.display())))?;
+
+    let abs_src = canonicalize(src).map_err(|e| Error::io(src, e))?;
+    let abs_target_parent = canonicalize(target_parent).map_err(|e| Error::io(target_parent, e))?;
+
+    Ok(get_relative_path(&abs_target_parent, &abs_src))
 }
 
 impl CommandInterface for SymlinkCommand {
@@ -32,15 +157,26 @@ impl CommandInterface for SymlinkCommand {
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+        progress: &Progress,
+    ) -> Result<()> {
+        let options = get_options(&args)?;
+
+        if let Some(mappings) = get_file_mappings(&args, &config.config_dir)? {
+            let walk_options = get_walk_options(&args)?;
+            return link_file_mappings(&mappings, &walk_options, options, progress);
+        }
+
+        if let Some(packages) = get_packages(&args, &config.config_dir)? {
+            return link_packages(&packages, options, progress);
+        }
+
         let dirs = get_source_and_target(args.clone(), &config.config_dir)?;
 
         create_symlink(
             &dirs.src,
             &dirs.target,
-            dirs.ignore,
-            should_force(args),
+            &dirs.walk_options,
+            options,
             progress,
         )
     }
@@ -49,32 +185,43 @@ impl CommandInterface for SymlinkCommand {
         &self,
         args: ConfigValue,
         config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
-        let dirs = get_source_and_target(args, &config.config_dir)?;
+        progress: &Progress,
+    ) -> Result<()> {
+        let options = get_options(&args)?;
+
+        if let Some(mappings) = get_file_mappings(&args, &config.config_dir)? {
+            let walk_options = get_walk_options(&args)?;
+            return unlink_file_mappings(&mappings, &walk_options, options, progress);
+        }
+
+        if let Some(packages) = get_packages(&args, &config.config_dir)? {
+            return unlink_packages(&packages, options, progress);
+        }
+
+        let dirs = get_source_and_target(args.clone(), &config.config_dir)?;
 
-        remove_symlink(&dirs.src, &dirs.target, progress)
+        remove_symlink(
+            &dirs.src,
+            &dirs.target,
+            &dirs.walk_options,
+            options,
+            progress,
+        )
     }
 
-    fn update(
-        &self,
-        args: ConfigValue,
-        config: &CommandConfig,
-        progress: &ProgressBar,
-    ) -> Result<(), String> {
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
         self.install(args, config, progress)
     }
 }
 
-fn link_files(
-    source_dir: &PathBuf,
+fn link_dir(
+    source_dir: &Path,
     destination_dir: &Path,
-    ignore: HashSet<String>,
-    force: bool,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
     let message = format!(
-        "Creating symlinks: {} {} {} ...",
+        "Linking directory: {} {} {} ...",
         White.bold().paint(source_dir.display().to_string()),
         Green.bold().paint("->"),
         White.bold().paint(destination_dir.display().to_string())
@@ -83,33 +230,226 @@ fn link_files(
     debug!(message);
     progress.set_message(message);
 
-    walk_files(source_dir, destination_dir, ignore, |src, target| {
+    if let Some(parent) = destination_dir.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+
+    let link_source = get_link_source(source_dir, destination_dir, options.relative)?;
+
+    if destination_dir.is_symlink() {
+        if read_link(destination_dir).is_ok_and(|linked| linked == link_source) {
+            debug!("{}", Yellow.paint("Directory is already linked ..."));
+            return Ok(());
+        }
+
+        if !options.force {
+            return Err(Error::Command(format!(
+                "Target is already a symlink: {}",
+                destination_dir.display()
+            )));
+        }
+
+        remove_symlink_dir(destination_dir)
+            .or_else(|_| remove_file(destination_dir))
+            .map_err(|e| Error::io(destination_dir, e))?;
+    } else if destination_dir.exists() {
+        if !options.force {
+            return Err(Error::Command(format!(
+                "Target directory already exists: {}",
+                destination_dir.display()
+            )));
+        }
+
         debug!(
-            "Linking {} to {} ...",
-            White.bold().paint(src.to_str().unwrap()),
-            White.bold().paint(target.to_str().unwrap())
+            "{}",
+            Yellow.paint("Replacing exisiting directory with symlink (force) ...")
+        );
+
+        let remove_result = if destination_dir.is_dir() {
+            remove_dir_all(destination_dir)
+        } else {
+            remove_file(destination_dir)
+        };
+        remove_result.map_err(|e| Error::io(destination_dir, e))?;
+    }
+
+    symlink_dir(link_source, destination_dir).map_err(|e| Error::io(destination_dir, e))
+}
+
+fn unlink_dir(destination_dir: &Path, progress: &Progress) -> Result<()> {
+    let message = format!(
+        "Unlinking directory {} ...",
+        White.bold().paint(destination_dir.display().to_string())
+    );
+
+    debug!(message);
+    progress.set_message(message);
+
+    if !destination_dir.is_symlink() && !destination_dir.exists() {
+        debug!(
+            "{} {} ...",
+            Yellow.paint("Not linked, skipping"),
+            White.bold().paint(destination_dir.display().to_string())
         );
+        return Ok(());
+    }
+
+    if !destination_dir.is_symlink() {
+        return Err(Error::Command(format!(
+            "Target is not a symlink: {}",
+            destination_dir.display()
+        )));
+    }
+
+    remove_symlink_dir(destination_dir)
+        .or_else(|_| remove_file(destination_dir))
+        .map_err(|e| Error::io(destination_dir, e))
+}
+
+/**
+ * Moves an existing target into the source location, so that it can be linked back afterwards.
+ * This is used to take over configs that already exist on a machine (like `stow --adopt`).
+ */
+fn adopt(src: &Path, target: &Path) -> Result<()> {
+    if target.is_symlink() || !target.exists() {
+        return Err(Error::Command(format!(
+            "Source does not exist and there is nothing to adopt: {}",
+            src.display()
+        )));
+    }
 
-        if force && target.is_file() {
-            debug!(
-                "{}",
-                Yellow.paint("Replacing exisiting file with symlink (force) ...")
-            );
+    debug!(
+        "Adopting {} into {} ...",
+        White.bold().paint(target.display().to_string()),
+        White.bold().paint(src.display().to_string())
+    );
+
+    if let Some(parent) = src.parent() {
+        create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
 
-            remove_file(target).ok();
+    let move_result = match rename(target, src) {
+        // rename doesn't work across filesystems, files can still be copied over
+        Err(e) if e.kind() == ErrorKind::CrossesDevices && target.is_file() => {
+            copy(target, src).and_then(|_| remove_file(target))
         }
+        result => result,
+    };
 
-        symlink_file(src, target)
-            .map_err(|e| format!("Failed to link file: {}", Red.paint(e.to_string())))
-            .ok();
-    })
+    move_result.map_err(|e| Error::io(target, e))
+}
+
+fn link_file(src: &Path, target: &Path, options: SymlinkOptions) -> Result<()> {
+    debug!(
+        "Linking {} to {} ...",
+        White.bold().paint(src.to_str().unwrap()),
+        White.bold().paint(target.to_str().unwrap())
+    );
+
+    // like `stow --adopt`, a regular file at the target replaces the tracked file before linking it back
+    if options.adopt
+        && target
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_file())
+        && !is_same_file(src, target)
+    {
+        adopt(src, target)?;
+    }
+
+    if options.hard {
+        return create_hard_link(src, target, options.force);
+    }
+
+    let link_source = get_link_source(src, target, options.relative)?;
+
+    if read_link(target).is_ok_and(|linked| linked == link_source) {
+        debug!("{}", Yellow.paint("File is already linked ..."));
+        return Ok(());
+    }
+
+    if options.force && (target.is_file() || target.is_symlink()) {
+        debug!(
+            "{}",
+            Yellow.paint("Replacing exisiting file with symlink (force) ...")
+        );
+
+        match remove_file(target) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(Error::io(target, e)),
+            _ => {}
+        }
+    }
+
+    symlink_file(link_source, target).map_err(|e| Error::io(target, e))
+}
+
+fn unlink_file(src: &Path, target: &Path, options: SymlinkOptions) -> Result<()> {
+    let is_linked = if options.hard {
+        is_same_file(src, target)
+    } else {
+        target.is_symlink()
+    };
+
+    if !is_linked {
+        debug!(
+            "{} {} ...",
+            Yellow.paint("Not linked, skipping"),
+            White.bold().paint(target.to_str().unwrap())
+        );
+        return Ok(());
+    }
+
+    debug!(
+        "Unlinking {} ...",
+        White.bold().paint(target.to_str().unwrap())
+    );
+
+    let unlink_result = if options.hard {
+        remove_file(target)
+    } else {
+        remove_symlink_file(target)
+    };
+
+    unlink_result.map_err(|e| Error::io(target, e))
+}
+
+fn link_files(
+    source_dir: &PathBuf,
+    destination_dir: &Path,
+    walk_options: &WalkOptions,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
+    let message = format!(
+        "Creating {}: {} {} {} ...",
+        if options.hard {
+            "hard links"
+        } else {
+            "symlinks"
+        },
+        White.bold().paint(source_dir.display().to_string()),
+        Green.bold().paint("->"),
+        White.bold().paint(destination_dir.display().to_string())
+    );
+
+    debug!(message);
+    progress.set_message(message);
+
+    walk_files(
+        source_dir,
+        destination_dir,
+        walk_options,
+        progress,
+        |src, target| link_file(src, target, options),
+    )
 }
 
 fn unlink_files(
     source_dir: &PathBuf,
     destination_dir: &Path,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    walk_options: &WalkOptions,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
     let message = format!(
         "Unlinking files in {} ...",
         White.bold().paint(destination_dir.display().to_string())
@@ -121,52 +461,205 @@ fn unlink_files(
     walk_files(
         source_dir,
         destination_dir,
-        HashSet::new(),
-        |_src, target| {
-            debug!(
-                "Unlinking {} ...",
-                White.bold().paint(target.to_str().unwrap())
-            );
-            remove_symlink_file(target)
-                .map_err(|e| format!("Failed to unlink file: {}", Red.paint(e.to_string())))
-                .ok();
-        },
+        walk_options,
+        progress,
+        |src, target| unlink_file(src, target, options),
     )
 }
 
+fn link_file_mappings(
+    mappings: &[FileMapping],
+    walk_options: &WalkOptions,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
+    let mut errors: Vec<Error> = vec![];
+
+    for mapping in mappings {
+        let result = match expand_path(&mapping.src, false)? {
+            src if src.is_dir() => create_symlink(
+                &mapping.src,
+                &mapping.target,
+                walk_options,
+                options,
+                progress,
+            ),
+            src if src.exists() => expand_file_target(&mapping.target)
+                .and_then(|target| link_file(&src, &target, options)),
+            src if options.adopt => expand_path(&mapping.target, false).and_then(|target| {
+                adopt(&src, &target)?;
+                link_file(&src, &target, options)
+            }),
+            _ => Err(Error::Command(format!(
+                "Source file does not exist: {}",
+                mapping.src
+            ))),
+        };
+
+        if let Err(err) = result {
+            errors.push(err);
+        }
+    }
+
+    join_errors(errors)
+}
+
+fn link_packages(packages: &Packages, options: SymlinkOptions, progress: &Progress) -> Result<()> {
+    let mut errors: Vec<Error> = vec![];
+
+    for package_dir in &packages.dirs {
+        // adopting a missing package would move the whole target directory into it
+        if !expand_path(package_dir, false)?.is_dir() {
+            errors.push(Error::Command(format!(
+                "Package does not exist: {package_dir}"
+            )));
+            continue;
+        }
+
+        if let Err(err) = create_symlink(
+            package_dir,
+            &packages.target,
+            &packages.walk_options,
+            options,
+            progress,
+        ) {
+            errors.push(err);
+        }
+    }
+
+    join_errors(errors)
+}
+
+fn unlink_packages(
+    packages: &Packages,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
+    let mut errors: Vec<Error> = vec![];
+
+    for package_dir in &packages.dirs {
+        if let Err(err) = remove_symlink(
+            package_dir,
+            &packages.target,
+            &packages.walk_options,
+            options,
+            progress,
+        ) {
+            errors.push(err);
+        }
+    }
+
+    join_errors(errors)
+}
+
+fn unlink_file_mappings(
+    mappings: &[FileMapping],
+    walk_options: &WalkOptions,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
+    let mut errors: Vec<Error> = vec![];
+
+    for mapping in mappings {
+        let src = expand_path(&mapping.src, false)?;
+
+        let result = if src.is_dir() {
+            remove_symlink(
+                &mapping.src,
+                &mapping.target,
+                walk_options,
+                options,
+                progress,
+            )
+        } else {
+            expand_path(&mapping.target, false)
+                .and_then(|target| unlink_file(&src, &target, options))
+        };
+
+        if let Err(err) = result {
+            errors.push(err);
+        }
+    }
+
+    join_errors(errors)
+}
+
 pub fn create_symlink(
     source: &str,
     destination: &str,
-    ignore: HashSet<String>,
-    force: bool,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    walk_options: &WalkOptions,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
     let source_dir = expand_path(source, false)?;
 
     if !source_dir.exists() {
-        return Err(format!("Source directory does not exist: {source}"));
+        if !options.adopt {
+            return Err(Error::Command(format!(
+                "Source directory does not exist: {source}"
+            )));
+        }
+
+        let destination_path = expand_path(destination, false)?;
+
+        // a whole directory like `~/.config` would pull unrelated configs and secrets into the source
+        if destination_path.is_dir() && !destination_path.is_symlink() && !options.link_dir {
+            return Err(Error::Command(format!(
+                "Refusing to adopt the directory {}, set link_dir to adopt it as a whole",
+                destination_path.display()
+            )));
+        }
+
+        adopt(&source_dir, &destination_path)?;
+
+        if source_dir.is_file() {
+            return link_file(&source_dir, &destination_path, options);
+        }
     }
 
-    let destination_dir = expand_path(destination, true)?;
+    let link_whole_dir = options.link_dir && !options.hard && source_dir.is_dir();
+    let destination_dir = expand_path(destination, !link_whole_dir)?;
 
     if source_dir == destination_dir {
-        return Err(format!(
+        return Err(Error::Command(format!(
             "Source and destination directories are the same: {source}"
-        ));
+        )));
     }
 
-    link_files(&source_dir, &destination_dir, ignore, force, progress)
+    if link_whole_dir {
+        return link_dir(&source_dir, &destination_dir, options, progress);
+    }
+
+    link_files(
+        &source_dir,
+        &destination_dir,
+        walk_options,
+        options,
+        progress,
+    )
 }
 
 pub fn remove_symlink(
     source: &str,
     destination: &str,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    walk_options: &WalkOptions,
+    options: SymlinkOptions,
+    progress: &Progress,
+) -> Result<()> {
     let source_dir = expand_path(source, false)?;
     let destination_dir = expand_path(destination, false)?;
 
-    unlink_files(&source_dir, &destination_dir, progress)
+    if options.link_dir && !options.hard && source_dir.is_dir() {
+        return unlink_dir(&destination_dir, progress);
+    }
+
+    unlink_files(
+        &source_dir,
+        &destination_dir,
+        walk_options,
+        options,
+        progress,
+    )
 }
 
 #[cfg(test)]
@@ -183,11 +676,18 @@ mod test {
 
         let src = src_path.to_str().unwrap();
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
-        assert!(create_symlink(src, src, HashSet::new(), false, &pb)
-            .unwrap_err()
-            .contains("Source and destination directories are the same"));
+        assert!(create_symlink(
+            src,
+            src,
+            &WalkOptions::default(),
+            SymlinkOptions::default(),
+            &pb
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("Source and destination directories are the same"));
     }
 
     #[test]
@@ -200,9 +700,16 @@ mod test {
         let dest_dir = tempdir().unwrap();
         let dest = dest_dir.path().to_str().unwrap();
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
-        create_symlink(src, dest, HashSet::new(), false, &pb).unwrap();
+        create_symlink(
+            src,
+            dest,
+            &WalkOptions::default(),
+            SymlinkOptions::default(),
+            &pb,
+        )
+        .unwrap();
 
         let dest_path = dest_dir.path().join("example.txt");
         assert!(dest_path.is_symlink())
@@ -221,9 +728,19 @@ mod test {
 
         File::create(&dest_path).unwrap();
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
-        create_symlink(src, dest, HashSet::new(), true, &pb).unwrap();
+        create_symlink(
+            src,
+            dest,
+            &WalkOptions::default(),
+            SymlinkOptions {
+                force: true,
+                ..Default::default()
+            },
+            &pb,
+        )
+        .unwrap();
 
         assert!(dest_path.is_symlink());
     }
@@ -238,15 +755,322 @@ mod test {
         let dest_dir = tempdir().unwrap();
         let dest = dest_dir.path().to_str().unwrap();
 
-        let pb = ProgressBar::new(0);
+        let pb = Progress::default();
 
-        create_symlink(src, dest, HashSet::new(), false, &pb).unwrap();
+        create_symlink(
+            src,
+            dest,
+            &WalkOptions::default(),
+            SymlinkOptions::default(),
+            &pb,
+        )
+        .unwrap();
 
         let dest_path = dest_dir.path().join("example.txt");
         assert!(dest_path.exists());
 
-        remove_symlink(src, dest, &pb).unwrap();
+        remove_symlink(
+            src,
+            dest,
+            &WalkOptions::default(),
+            SymlinkOptions::default(),
+            &pb,
+        )
+        .unwrap();
 
         assert!(!dest_path.exists());
     }
+
+    #[test]
+    fn it_links_whole_directory() {
+        let src_dir = tempdir().unwrap();
+        let src = src_dir.path().to_str().unwrap();
+        File::create(src_dir.path().join("example.txt")).unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join("linked");
+        let dest = dest_path.to_str().unwrap();
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            link_dir: true,
+            ..Default::default()
+        };
+
+        create_symlink(src, dest, &WalkOptions::default(), options, &pb).unwrap();
+
+        assert!(dest_path.is_symlink());
+        assert!(dest_path.join("example.txt").exists());
+
+        File::create(src_dir.path().join("new.txt")).unwrap();
+        assert!(dest_path.join("new.txt").exists());
+
+        remove_symlink(src, dest, &WalkOptions::default(), options, &pb).unwrap();
+
+        assert!(!dest_path.exists());
+        assert!(src_dir.path().join("example.txt").exists());
+
+        // removing it again is a no-op
+        remove_symlink(src, dest, &WalkOptions::default(), options, &pb).unwrap();
+    }
+
+    #[test]
+    fn it_creates_hard_links() {
+        let src_dir = tempdir().unwrap();
+        let src = src_dir.path().to_str().unwrap();
+        File::create(src_dir.path().join("example.txt")).unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().to_str().unwrap();
+        let dest_path = dest_dir.path().join("example.txt");
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            hard: true,
+            ..Default::default()
+        };
+
+        create_symlink(src, dest, &WalkOptions::default(), options, &pb).unwrap();
+
+        assert!(dest_path.exists());
+        assert!(!dest_path.is_symlink());
+        assert!(is_same_file(
+            &src_dir.path().join("example.txt"),
+            &dest_path
+        ));
+
+        remove_symlink(src, dest, &WalkOptions::default(), options, &pb).unwrap();
+
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn it_creates_relative_symlinks() {
+        let root_dir = tempdir().unwrap();
+        let src_path = root_dir.path().join("dotfiles");
+        std::fs::create_dir(&src_path).unwrap();
+        File::create(src_path.join("example.txt")).unwrap();
+
+        let dest_path = root_dir.path().join("home");
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            relative: true,
+            ..Default::default()
+        };
+
+        create_symlink(
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap(),
+            &WalkOptions::default(),
+            options,
+            &pb,
+        )
+        .unwrap();
+
+        let link = dest_path.join("example.txt");
+        assert_eq!(
+            read_link(&link).unwrap(),
+            PathBuf::from("../dotfiles/example.txt")
+        );
+        assert!(link.exists());
+    }
+
+    #[test]
+    fn it_links_files_to_different_names() {
+        let src_dir = tempdir().unwrap();
+        File::create(src_dir.path().join("gitconfig-work")).unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join(".gitconfig");
+
+        let mappings = vec![FileMapping {
+            src: src_dir
+                .path()
+                .join("gitconfig-work")
+                .to_string_lossy()
+                .to_string(),
+            target: dest_path.to_string_lossy().to_string(),
+        }];
+
+        let pb = Progress::default();
+
+        link_file_mappings(
+            &mappings,
+            &WalkOptions::default(),
+            SymlinkOptions::default(),
+            &pb,
+        )
+        .unwrap();
+
+        assert!(dest_path.is_symlink());
+        assert!(dest_path.is_file());
+
+        unlink_file_mappings(
+            &mappings,
+            &WalkOptions::default(),
+            SymlinkOptions::default(),
+            &pb,
+        )
+        .unwrap();
+
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn it_adopts_existing_files() {
+        let src_dir = tempdir().unwrap();
+        let src_path = src_dir.path().join("git").join("gitconfig");
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join(".gitconfig");
+        std::fs::write(&dest_path, "[user]").unwrap();
+
+        let mappings = vec![FileMapping {
+            src: src_path.to_string_lossy().to_string(),
+            target: dest_path.to_string_lossy().to_string(),
+        }];
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            adopt: true,
+            ..Default::default()
+        };
+
+        link_file_mappings(&mappings, &WalkOptions::default(), options, &pb).unwrap();
+
+        assert!(dest_path.is_symlink());
+        assert_eq!(std::fs::read_to_string(&src_path).unwrap(), "[user]");
+        assert_eq!(std::fs::read_to_string(&dest_path).unwrap(), "[user]");
+    }
+
+    #[test]
+    fn it_fails_to_adopt_missing_targets() {
+        let src_dir = tempdir().unwrap();
+        let src_path = src_dir.path().join("missing");
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join("missing");
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            adopt: true,
+            ..Default::default()
+        };
+
+        assert!(create_symlink(
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap(),
+            &WalkOptions::default(),
+            options,
+            &pb
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_refuses_to_adopt_whole_directories() {
+        let src_dir = tempdir().unwrap();
+        let src_path = src_dir.path().join("config");
+
+        let dest_dir = tempdir().unwrap();
+        std::fs::write(dest_dir.path().join("secrets.env"), "TOKEN=").unwrap();
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            adopt: true,
+            ..Default::default()
+        };
+
+        assert!(create_symlink(
+            src_path.to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &WalkOptions::default(),
+            options,
+            &pb
+        )
+        .is_err());
+        assert!(!src_path.exists());
+        assert!(dest_dir.path().join("secrets.env").is_file());
+    }
+
+    #[test]
+    fn it_adopts_existing_files_of_the_source_tree() {
+        let src_dir = tempdir().unwrap();
+        let nvim_path = src_dir.path().join(".config").join("nvim");
+        std::fs::create_dir_all(&nvim_path).unwrap();
+        std::fs::write(nvim_path.join("init.lua"), "-- init").unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_nvim_path = dest_dir.path().join(".config").join("nvim");
+        std::fs::create_dir_all(&dest_nvim_path).unwrap();
+        std::fs::write(dest_nvim_path.join("init.lua"), "-- local").unwrap();
+        std::fs::write(dest_nvim_path.join("lazy.lua"), "-- lazy").unwrap();
+        std::fs::write(dest_dir.path().join(".config").join("gh.yml"), "").unwrap();
+
+        let pb = Progress::default();
+        let options = SymlinkOptions {
+            adopt: true,
+            ..Default::default()
+        };
+
+        create_symlink(
+            src_dir.path().to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &WalkOptions::default(),
+            options,
+            &pb,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(nvim_path.join("init.lua")).unwrap(),
+            "-- local"
+        );
+        assert!(dest_nvim_path.join("init.lua").is_symlink());
+        assert!(!nvim_path.join("lazy.lua").exists());
+        assert!(dest_nvim_path.join("lazy.lua").is_file());
+        assert!(!dest_nvim_path.join("lazy.lua").is_symlink());
+        assert!(!src_dir.path().join(".config").join("gh.yml").exists());
+        assert!(dest_dir.path().join(".config").join("gh.yml").is_file());
+    }
+
+    #[test]
+    fn it_links_stow_packages() {
+        let root_dir = tempdir().unwrap();
+        let root = PathDir::new(root_dir.path()).unwrap();
+        let package_path = root_dir.path().join("packages").join("nvim");
+        std::fs::create_dir_all(package_path.join(".config").join("nvim")).unwrap();
+        File::create(package_path.join(".config").join("nvim").join("init.lua")).unwrap();
+
+        let home_dir = tempdir().unwrap();
+
+        let mut args = HashMap::new();
+        args.insert(
+            String::from("packages"),
+            ConfigValue::Array(vec![ConfigValue::String(String::from("nvim"))]),
+        );
+        args.insert(
+            String::from("target"),
+            ConfigValue::String(home_dir.path().to_string_lossy().to_string()),
+        );
+
+        let packages = get_packages(&ConfigValue::Hash(args), &root)
+            .unwrap()
+            .unwrap();
+
+        let pb = Progress::default();
+        link_packages(&packages, SymlinkOptions::default(), &pb).unwrap();
+
+        let link = home_dir
+            .path()
+            .join(".config")
+            .join("nvim")
+            .join("init.lua");
+        assert!(link.is_symlink());
+
+        unlink_packages(&packages, SymlinkOptions::default(), &pb).unwrap();
+
+        assert!(!link.exists());
+    }
 }
@@ -0,0 +1,355 @@
+use ansi_term::Color::{Green, White};
+use std::{
+    fmt,
+    net::{TcpStream, ToSocketAddrs},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+use crate::{
+    command::{CommandConfig, CommandInterface},
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+    events::Progress,
+    utils::directory::{expand_path, get_relative_dir},
+};
+
+pub struct WaitForCommand {}
+
+static CONDITIONS: [&str; 4] = ["port", "file", "service", "command"];
+static DEFAULT_TIMEOUT_SECS: f32 = 60.0;
+static DEFAULT_INTERVAL_SECS: f32 = 1.0;
+/** independent of the interval, which might be 0 */
+static CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    /** e.g. "localhost:5432" */
+    Port(String),
+    File(String),
+    Service(String),
+    Command(String),
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Port(address) => write!(f, "port {address}"),
+            Condition::File(file) => write!(f, "file {file}"),
+            Condition::Service(service) => write!(f, "service {service}"),
+            Condition::Command(command) => write!(f, "command \"{command}\""),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct WaitForArgs {
+    condition: Condition,
+    timeout: Duration,
+    interval: Duration,
+}
+
+pub(crate) fn get_duration(args: &ConfigValue, name: &str, default: f32) -> Result<Duration> {
+    let seconds = match args.as_hash().and_then(|values| values.get(name)) {
+        Some(ConfigValue::Integer(seconds)) => *seconds as f32,
+        Some(ConfigValue::Float(seconds)) => *seconds,
+        Some(value) => {
+            return Err(Error::Validation(format!(
+                "{name}: {value:?} is not a number of seconds"
+            )))
+        }
+        None => default,
+    };
+
+    if seconds < 0.0 {
+        return Err(Error::Validation(format!("{name}: must not be negative")));
+    }
+
+    Ok(Duration::from_secs_f32(seconds))
+}
+
+fn get_args(args: &ConfigValue) -> Result<WaitForArgs> {
+    let Some(values) = args.as_hash() else {
+        return Err(Error::Validation(String::from(
+            "Expected named arguments, got positional arguments",
+        )));
+    };
+
+    let conditions: Vec<(&str, &ConfigValue)> = CONDITIONS
+        .iter()
+        .filter_map(|name| values.get(*name).map(|value| (*name, value)))
+        .collect();
+
+    let [(name, value)] = conditions[..] else {
+        return Err(Error::Validation(format!(
+            "Expected exactly one of: {}",
+            CONDITIONS.join(", ")
+        )));
+    };
+
+    let Some(value) = value.as_str().map(|value| value.to_string()) else {
+        return Err(Error::Validation(format!(
+            "{name}: {value:?} is not a string"
+        )));
+    };
+
+    let condition = match name {
+        "port" => Condition::Port(value),
+        "file" => Condition::File(value),
+        "service" => Condition::Service(value),
+        _ => Condition::Command(value),
+    };
+
+    Ok(WaitForArgs {
+        condition,
+        timeout: get_duration(args, "timeout", DEFAULT_TIMEOUT_SECS)?,
+        interval: get_duration(args, "interval", DEFAULT_INTERVAL_SECS)?,
+    })
+}
+
+fn is_port_open(address: &str) -> bool {
+    let Ok(addresses) = address.to_socket_addrs() else {
+        return false;
+    };
+
+    addresses
+        .into_iter()
+        .any(|address| TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).is_ok())
+}
+
+/**
+ * Single quotes are escaped by doubling them inside of a single quoted PowerShell string.
+ */
+fn quote_powershell(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+/**
+ * Services are managed by systemd on Linux, launchd on macOS and the service control manager on Windows.
+ */
+fn is_service_active(service: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("launchctl");
+        command.args(["list", service]);
+        command
+    } else if cfg!(target_family = "windows") {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "if ((Get-Service '{}').Status -ne 'Running') {{ exit 1 }}",
+                quote_powershell(service)
+            ),
+        ]);
+        command
+    } else {
+        let mut command = Command::new("systemctl");
+        command.args(["is-active", "--quiet", service]);
+        command
+    };
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+fn is_fulfilled(condition: &Condition, config: &CommandConfig) -> Result<bool> {
+    match condition {
+        Condition::Port(address) => Ok(is_port_open(address)),
+        Condition::File(file) => {
+            Ok(expand_path(&get_relative_dir(&config.config_dir, file), false)?.exists())
+        }
+        Condition::Service(service) => Ok(is_service_active(service)),
+        Condition::Command(command) => config.default_shell.command_succeeds(command),
+    }
+}
+
+/**
+ * The condition is checked at least once, even with a timeout of 0.
+ */
+fn wait_for(args: &ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+    let args = get_args(args)?;
+    let start = Instant::now();
+
+    let message = format!(
+        "Waiting for {} ...",
+        White.bold().paint(args.condition.to_string())
+    );
+    debug!(message);
+    progress.set_message(message);
+
+    loop {
+        if is_fulfilled(&args.condition, config)? {
+            break;
+        }
+
+        if start.elapsed() >= args.timeout {
+            return Err(Error::Command(format!(
+                "Timed out after {:.1}s waiting for {}",
+                args.timeout.as_secs_f32(),
+                args.condition
+            )));
+        }
+
+        thread::sleep(args.interval);
+    }
+
+    progress.set_message(format!(
+        "{} {} after {:.1}s",
+        Green.paint("Found"),
+        White.bold().paint(args.condition.to_string()),
+        start.elapsed().as_secs_f32()
+    ));
+
+    Ok(())
+}
+
+impl CommandInterface for WaitForCommand {
+    fn install(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        wait_for(&args, config, progress)
+    }
+
+    fn uninstall(
+        &self,
+        args: ConfigValue,
+        _config: &CommandConfig,
+        _progress: &Progress,
+    ) -> Result<()> {
+        // nothing depends on the condition while uninstalling
+        get_args(&args)?;
+
+        Ok(())
+    }
+
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        wait_for(&args, config, progress)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, fs::write, net::TcpListener};
+
+    use super::*;
+
+    fn get_wait_args(condition: &str, value: &str, timeout: f32) -> ConfigValue {
+        ConfigValue::Hash(HashMap::from([
+            (
+                condition.to_string(),
+                ConfigValue::String(value.to_string()),
+            ),
+            (String::from("timeout"), ConfigValue::Float(timeout)),
+            (String::from("interval"), ConfigValue::Float(0.05)),
+        ]))
+    }
+
+    #[test]
+    fn it_gets_exactly_one_condition() {
+        let args = get_args(&ConfigValue::Hash(HashMap::from([(
+            String::from("port"),
+            ConfigValue::String(String::from("localhost:5432")),
+        )])))
+        .unwrap();
+
+        assert_eq!(
+            args,
+            WaitForArgs {
+                condition: Condition::Port(String::from("localhost:5432")),
+                timeout: Duration::from_secs(60),
+                interval: Duration::from_secs(1),
+            }
+        );
+
+        let mut both = get_wait_args("file", "/tmp/ready", 1.0);
+        if let ConfigValue::Hash(values) = &mut both {
+            values.insert(
+                String::from("port"),
+                ConfigValue::String(String::from("localhost:5432")),
+            );
+        }
+        get_args(&both).unwrap_err();
+    }
+
+    #[test]
+    fn it_waits_for_a_file_to_appear() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CommandConfig::for_dir(dir.path());
+        let file = dir.path().join("ready");
+        let args = get_wait_args("file", "ready", 5.0);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(100));
+                write(&file, "").unwrap();
+            });
+
+            wait_for(&args, &config, &Progress::default()).unwrap();
+        });
+    }
+
+    #[test]
+    fn it_waits_for_an_open_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        wait_for(
+            &get_wait_args("port", &address, 1.0),
+            &CommandConfig::for_dir(dir.path()),
+            &Progress::default(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn it_checks_ports_with_an_interval_of_0() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let mut args = get_wait_args("port", &address, 1.0);
+        if let ConfigValue::Hash(values) = &mut args {
+            values.insert(String::from("interval"), ConfigValue::Integer(0));
+        }
+
+        wait_for(
+            &args,
+            &CommandConfig::for_dir(dir.path()),
+            &Progress::default(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn it_escapes_quotes_for_powershell() {
+        assert_eq!(quote_powershell("it's"), "it''s");
+    }
+
+    #[test]
+    fn it_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = wait_for(
+            &get_wait_args("command", "exit 1", 0.2),
+            &CommandConfig::for_dir(dir.path()),
+            &Progress::default(),
+        );
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Timed out after 0.2s waiting for command \"exit 1\""));
+    }
+}
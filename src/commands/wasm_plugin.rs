@@ -0,0 +1,325 @@
+use ansi_term::Color::White;
+use ergo_fs::{Path, PathBuf};
+use serde_json::Value;
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+use tracing::debug;
+use wasmtime::{component::ResourceTable, Config, Engine, Linker, Module, Store, Trap};
+use wasmtime_wasi::{
+    preview2::{
+        pipe::{MemoryInputPipe, MemoryOutputPipe},
+        preview1::{add_to_linker_sync, WasiPreview1Adapter, WasiPreview1View},
+        DirPerms, FilePerms, I32Exit, WasiCtx, WasiCtxBuilder, WasiView,
+    },
+    sync::{ambient_authority, Dir},
+};
+
+use crate::{
+    command::{CommandConfig, CommandInterface},
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+    events::Progress,
+    task_runner::TaskRunnerMode,
+};
+
+use super::plugin::{get_request, handle_output_line, PLUGIN_DIR};
+
+static WASM_EXTENSION: &str = "wasm";
+static GUEST_CONFIG_DIR: &str = "/config";
+const WASM_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_OUTPUT_SIZE: usize = 16 * 1024 * 1024;
+
+/**
+ * Runs a WASI module with the same protocol as executable plugins.
+ * The module can't access the network or the environment, can only read the config directory
+ * and is stopped after `WASM_TIMEOUT`.
+ */
+pub struct WasmPluginCommand {
+    pub name: String,
+    pub module: PathBuf,
+}
+
+pub fn find_wasm_plugin(name: &str, config_dir: &Path) -> Option<PathBuf> {
+    let module = config_dir
+        .join(PLUGIN_DIR)
+        .join(format!("{name}.{WASM_EXTENSION}"));
+
+    module.is_file().then_some(module)
+}
+
+fn get_error(module: &Path, err: impl std::fmt::Display) -> Error {
+    Error::Command(format!("{}: {err}", module.display()))
+}
+
+/**
+ * Paths of the host are meaningless inside of the sandbox, only the config directory is mounted.
+ */
+fn get_guest_request(mode: TaskRunnerMode, args: &ConfigValue, config: &CommandConfig) -> Value {
+    let mut request = get_request(mode, args, config);
+    request["config"]["config_dir"] = GUEST_CONFIG_DIR.into();
+    request["config"]["temp_dir"] = Value::Null;
+    request["config"]["state_dir"] = Value::Null;
+    request["config"]["cache_dir"] = Value::Null;
+
+    request
+}
+
+/**
+ * The state of a module, as required by the preview1 adapter of wasmtime_wasi.
+ */
+struct PluginState {
+    table: ResourceTable,
+    wasi: WasiCtx,
+    adapter: WasiPreview1Adapter,
+}
+
+impl WasiView for PluginState {
+    fn table(&self) -> &ResourceTable {
+        &self.table
+    }
+
+    fn table_mut(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&self) -> &WasiCtx {
+        &self.wasi
+    }
+
+    fn ctx_mut(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl WasiPreview1View for PluginState {
+    fn adapter(&self) -> &WasiPreview1Adapter {
+        &self.adapter
+    }
+
+    fn adapter_mut(&mut self) -> &mut WasiPreview1Adapter {
+        &mut self.adapter
+    }
+}
+
+impl WasmPluginCommand {
+    fn execute(&self, request: String, config_dir: &Path) -> Result<Vec<u8>> {
+        let mut engine_config = Config::new();
+        engine_config.epoch_interruption(true);
+        let engine = Engine::new(&engine_config).map_err(|e| get_error(&self.module, e))?;
+        let module =
+            Module::from_file(&engine, &self.module).map_err(|e| get_error(&self.module, e))?;
+
+        let mut linker: Linker<PluginState> = Linker::new(&engine);
+        add_to_linker_sync(&mut linker).map_err(|e| get_error(&self.module, e))?;
+
+        let config_dir_handle = Dir::open_ambient_dir(config_dir, ambient_authority())
+            .map_err(|e| Error::io(config_dir, e))?;
+
+        let stdout = MemoryOutputPipe::new(MAX_OUTPUT_SIZE);
+        let wasi = WasiCtxBuilder::new()
+            .stdin(MemoryInputPipe::new(request + "\n"))
+            .stdout(stdout.clone())
+            .inherit_stderr()
+            // the module can read the config, but must not change the repository
+            .preopened_dir(
+                config_dir_handle,
+                DirPerms::READ,
+                FilePerms::READ,
+                GUEST_CONFIG_DIR,
+            )
+            .build();
+
+        let state = PluginState {
+            table: ResourceTable::new(),
+            wasi,
+            adapter: WasiPreview1Adapter::new(),
+        };
+        let mut store = Store::new(&engine, state);
+        // the module is interrupted once the epoch is increased after the timeout
+        store.set_epoch_deadline(1);
+        linker
+            .module(&mut store, "", &module)
+            .map_err(|e| get_error(&self.module, e))?;
+
+        let (finished, timer) = mpsc::channel::<()>();
+        let timer_engine = engine.clone();
+        thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = timer.recv_timeout(WASM_TIMEOUT) {
+                timer_engine.increment_epoch();
+            }
+        });
+
+        let call_result = linker
+            .get_default(&mut store, "")
+            .and_then(|entry| entry.typed::<(), ()>(&store))
+            .and_then(|entry| entry.call(&mut store, ()));
+
+        drop(finished);
+
+        if let Err(err_call) = call_result {
+            if err_call.downcast_ref::<Trap>() == Some(&Trap::Interrupt) {
+                return Err(get_error(
+                    &self.module,
+                    format!("timed out after {}s", WASM_TIMEOUT.as_secs()),
+                ));
+            }
+
+            match err_call.downcast_ref::<I32Exit>() {
+                Some(I32Exit(0)) => {}
+                Some(I32Exit(code)) => {
+                    return Err(Error::ExitStatus {
+                        command: self.module.display().to_string(),
+                        code: Some(*code),
+                    })
+                }
+                None => return Err(get_error(&self.module, err_call)),
+            }
+        }
+
+        Ok(stdout.contents().to_vec())
+    }
+
+    fn run(
+        &self,
+        mode: TaskRunnerMode,
+        args: &ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        debug!("Running wasm plugin {} ({mode}) ...", self.module.display());
+        progress.set_message(format!("🔌 {}", White.bold().paint(&self.name)));
+
+        let request = get_guest_request(mode, args, config);
+        let output = self.execute(request.to_string(), &config.config_dir)?;
+
+        // every line is handled, even though only the last result counts
+        let mut results: Vec<_> = String::from_utf8_lossy(&output)
+            .lines()
+            .filter_map(|line| handle_output_line(&self.name, line, config, progress))
+            .collect();
+
+        match results.pop() {
+            Some(Err(err_plugin)) => Err(Error::Command(err_plugin)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl CommandInterface for WasmPluginCommand {
+    fn install(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        self.run(TaskRunnerMode::Install, &args, config, progress)
+    }
+
+    fn uninstall(
+        &self,
+        args: ConfigValue,
+        config: &CommandConfig,
+        progress: &Progress,
+    ) -> Result<()> {
+        self.run(TaskRunnerMode::Uninstall, &args, config, progress)
+    }
+
+    fn update(&self, args: ConfigValue, config: &CommandConfig, progress: &Progress) -> Result<()> {
+        self.run(TaskRunnerMode::Update, &args, config, progress)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_finds_wasm_plugins_in_the_config_dir() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join(PLUGIN_DIR)).unwrap();
+        fs::write(dir.path().join(PLUGIN_DIR).join("greet.wasm"), "").unwrap();
+
+        assert_eq!(
+            find_wasm_plugin("greet", dir.path()),
+            Some(dir.path().join(PLUGIN_DIR).join("greet.wasm"))
+        );
+        assert!(find_wasm_plugin("missing", dir.path()).is_none());
+    }
+
+    #[test]
+    fn it_only_passes_guest_paths() {
+        let dir = tempdir().unwrap();
+        let config = CommandConfig::for_dir(dir.path());
+
+        let request = get_guest_request(TaskRunnerMode::Install, &ConfigValue::Null, &config);
+
+        assert_eq!(request["config"]["config_dir"], GUEST_CONFIG_DIR);
+        assert!(request["config"]["temp_dir"].is_null());
+        assert!(request["config"]["state_dir"].is_null());
+        assert!(request["config"]["cache_dir"].is_null());
+    }
+
+    #[test]
+    fn it_fails_for_invalid_modules() {
+        let dir = tempdir().unwrap();
+        let module = dir.path().join("invalid.wasm");
+        fs::write(&module, "not wasm").unwrap();
+
+        let plugin = WasmPluginCommand {
+            name: String::from("invalid"),
+            module,
+        };
+
+        let err = plugin.execute(String::from("{}"), dir.path()).unwrap_err();
+
+        assert!(err.to_string().contains("invalid.wasm"));
+    }
+
+    #[test]
+    fn it_mounts_the_config_dir_read_only() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("machine_setup.yaml"), "tasks: {}").unwrap();
+
+        // tries to create /config/written.txt and exits with the errno if that fails
+        let module = dir.path().join("write.wasm");
+        fs::write(
+            &module,
+            r#"(module
+                (import "wasi_snapshot_preview1" "path_open"
+                    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 16) "written.txt")
+                (func (export "_start")
+                    (local $errno i32)
+                    (local.set $errno
+                        (call $path_open
+                            (i32.const 3) (i32.const 0) (i32.const 16) (i32.const 11)
+                            (i32.const 1) (i64.const 64) (i64.const 0) (i32.const 0)
+                            (i32.const 32)))
+                    (if (local.get $errno)
+                        (then (call $proc_exit (local.get $errno))))))"#,
+        )
+        .unwrap();
+
+        let plugin = WasmPluginCommand {
+            name: String::from("write"),
+            module,
+        };
+
+        let err = plugin.execute(String::from("{}"), dir.path()).unwrap_err();
+
+        assert!(matches!(err, Error::ExitStatus { code: Some(code), .. } if code != 0));
+        assert!(!dir.path().join("written.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("machine_setup.yaml")).unwrap(),
+            "tasks: {}"
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
@@ -1,11 +1,23 @@
 use ergo_fs::Path;
+use std::fs;
 
-use crate::{task::Task, utils::shell::Shell};
+use crate::{
+    error::{Error, Result},
+    task::Task,
+    utils::{
+        app_dirs::{get_default_cache_dir, get_default_state_dir, get_default_temp_dir},
+        directory::DIR_IGNORE,
+        network::NetworkConfig,
+        shell::DefaultShell,
+        threads::get_thread_number,
+    },
+};
 
 use super::{
     config_value::ConfigValue,
-    json_config::{JsonConfig, ALLOWED_JSON_EXTENSIONS},
-    yaml_config::{YamlConfig, ALLOWED_YAML_EXTENSIONS},
+    json_config::{parse_json_value, JsonConfig, ALLOWED_JSON_EXTENSIONS},
+    macros::apply_macros,
+    yaml_config::{parse_yaml_value, YamlConfig, ALLOWED_YAML_EXTENSIONS},
 };
 
 #[derive(Debug, Clone)]
@@ -14,17 +26,151 @@ pub struct Command {
     pub args: ConfigValue,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TaskList {
     pub tasks: Vec<Task>,
     pub temp_dir: String,
-    pub default_shell: Shell,
+    pub state_dir: String,
+    pub cache_dir: String,
+    pub default_shell: DefaultShell,
     pub num_threads: usize,
     pub parallel: bool,
+    pub sudo: bool,
+    pub network: NetworkConfig,
+    pub inventory: Option<String>,
+}
+
+fn merge_defaults(args: &mut ConfigValue, defaults: &ConfigValue) {
+    let (ConfigValue::Hash(args), ConfigValue::Hash(defaults)) = (args, defaults) else {
+        return;
+    };
+
+    for (key, default) in defaults {
+        match args.get_mut(key) {
+            Some(value) => merge_defaults(value, default),
+            None => {
+                args.insert(key.to_string(), default.clone());
+            }
+        }
+    }
+}
+
+/**
+ * Fills in the args that were not set explicitly with the defaults of the command type.
+ * Nested objects are merged as well, lists and other values are never combined.
+ */
+pub(crate) fn apply_defaults(tasks: &mut [Task], defaults: &ConfigValue) -> Result<()> {
+    if defaults.is_null() || defaults.is_invalid() {
+        return Ok(());
+    }
+
+    let Some(defaults) = defaults.as_hash() else {
+        return Err(Error::Config(String::from("defaults: has to be an object")));
+    };
+
+    if let Some((name, _)) = defaults.iter().find(|(_, args)| !args.is_hash()) {
+        return Err(Error::Config(format!(
+            "defaults: args of {name} have to be an object"
+        )));
+    }
+
+    for command in tasks.iter_mut().flat_map(|task| task.commands.iter_mut()) {
+        if let Some(command_defaults) = defaults.get(&command.name) {
+            merge_defaults(&mut command.args, command_defaults);
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Commands that walk through directories and accept `ignore` patterns.
+ */
+static WALKING_COMMANDS: [&str; 2] = ["copy", "symlink"];
+
+/**
+ * The global patterns come first, so a command can still re-include files with "!pattern".
+ */
+pub(crate) fn apply_global_ignore(tasks: &mut [Task], ignore: &ConfigValue) -> Result<()> {
+    let patterns = match ignore {
+        ConfigValue::Null | ConfigValue::Invalid => return Ok(()),
+        ConfigValue::String(pattern) => vec![ConfigValue::String(pattern.to_string())],
+        ConfigValue::Array(patterns) if patterns.iter().all(|p| p.as_str().is_some()) => {
+            patterns.clone()
+        }
+        _ => {
+            return Err(Error::Config(String::from(
+                "ignore: has to be a list of patterns",
+            )))
+        }
+    };
+
+    for command in tasks.iter_mut().flat_map(|task| task.commands.iter_mut()) {
+        if !WALKING_COMMANDS.contains(&command.name.as_str()) {
+            continue;
+        }
+
+        let ConfigValue::Hash(args) = &mut command.args else {
+            continue;
+        };
+
+        let mut command_patterns = patterns.clone();
+        match args.remove(DIR_IGNORE) {
+            None | Some(ConfigValue::Null) => {}
+            Some(ConfigValue::Array(own_patterns)) => command_patterns.extend(own_patterns),
+            Some(own_pattern) => command_patterns.push(own_pattern),
+        }
+
+        args.insert(DIR_IGNORE.to_string(), ConfigValue::Array(command_patterns));
+    }
+
+    Ok(())
+}
+
+/**
+ * Everything next to `tasks` is the same for every config format, so it's read from the converted config.
+ */
+pub(crate) fn get_task_list(mut tasks: Vec<Task>, config: &ConfigValue) -> Result<TaskList> {
+    let get = |key: &str| {
+        config
+            .as_hash()
+            .and_then(|values| values.get(key))
+            .cloned()
+            .unwrap_or(ConfigValue::Null)
+    };
+    let get_dir = |key: &str, get_default_dir: fn() -> String| {
+        get(key)
+            .as_str()
+            .map(|dir| dir.to_string())
+            .unwrap_or_else(get_default_dir)
+    };
+
+    let default_shell =
+        DefaultShell::from_config_value(&get("default_shell")).map_err(|err_shell| {
+            Error::Config(format!("default_shell: {}", err_shell.into_message()))
+        })?;
+
+    // defaults also apply to the commands a macro expands to
+    apply_macros(&mut tasks, &get("commands")).map_err(Error::Config)?;
+    apply_defaults(&mut tasks, &get("defaults"))?;
+    apply_global_ignore(&mut tasks, &get("ignore"))?;
+
+    Ok(TaskList {
+        tasks,
+        temp_dir: get_dir("temp_dir", get_default_temp_dir),
+        state_dir: get_dir("state_dir", get_default_state_dir),
+        cache_dir: get_dir("cache_dir", get_default_cache_dir),
+        default_shell,
+        num_threads: get_thread_number(get("num_threads").as_i32().map(i64::from)),
+        parallel: get("parallel").as_bool().unwrap_or(false),
+        sudo: get("sudo").as_bool().unwrap_or(false),
+        network: NetworkConfig::from_config_value(&get("network"))?,
+        inventory: get("inventory").as_str().map(|path| path.to_string()),
+    })
 }
 
 pub trait BaseConfig {
-    fn read(&self, path: &str) -> Result<TaskList, String>;
+    fn read(&self, path: &str) -> Result<TaskList>;
 }
 
 fn get_valid_file_endings() -> Vec<&'static str> {
@@ -41,7 +187,7 @@ fn is_valid_file_ending(file_ending: &str) -> bool {
     priorities.contains(&file_ending)
 }
 
-fn find_config_file(config_path: &str) -> Result<String, String> {
+fn find_config_file(config_path: &str) -> Result<String> {
     let priorities = get_valid_file_endings();
 
     for ending in &priorities {
@@ -51,14 +197,14 @@ fn find_config_file(config_path: &str) -> Result<String, String> {
         }
     }
 
-    Err(format!(
+    Err(Error::Config(format!(
         "Could not find a valid config file {}.{{{}}}",
         &config_path,
         &priorities.join(",")
-    ))
+    )))
 }
 
-fn get_config_handler(file_ending: &str) -> Result<Box<dyn BaseConfig>, String> {
+fn get_config_handler(file_ending: &str) -> Result<Box<dyn BaseConfig>> {
     match file_ending {
         file_ending if ALLOWED_YAML_EXTENSIONS.contains(&file_ending) => {
             Ok(Box::new(YamlConfig {}))
@@ -66,7 +212,9 @@ fn get_config_handler(file_ending: &str) -> Result<Box<dyn BaseConfig>, String>
         file_ending if ALLOWED_JSON_EXTENSIONS.contains(&file_ending) => {
             Ok(Box::new(JsonConfig {}))
         }
-        _ => Err(format!("Unsupported config file type: {file_ending}")),
+        _ => Err(Error::Config(format!(
+            "Unsupported config file type: {file_ending}"
+        ))),
     }
 }
 
@@ -87,21 +235,25 @@ fn get_file_ending(path: &str) -> Option<String> {
     Some(path_str.split(FILE_ENDING_SEP).last().unwrap().to_owned())
 }
 
-pub fn get_config(config_path: &str) -> Result<TaskList, String> {
-    let mut file_path = config_path.to_owned();
-    let mut file_ending = get_file_ending(config_path);
-
-    if file_ending.is_none() {
-        file_path = find_config_file(config_path)?;
-        file_ending = get_file_ending(&file_path);
+/**
+ * The file ending is optional, e.g. `machine_setup` resolves to `machine_setup.yaml`.
+ */
+pub fn resolve_config_path(config_path: &str) -> Result<String> {
+    match get_file_ending(config_path) {
+        Some(_) => Ok(config_path.to_owned()),
+        None => find_config_file(config_path),
     }
-    let file_ending = file_ending.unwrap_or_else(|| String::from(""));
+}
+
+pub fn get_config(config_path: &str) -> Result<TaskList> {
+    let file_path = resolve_config_path(config_path)?;
+    let file_ending = get_file_ending(&file_path).unwrap_or_else(|| String::from(""));
 
     if !is_valid_file_ending(&file_ending) {
-        return Err(format!(
+        return Err(Error::Config(format!(
             ".{} is not a supported config file type.",
             &file_ending
-        ));
+        )));
     }
 
     let config = get_config_handler(&file_ending)?;
@@ -109,6 +261,26 @@ pub fn get_config(config_path: &str) -> Result<TaskList, String> {
     config.read(&file_path)
 }
 
+/**
+ * Reads a YAML or JSON file that is not a task list (e.g. the inventory).
+ */
+pub fn read_config_value(path: &str) -> Result<ConfigValue> {
+    let file_ending = get_file_ending(path).unwrap_or_default();
+    let contents = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+
+    let value = match file_ending.as_str() {
+        file_ending if ALLOWED_YAML_EXTENSIONS.contains(&file_ending) => {
+            parse_yaml_value(&contents)
+        }
+        file_ending if ALLOWED_JSON_EXTENSIONS.contains(&file_ending) => {
+            parse_json_value(&contents)
+        }
+        _ => Err(format!(".{file_ending} is not a supported file type.")),
+    };
+
+    value.map_err(|err| Error::Config(format!("{path}: {err}")))
+}
+
 #[cfg(test)]
 mod test {
     use ergo_fs::IoWrite;
@@ -128,7 +300,86 @@ mod test {
         let config = get_config(src_path.to_str().unwrap());
 
         assert!(config.is_err());
-        assert!(config.unwrap_err().contains("No tasks defined"));
+        assert!(config.unwrap_err().to_string().contains("No tasks defined"));
+    }
+
+    #[test]
+    fn it_applies_defaults_under_explicit_args() {
+        let mut tasks = vec![Task {
+            name: String::from("dotfiles"),
+            commands: vec![Command {
+                name: String::from("symlink"),
+                args: parse_yaml_value("src: ./zsh\ntarget: ~\nforce: false\n").unwrap(),
+            }],
+            os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        }];
+        let defaults =
+            parse_yaml_value("symlink:\n  force: true\n  ignore: [\".git\"]\nrun:\n  shell: zsh\n")
+                .unwrap();
+
+        apply_defaults(&mut tasks, &defaults).unwrap();
+
+        let args = tasks[0].commands[0].args.as_hash().unwrap();
+        assert_eq!(args.get("force"), Some(&ConfigValue::Boolean(false)));
+        assert_eq!(
+            args.get("ignore"),
+            Some(&ConfigValue::Array(vec![ConfigValue::String(
+                String::from(".git")
+            )]))
+        );
+        assert!(!args.contains_key("shell"));
+    }
+
+    #[test]
+    fn it_prepends_global_ignore_patterns() {
+        let get_command = |name: &str, args: &str| Command {
+            name: name.to_string(),
+            args: parse_yaml_value(args).unwrap(),
+        };
+        let mut tasks = vec![Task {
+            name: String::from("dotfiles"),
+            commands: vec![
+                get_command("copy", "src: ./git\ntarget: ~\nignore: \"!.gitignore\"\n"),
+                get_command("symlink", "src: ./zsh\ntarget: ~\n"),
+                get_command("run", "commands: \"true\"\n"),
+            ],
+            os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        }];
+        let ignore = parse_yaml_value("[\".DS_Store\", \".git*\"]").unwrap();
+
+        apply_global_ignore(&mut tasks, &ignore).unwrap();
+
+        let get_patterns = |index: usize| {
+            tasks[0].commands[index]
+                .args
+                .as_hash()
+                .unwrap()
+                .get(DIR_IGNORE)
+                .cloned()
+        };
+        assert_eq!(
+            get_patterns(0),
+            parse_yaml_value("[\".DS_Store\", \".git*\", \"!.gitignore\"]").ok()
+        );
+        assert_eq!(get_patterns(1), Some(ignore));
+        assert_eq!(get_patterns(2), None);
+    }
+
+    #[test]
+    fn it_fails_when_defaults_are_not_objects() {
+        let defaults = parse_yaml_value("symlink: true\n").unwrap();
+
+        apply_defaults(&mut [], &defaults).unwrap_err();
     }
 
     #[test]
@@ -138,12 +389,16 @@ mod test {
         assert!(config.is_err());
         assert!(config
             .unwrap_err()
+            .to_string()
             .contains(".js is not a supported config file type."));
     }
 
     #[test]
     fn it_fails_if_no_default_config_is_found() {
         let err = find_config_file("./test").unwrap_err();
-        assert!(err.contains("Could not find a valid config file"));
+        assert!(matches!(err, Error::Config(_)));
+        assert!(err
+            .to_string()
+            .contains("Could not find a valid config file"));
     }
 }
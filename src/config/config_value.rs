@@ -1,3 +1,4 @@
+use serde_json::{Number, Value};
 use std::collections::HashMap;
 use std::string;
 
@@ -63,4 +64,22 @@ impl ConfigValue {
     pub fn is_hash(&self) -> bool {
         matches!(*self, ConfigValue::Hash(_))
     }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            ConfigValue::Float(f) => Number::from_f64(*f as f64)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            ConfigValue::Integer(i) => Value::from(*i),
+            ConfigValue::String(s) => Value::String(s.to_string()),
+            ConfigValue::Boolean(b) => Value::Bool(*b),
+            ConfigValue::Array(a) => Value::Array(a.iter().map(ConfigValue::to_json).collect()),
+            ConfigValue::Hash(h) => Value::Object(
+                h.iter()
+                    .map(|(key, value)| (key.to_string(), value.to_json()))
+                    .collect(),
+            ),
+            ConfigValue::Null | ConfigValue::Invalid => Value::Null,
+        }
+    }
 }
@@ -2,11 +2,7 @@ use ansi_term::Color::White;
 use serde_json::Value;
 use tracing::info;
 
-use crate::{
-    config::base_config::*,
-    task::Task,
-    utils::{shell::Shell, threads::get_thread_number},
-};
+use crate::{config::base_config::*, error::Error, task::Task};
 use std::{collections::HashMap, io::Read, path::Path, str::FromStr};
 
 use super::{config_value::ConfigValue, os::Os};
@@ -19,7 +15,10 @@ pub static ALLOWED_JSON_EXTENSIONS: [&str; 1] = ["json"];
 fn convert_to_config_value(json: &Value) -> ConfigValue {
     match json {
         Value::String(s) => ConfigValue::String(s.to_string()),
-        Value::Number(n) => ConfigValue::Integer(n.as_i64().unwrap() as i32),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => ConfigValue::Integer(n as i32),
+            None => ConfigValue::Float(n.as_f64().unwrap_or_default() as f32),
+        },
         Value::Bool(b) => ConfigValue::Boolean(b.to_owned()),
         Value::Array(a) => {
             let mut array = Vec::new();
@@ -60,6 +59,29 @@ fn get_os_list(value: &Value) -> Result<Vec<Os>, String> {
     Err(format!("{value:?} is in the wrong format"))
 }
 
+fn get_string_list(value: &Value, key: &str) -> Result<Vec<String>, String> {
+    if value.is_null() {
+        return Ok(vec![]);
+    }
+
+    if let Some(item) = value.as_str() {
+        return Ok(vec![item.to_string()]);
+    }
+
+    if let Some(items) = value.as_array() {
+        return items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|item| item.to_string())
+                    .ok_or_else(|| format!("{key}: {item:?} is not a string"))
+            })
+            .collect();
+    }
+
+    Err(format!("{key}: {value:?} is in the wrong format"))
+}
+
 fn get_commands(value: &Value) -> Result<Vec<Command>, String> {
     if value.is_null() {
         return Err(String::from("No commands defined"));
@@ -89,85 +111,78 @@ fn get_commands(value: &Value) -> Result<Vec<Command>, String> {
     Ok(commands)
 }
 
-fn parse_json(path: &Path) -> Result<TaskList, String> {
+pub(crate) fn parse_json_task(key: &str, value: &Value) -> Result<Task, String> {
+    if !value.is_object() {
+        return Err(format!("{key}: task definition is incorrect"));
+    }
+
+    let values = value.as_object().unwrap();
+
+    let commands = get_commands(&values["commands"])?;
+    let os_list = get_os_list(values.get("os").unwrap_or(&Value::Null))?;
+
+    Ok(Task {
+        name: key.to_string(),
+        os: os_list,
+        commands,
+        parallel: values
+            .get("parallel")
+            .unwrap_or(&Value::Bool(false))
+            .as_bool()
+            .unwrap(),
+        tags: get_string_list(values.get("tags").unwrap_or(&Value::Null), "tags")?,
+        when: get_string_list(values.get("when").unwrap_or(&Value::Null), "when")?,
+        only_if: values
+            .get("only_if")
+            .and_then(|guard| guard.as_str())
+            .map(|guard| guard.to_string()),
+        skip_if: values
+            .get("skip_if")
+            .and_then(|guard| guard.as_str())
+            .map(|guard| guard.to_string()),
+    })
+}
+
+pub(crate) fn parse_json_value(contents: &str) -> Result<ConfigValue, String> {
+    let value: Value = serde_json::from_str(contents).map_err(|err| format!("{err}"))?;
+
+    Ok(convert_to_config_value(&value))
+}
+
+fn parse_json(path: &Path) -> Result<TaskList, Error> {
     let mut file = std::fs::File::open(path).unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
 
     let config: Result<Value, serde_json::Error> = serde_json::from_str(&contents);
     if let Err(config_err) = config {
-        return Err(format!("{config_err}"));
+        return Err(Error::Config(format!("{config_err}")));
     }
     let config = config.unwrap();
 
     if config["tasks"] == Value::Null {
-        return Err(String::from("\nNo tasks defined"));
+        return Err(Error::Config(String::from("\nNo tasks defined")));
     }
 
     let mut tasks: Vec<Task> = vec![];
 
-    for task in config["tasks"].as_object().unwrap().iter() {
-        let (key, value) = task;
-
-        if !value.is_object() {
-            return Err(format!("{key}: task definition is incorrect"));
-        }
-
-        let values = value.as_object().unwrap();
-
-        let commands = get_commands(&values["commands"])?;
-        let os_list = get_os_list(values.get("os").unwrap_or(&Value::Null))?;
-
-        let task = Task {
-            name: key.to_string(),
-            os: os_list,
-            commands,
-            parallel: values
-                .get("parallel")
-                .unwrap_or(&Value::Bool(false))
-                .as_bool()
-                .unwrap(),
-        };
-        tasks.push(task);
+    for (key, value) in config["tasks"].as_object().unwrap().iter() {
+        tasks.push(parse_json_task(key, value).map_err(Error::Config)?);
     }
 
-    let temp_dir = config["temp_dir"]
-        .as_str()
-        .unwrap_or("~/.machine_setup")
-        .to_string();
-
-    let default_shell_str = config["default_shell"]
-        .as_str()
-        .unwrap_or("bash")
-        .to_string();
-
-    let default_shell = Shell::from_str(&default_shell_str);
-    if let Err(err_shell) = default_shell {
-        return Err(format!("default_shell: {err_shell}"));
-    }
-    let default_shell = default_shell.unwrap();
-
-    let parallel = config["parallel"].as_bool().unwrap_or(false);
-
-    Ok(TaskList {
-        tasks,
-        temp_dir,
-        default_shell,
-        num_threads: get_thread_number(config["num_threads"].as_i64()),
-        parallel,
-    })
+    get_task_list(tasks, &convert_to_config_value(&config))
 }
 
 impl BaseConfig for JsonConfig {
-    fn read(&self, path: &str) -> Result<TaskList, String> {
+    fn read(&self, path: &str) -> Result<TaskList, Error> {
         let json_path = Path::new(path);
 
         if !json_path.exists() {
-            return Err(format!("File {path} does not exist"));
+            return Err(Error::Config(format!("File {path} does not exist")));
         }
 
         if json_path.extension().unwrap().to_str().unwrap() != "json" {
-            return Err(format!("File {path} is not a JSON file"));
+            return Err(Error::Config(format!("File {path} is not a JSON file")));
         }
 
         info!("Reading config from {} ...", White.bold().paint(path));
@@ -209,7 +224,7 @@ mod test {
         let result = config.read(src_path.to_str().unwrap());
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("No tasks defined"));
+        assert!(result.unwrap_err().to_string().contains("No tasks defined"));
     }
 
     #[test]
@@ -226,6 +241,9 @@ mod test {
         let result = config.read(src_path.to_str().unwrap());
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Commands have to be a list"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Commands have to be a list"));
     }
 }
@@ -0,0 +1,379 @@
+use regex::Captures;
+use std::collections::HashMap;
+
+use crate::{command::COMMAND_NAMES, task::Task, utils::variables::PLACEHOLDER};
+
+use super::{base_config::Command, config_value::ConfigValue};
+
+/**
+ * A command type that is composed of other commands in the `commands` section of the config.
+ * Params without a default are required.
+ */
+#[derive(Debug, Clone)]
+struct Macro {
+    params: HashMap<String, Option<ConfigValue>>,
+    commands: Vec<Command>,
+}
+
+fn get_params(
+    name: &str,
+    value: Option<&ConfigValue>,
+) -> Result<HashMap<String, Option<ConfigValue>>, String> {
+    match value {
+        None | Some(ConfigValue::Null) => Ok(HashMap::new()),
+        Some(ConfigValue::Array(params)) => params
+            .iter()
+            .map(|param| match param.as_str() {
+                Some(param) => Ok((param.to_string(), None)),
+                None => Err(format!("commands: params of {name} have to be names")),
+            })
+            .collect(),
+        Some(ConfigValue::Hash(params)) => Ok(params
+            .iter()
+            .map(|(param, default)| {
+                let default = (!default.is_null()).then(|| default.clone());
+                (param.to_string(), default)
+            })
+            .collect()),
+        Some(_) => Err(format!(
+            "commands: params of {name} have to be a list or an object with defaults"
+        )),
+    }
+}
+
+/**
+ * Every command needs its own list item, the keys of an object have no order.
+ */
+fn get_commands(name: &str, value: Option<&ConfigValue>) -> Result<Vec<Command>, String> {
+    let Some(ConfigValue::Array(commands)) = value else {
+        return Err(format!("commands: {name} has to define a list of commands"));
+    };
+
+    commands
+        .iter()
+        .map(|command| match command.as_hash() {
+            Some(command) if command.len() == 1 => {
+                let (name, args) = command.iter().next().unwrap();
+
+                Ok(Command {
+                    name: name.to_string(),
+                    args: args.clone(),
+                })
+            }
+            _ => Err(format!(
+                "commands: every command of {name} needs its own list item"
+            )),
+        })
+        .collect()
+}
+
+fn get_macros(definitions: &ConfigValue) -> Result<HashMap<String, Macro>, String> {
+    if definitions.is_null() || definitions.is_invalid() {
+        return Ok(HashMap::new());
+    }
+
+    let Some(definitions) = definitions.as_hash() else {
+        return Err(String::from("commands: has to be an object"));
+    };
+
+    definitions
+        .iter()
+        .map(|(name, definition)| {
+            if COMMAND_NAMES.contains(&name.as_str()) {
+                return Err(format!("commands: {name} is already a built-in command"));
+            }
+
+            let Some(values) = definition.as_hash() else {
+                return Err(format!("commands: {name} has to be an object"));
+            };
+
+            Ok((
+                name.to_string(),
+                Macro {
+                    params: get_params(name, values.get("params"))?,
+                    commands: get_commands(name, values.get("commands"))?,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn get_arguments(
+    name: &str,
+    definition: &Macro,
+    args: &ConfigValue,
+) -> Result<HashMap<String, ConfigValue>, String> {
+    let empty = HashMap::new();
+    let args = match args {
+        ConfigValue::Null => &empty,
+        ConfigValue::Hash(args) => args,
+        _ => return Err(format!("{name}: expected named arguments")),
+    };
+
+    if let Some(unknown) = args
+        .keys()
+        .find(|arg| !definition.params.contains_key(*arg))
+    {
+        return Err(format!("{name}: unknown param \"{unknown}\""));
+    }
+
+    definition
+        .params
+        .iter()
+        .map(
+            |(param, default)| match args.get(param).or(default.as_ref()) {
+                Some(value) => Ok((param.to_string(), value.clone())),
+                None => Err(format!("{name}: param \"{param}\" is missing")),
+            },
+        )
+        .collect()
+}
+
+fn to_string(value: &ConfigValue) -> Option<String> {
+    match value {
+        ConfigValue::String(s) => Some(s.to_string()),
+        ConfigValue::Integer(i) => Some(i.to_string()),
+        ConfigValue::Float(f) => Some(f.to_string()),
+        ConfigValue::Boolean(b) => Some(b.to_string()),
+        ConfigValue::Null => Some(String::from("")),
+        _ => None,
+    }
+}
+
+/**
+ * A placeholder on its own keeps the type of the argument, e.g. a list of packages.
+ * Placeholders of anything but a param are kept for the variables of the run.
+ */
+fn substitute(
+    value: &ConfigValue,
+    arguments: &HashMap<String, ConfigValue>,
+) -> Result<ConfigValue, String> {
+    match value {
+        ConfigValue::String(s) => {
+            let whole = PLACEHOLDER
+                .captures(s.trim())
+                .filter(|captures| captures[0].len() == s.trim().len());
+            if let Some(argument) = whole.and_then(|captures| arguments.get(&captures[1])) {
+                return Ok(argument.clone());
+            }
+
+            if let Some(captures) = PLACEHOLDER.captures_iter(s).find(|captures| {
+                arguments
+                    .get(&captures[1])
+                    .is_some_and(|argument| to_string(argument).is_none())
+            }) {
+                return Err(format!(
+                    "param \"{}\" can't be part of a string, because it's not a single value",
+                    &captures[1]
+                ));
+            }
+
+            let substituted = PLACEHOLDER.replace_all(s, |captures: &Captures| {
+                arguments
+                    .get(&captures[1])
+                    .and_then(to_string)
+                    .unwrap_or_else(|| captures[0].to_string())
+            });
+
+            Ok(ConfigValue::String(substituted.to_string()))
+        }
+        ConfigValue::Array(values) => Ok(ConfigValue::Array(
+            values
+                .iter()
+                .map(|value| substitute(value, arguments))
+                .collect::<Result<_, _>>()?,
+        )),
+        ConfigValue::Hash(values) => Ok(ConfigValue::Hash(
+            values
+                .iter()
+                .map(|(key, value)| {
+                    substitute(value, arguments).map(|value| (key.to_string(), value))
+                })
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Ok(value.clone()),
+    }
+}
+
+/**
+ * Macros can use other macros, `stack` holds the ones that are currently expanded.
+ */
+fn expand(
+    command: &Command,
+    macros: &HashMap<String, Macro>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<Command>, String> {
+    let Some(definition) = macros.get(&command.name) else {
+        return Ok(vec![command.clone()]);
+    };
+
+    if stack.contains(&command.name) {
+        return Err(format!(
+            "commands: {} uses itself ({} -> {})",
+            command.name,
+            stack.join(" -> "),
+            command.name
+        ));
+    }
+
+    let arguments = get_arguments(&command.name, definition, &command.args)?;
+
+    stack.push(command.name.to_string());
+    let mut expanded = vec![];
+    for inner in &definition.commands {
+        let inner = Command {
+            name: inner.name.to_string(),
+            args: substitute(&inner.args, &arguments)
+                .map_err(|err_param| format!("{}: {err_param}", command.name))?,
+        };
+
+        expanded.extend(expand(&inner, macros, stack)?);
+    }
+    stack.pop();
+
+    Ok(expanded)
+}
+
+/**
+ * Replaces the commands of every task that use a macro with the commands the macro is composed of.
+ */
+pub(crate) fn apply_macros(tasks: &mut [Task], definitions: &ConfigValue) -> Result<(), String> {
+    let macros = get_macros(definitions)?;
+    if macros.is_empty() {
+        return Ok(());
+    }
+
+    for task in tasks.iter_mut() {
+        let mut commands = vec![];
+        for command in &task.commands {
+            commands.extend(
+                expand(command, &macros, &mut vec![])
+                    .map_err(|err_macro| format!("{}: {err_macro}", task.name))?,
+            );
+        }
+
+        task.commands = commands;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::yaml_config::parse_yaml_value;
+
+    use super::*;
+
+    fn get_task(commands: &str) -> Task {
+        let ConfigValue::Array(commands) = parse_yaml_value(commands).unwrap() else {
+            panic!("commands have to be a list");
+        };
+
+        Task {
+            name: String::from("rust"),
+            commands: commands
+                .into_iter()
+                .flat_map(|command| command.as_hash().unwrap().clone())
+                .map(|(name, args)| Command { name, args })
+                .collect(),
+            os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        }
+    }
+
+    static TOOLCHAIN: &str = "
+toolchain:
+  params:
+    name:
+    repo:
+    installer: install.sh
+  commands:
+    - clone:
+        repo: \"{{ repo }}\"
+        target: \"~/tools/{{name}}\"
+    - run:
+        commands: \"~/tools/{{ name }}/{{ installer }} --os {{ os }}\"
+";
+
+    #[test]
+    fn it_expands_macros_with_their_params() {
+        let mut tasks = vec![get_task(
+            "- toolchain:\n    name: rust\n    repo: https://github.com/rust-lang/rustup\n- run:\n    commands: rustc --version\n",
+        )];
+
+        apply_macros(&mut tasks, &parse_yaml_value(TOOLCHAIN).unwrap()).unwrap();
+
+        let commands: Vec<(String, ConfigValue)> = tasks[0]
+            .commands
+            .iter()
+            .map(|command| (command.name.to_string(), command.args.clone()))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                (
+                    String::from("clone"),
+                    parse_yaml_value(
+                        "repo: https://github.com/rust-lang/rustup\ntarget: ~/tools/rust\n"
+                    )
+                    .unwrap()
+                ),
+                (
+                    String::from("run"),
+                    parse_yaml_value("commands: \"~/tools/rust/install.sh --os {{ os }}\"\n")
+                        .unwrap()
+                ),
+                (
+                    String::from("run"),
+                    parse_yaml_value("commands: rustc --version\n").unwrap()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_type_of_whole_params() {
+        let definitions = parse_yaml_value(
+            "packages:\n  params: [names]\n  commands:\n    - run:\n        env:\n          PACKAGES: \"{{ names }}\"\n        commands: \"{{names}}\"\n",
+        )
+        .unwrap();
+        let mut tasks = vec![get_task("- packages:\n    names: [git, fzf]\n")];
+
+        apply_macros(&mut tasks, &definitions).unwrap();
+
+        assert_eq!(
+            tasks[0].commands[0].args,
+            parse_yaml_value("env:\n  PACKAGES: [git, fzf]\ncommands: [git, fzf]\n").unwrap()
+        );
+
+        let definitions = parse_yaml_value(
+            "packages:\n  params: [names]\n  commands:\n    - run:\n        commands: \"apt-get install {{ names }}\"\n",
+        )
+        .unwrap();
+        let mut tasks = vec![get_task("- packages:\n    names: [git, fzf]\n")];
+        apply_macros(&mut tasks, &definitions).unwrap_err();
+    }
+
+    #[test]
+    fn it_fails_for_invalid_macros() {
+        let definitions = parse_yaml_value(TOOLCHAIN).unwrap();
+
+        let missing = get_task("- toolchain:\n    name: rust\n");
+        let unknown = get_task("- toolchain:\n    name: rust\n    repo: a\n    branch: main\n");
+        for task in [missing, unknown] {
+            apply_macros(&mut [task], &definitions).unwrap_err();
+        }
+
+        let recursive =
+            parse_yaml_value("a:\n  commands:\n    - b:\nb:\n  commands:\n    - a:\n").unwrap();
+        let err = apply_macros(&mut [get_task("- a:\n")], &recursive).unwrap_err();
+        assert!(err.contains("uses itself"), "{err}");
+
+        let builtin = parse_yaml_value("run:\n  commands:\n    - copy:\n").unwrap();
+        apply_macros(&mut [], &builtin).unwrap_err();
+    }
+}
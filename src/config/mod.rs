@@ -1,6 +1,7 @@
 pub mod base_config;
 pub mod config_value;
 pub mod json_config;
+pub mod macros;
 pub mod os;
 pub mod validation_rules;
 pub mod validator;
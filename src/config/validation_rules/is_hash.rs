@@ -0,0 +1,48 @@
+use crate::config::{config_value::ConfigValue, validator::ValidationRule};
+
+pub struct IsHash {}
+
+impl ValidationRule for IsHash {
+    fn validate(&self, input: Option<&ConfigValue>) -> bool {
+        if input.is_none() {
+            return true;
+        }
+
+        input.unwrap_or(&ConfigValue::Invalid).is_hash()
+    }
+
+    fn to_string(&self) -> String {
+        String::from("argument must be an object")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn it_fails_when_required_arg_is_not_a_hash() {
+        let rule = IsHash {};
+        let input = ConfigValue::String("foo".to_string());
+        assert!(!rule.validate(Some(&input)));
+    }
+
+    #[test]
+    fn it_returns_ok_when_required_arg_is_a_hash() {
+        let rule = IsHash {};
+        let input = ConfigValue::Hash(HashMap::from([(
+            "linux".to_string(),
+            ConfigValue::String("foo".to_string()),
+        )]));
+        assert!(rule.validate(Some(&input)));
+    }
+
+    #[test]
+    fn it_returns_true_when_value_is_none() {
+        let rule = IsHash {};
+
+        assert!(rule.validate(None));
+    }
+}
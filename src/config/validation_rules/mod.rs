@@ -1,5 +1,6 @@
 pub mod is_array;
 pub mod is_bool;
+pub mod is_hash;
 pub mod is_string;
 pub mod one_of;
 pub mod required;
@@ -20,13 +20,14 @@ impl ValidationRule for OneOf {
     }
 
     fn to_string(&self) -> String {
-        return String::from("OneOf: ")
-            + &self
-                .rules
+        return format!(
+            "OneOf: {}",
+            self.rules
                 .iter()
                 .map(|rule| rule.to_string())
                 .collect::<Vec<String>>()
-                .join(" | ");
+                .join(" | ")
+        );
     }
 }
 
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::error::{Error, Result};
+
 use super::config_value::ConfigValue;
 
 pub trait ValidationRule {
@@ -14,14 +16,16 @@ pub fn arguments_are_named(args: Option<&ConfigValue>) -> bool {
 pub fn validate_args(
     args: Option<&ConfigValue>,
     rules: Vec<Box<dyn ValidationRule>>,
-) -> Result<(), String> {
+) -> Result<()> {
     if arguments_are_named(args) {
-        return Err("Expected positional arguments, got named arguments".to_string());
+        return Err(Error::Validation(
+            "Expected positional arguments, got named arguments".to_string(),
+        ));
     }
 
     for rule in rules {
         if !rule.validate(args) {
-            return Err(rule.to_string());
+            return Err(Error::Validation(rule.to_string()));
         }
     }
 
@@ -31,11 +35,13 @@ pub fn validate_args(
 pub fn validate_named_args(
     args: ConfigValue,
     rules: HashMap<String, Vec<Box<dyn ValidationRule>>>,
-) -> Result<(), String> {
+) -> Result<()> {
     let named_args = args.as_hash();
 
     if named_args.is_none() {
-        return Err("Expected named arguments, got positional arguments".to_string());
+        return Err(Error::Validation(
+            "Expected named arguments, got positional arguments".to_string(),
+        ));
     }
 
     let named_args = named_args.unwrap();
@@ -45,7 +51,11 @@ pub fn validate_named_args(
 
         let result = validate_args(input, rule_list);
         if result.is_err() {
-            return Err(format!("{}: {}", arg_name, result.unwrap_err()));
+            return Err(Error::Validation(format!(
+                "{}: {}",
+                arg_name,
+                result.unwrap_err()
+            )));
         }
     }
 
@@ -82,6 +92,9 @@ mod test {
 
         let args = ConfigValue::Hash(hash);
 
-        validate_named_args(args, rules).unwrap_err();
+        let err = validate_named_args(args, rules).unwrap_err();
+
+        assert!(matches!(err, Error::Validation(_)));
+        assert_eq!(err.to_string(), "foo: argument is required");
     }
 }
@@ -4,11 +4,7 @@ use ansi_term::Color::White;
 use tracing::info;
 use yaml_rust::{Yaml, YamlLoader};
 
-use crate::{
-    config::base_config::*,
-    task::Task,
-    utils::{shell::Shell, threads::get_thread_number},
-};
+use crate::{config::base_config::*, error::Error, task::Task};
 use std::{collections::HashMap, io::Read, path::Path, str::FromStr};
 
 use super::{config_value::ConfigValue, os::Os};
@@ -66,6 +62,29 @@ fn get_os_list(value: &Yaml) -> Result<Vec<Os>, String> {
     Err(format!("os: {value:?}"))
 }
 
+fn get_string_list(value: &Yaml, key: &str) -> Result<Vec<String>, String> {
+    if let Some(str_value) = value.as_str() {
+        return Ok(vec![str_value.to_string()]);
+    }
+
+    if let Some(arr_value) = value.as_vec() {
+        return arr_value
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|item| item.to_string())
+                    .ok_or_else(|| format!("{key}: {item:?}"))
+            })
+            .collect();
+    }
+
+    if value.is_null() || value.is_badvalue() {
+        return Ok(vec![]);
+    }
+
+    Err(format!("{key}: {value:?}"))
+}
+
 fn get_commands(value: &Yaml) -> Result<Vec<Command>, String> {
     if value.is_badvalue() || value.is_null() {
         return Err(String::from("\nNo commands defined"));
@@ -95,24 +114,18 @@ fn get_commands(value: &Yaml) -> Result<Vec<Command>, String> {
     Ok(commands)
 }
 
-fn parse_yaml(path: &Path) -> Result<TaskList, String> {
-    let mut file = std::fs::File::open(path).unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
+pub(crate) fn parse_yaml_value(contents: &str) -> Result<ConfigValue, String> {
+    let documents = YamlLoader::load_from_str(contents).map_err(|err| format!("{err}"))?;
 
-    let config = YamlLoader::load_from_str(&contents);
-    if let Err(config_err) = config {
-        return Err(format!("{config_err}"));
-    }
-
-    let config = config.unwrap();
-    let entries = &config[0];
-    if entries["tasks"] == Yaml::BadValue || entries["tasks"] == Yaml::Null {
-        return Err(String::from("\nNo tasks defined"));
-    }
+    Ok(documents
+        .first()
+        .map(convert_to_config_value)
+        .unwrap_or(ConfigValue::Null))
+}
 
+fn parse_yaml_tasks(tasks_yaml: &Yaml) -> Result<Vec<Task>, String> {
     let mut tasks: Vec<Task> = vec![];
-    for task in entries["tasks"].as_hash().unwrap().iter() {
+    for task in tasks_yaml.as_hash().unwrap().iter() {
         let (key, value) = task;
 
         if value.clone().into_hash().is_none() {
@@ -143,47 +156,48 @@ fn parse_yaml(path: &Path) -> Result<TaskList, String> {
             os: os_list,
             commands,
             parallel: value["parallel"].as_bool().unwrap_or(false),
+            tags: get_string_list(&value["tags"], "tags")?,
+            when: get_string_list(&value["when"], "when")?,
+            only_if: value["only_if"].as_str().map(|guard| guard.to_string()),
+            skip_if: value["skip_if"].as_str().map(|guard| guard.to_string()),
         };
         tasks.push(task);
     }
 
-    let temp_dir = entries["temp_dir"]
-        .as_str()
-        .unwrap_or("~/.machine_setup")
-        .to_string();
+    Ok(tasks)
+}
 
-    let default_shell_str = entries["default_shell"]
-        .as_str()
-        .unwrap_or("bash")
-        .to_string();
+fn parse_yaml(path: &Path) -> Result<TaskList, Error> {
+    let mut file = std::fs::File::open(path).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    let config = YamlLoader::load_from_str(&contents);
+    if let Err(config_err) = config {
+        return Err(Error::Config(format!("{config_err}")));
+    }
 
-    let default_shell = Shell::from_str(&default_shell_str);
-    if let Err(err_shell) = default_shell {
-        return Err(format!("default_shell: {err_shell}"));
+    let config = config.unwrap();
+    let entries = &config[0];
+    if entries["tasks"] == Yaml::BadValue || entries["tasks"] == Yaml::Null {
+        return Err(Error::Config(String::from("\nNo tasks defined")));
     }
-    let default_shell = default_shell.unwrap();
 
-    let parallel = entries["parallel"].as_bool().unwrap_or(false);
+    let tasks = parse_yaml_tasks(&entries["tasks"]).map_err(Error::Config)?;
 
-    Ok(TaskList {
-        tasks,
-        temp_dir,
-        default_shell,
-        num_threads: get_thread_number(entries["num_threads"].as_i64()),
-        parallel,
-    })
+    get_task_list(tasks, &convert_to_config_value(entries))
 }
 
 impl BaseConfig for YamlConfig {
-    fn read(&self, path: &str) -> Result<TaskList, String> {
+    fn read(&self, path: &str) -> Result<TaskList, Error> {
         let yaml_path = Path::new(path);
 
         if !yaml_path.exists() {
-            return Err(format!("File {path} does not exist"));
+            return Err(Error::Config(format!("File {path} does not exist")));
         }
 
         if !ALLOWED_YAML_EXTENSIONS.contains(&yaml_path.extension().unwrap().to_str().unwrap()) {
-            return Err(format!("File {path} is not a YAML file"));
+            return Err(Error::Config(format!("File {path} is not a YAML file")));
         }
 
         info!("Reading config from {} ...", White.bold().paint(path));
@@ -225,7 +239,7 @@ mod test {
         let result = config.read(src_path.to_str().unwrap());
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("No tasks defined"));
+        assert!(result.unwrap_err().to_string().contains("No tasks defined"));
     }
 
     #[test]
@@ -242,6 +256,9 @@ mod test {
         let result = config.read(src_path.to_str().unwrap());
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("No commands defined"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("No commands defined"));
     }
 }
@@ -0,0 +1,231 @@
+use ansi_term::Color::{Green, Red, White};
+use core::fmt;
+use ergo_fs::{Path, PathDir};
+use std::{
+    env,
+    io::IsTerminal,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+use tracing::{info, Level};
+
+use crate::{
+    error::{Error, Result},
+    utils::shell::quote,
+};
+
+const CONFIG_MOUNT: &str = "/machine_setup/config";
+const BINARY_MOUNT: &str = "/usr/local/bin/machine_setup";
+/** the config is mounted read-only, so it's copied to a writable directory first (e.g. for the lockfile) */
+const WORK_DIR: &str = "/tmp/machine_setup";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Docker,
+    Podman,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Engine::Docker => write!(f, "docker"),
+            Engine::Podman => write!(f, "podman"),
+        }
+    }
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "docker" => Ok(Engine::Docker),
+            "podman" => Ok(Engine::Podman),
+            _ => Err(format!("Unknown container engine: {s}")),
+        }
+    }
+}
+
+fn is_available(engine: Engine) -> bool {
+    Command::new(engine.to_string())
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+pub fn find_engine() -> Result<Engine> {
+    [Engine::Docker, Engine::Podman]
+        .into_iter()
+        .find(|engine| is_available(*engine))
+        .ok_or_else(|| Error::Command(String::from("Neither docker nor podman is installed")))
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerTest {
+    pub image: String,
+    /** file name of the config, relative to the config directory */
+    pub config_file: String,
+    pub task: Option<String>,
+    pub level: Level,
+}
+
+fn get_container_args(test: &ContainerTest, binary: &Path, config_dir: &Path) -> Vec<String> {
+    let mut install = vec![
+        String::from("machine_setup"),
+        String::from("install"),
+        String::from("--config"),
+        format!("{WORK_DIR}/{}", test.config_file),
+        String::from("--level"),
+        test.level.to_string().to_lowercase(),
+    ];
+    if let Some(task) = &test.task {
+        install.push(String::from("--task"));
+        install.push(task.to_string());
+    }
+
+    let script = format!(
+        "cp -r {CONFIG_MOUNT} {WORK_DIR} && {}",
+        install
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<String>>()
+            .join(" ")
+    );
+
+    let mut args = vec![String::from("run"), String::from("--rm")];
+    if std::io::stdout().is_terminal() {
+        args.push(String::from("--tty"));
+    }
+
+    args.extend([
+        String::from("--volume"),
+        format!("{}:{CONFIG_MOUNT}:ro", config_dir.display()),
+        String::from("--volume"),
+        format!("{}:{BINARY_MOUNT}:ro", binary.display()),
+        test.image.to_string(),
+        String::from("sh"),
+        String::from("-c"),
+        script,
+    ]);
+
+    args
+}
+
+/**
+ * Installs the config inside of a disposable container.
+ * The binary is mounted into the container, so it has to be built for Linux.
+ */
+pub fn run_test(engine: Engine, test: &ContainerTest, config_dir: &PathDir) -> Result<()> {
+    if env::consts::OS != "linux" {
+        return Err(Error::Command(String::from(
+            "Testing in a container requires machine_setup to run on Linux",
+        )));
+    }
+
+    let binary = env::current_exe().map_err(|err| Error::io("machine_setup", err))?;
+
+    info!(
+        "Testing in {} with {} ...",
+        White.bold().paint(&test.image),
+        engine
+    );
+
+    let status = Command::new(engine.to_string())
+        .args(get_container_args(test, &binary, config_dir.as_path()))
+        .status()
+        .map_err(|err| Error::Spawn {
+            program: engine.to_string(),
+            source: err,
+        })?;
+
+    if !status.success() {
+        return Err(Error::ExitStatus {
+            command: format!("{engine} run {}", test.image),
+            code: status.code(),
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Runs the test for every image and prints a summary.
+ */
+pub fn run_tests(
+    engine: Engine,
+    images: &[String],
+    test: &ContainerTest,
+    config_dir: &PathDir,
+) -> Result<()> {
+    let results: Vec<(String, Result<()>)> = images
+        .iter()
+        .map(|image| {
+            let test = ContainerTest {
+                image: image.to_string(),
+                ..test.clone()
+            };
+
+            (image.to_string(), run_test(engine, &test, config_dir))
+        })
+        .collect();
+
+    println!("\nResults\n-----------------------------");
+    for (image, result) in &results {
+        match result {
+            Ok(_) => println!("✅ {}", Green.paint(image)),
+            Err(err_test) => println!("❌ {}: {}", Red.paint(image), err_test),
+        }
+    }
+    println!("-----------------------------");
+
+    let failed: Vec<String> = results
+        .into_iter()
+        .filter(|(_, result)| result.is_err())
+        .map(|(image, _)| image)
+        .collect();
+
+    if !failed.is_empty() {
+        return Err(Error::Command(format!(
+            "Tests failed in {}",
+            failed.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_mounts_the_config_read_only() {
+        let test = ContainerTest {
+            image: String::from("ubuntu:24.04"),
+            config_file: String::from("machine_setup.yaml"),
+            task: Some(String::from("my task")),
+            level: Level::WARN,
+        };
+
+        let args = get_container_args(
+            &test,
+            Path::new("/usr/bin/machine_setup"),
+            Path::new("/home/timo/.dotfiles"),
+        );
+
+        assert!(args.contains(&format!("/home/timo/.dotfiles:{CONFIG_MOUNT}:ro")));
+        assert!(args.contains(&format!("/usr/bin/machine_setup:{BINARY_MOUNT}:ro")));
+        assert_eq!(
+            args.last().unwrap(),
+            &format!("cp -r {CONFIG_MOUNT} {WORK_DIR} && machine_setup install --config {WORK_DIR}/machine_setup.yaml --level warn --task 'my task'")
+        );
+    }
+
+    #[test]
+    fn it_parses_engines() {
+        assert_eq!(Engine::from_str("podman").unwrap(), Engine::Podman);
+        assert!(Engine::from_str("lxc").is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use ergo_fs::{Path, PathBuf};
+use std::fs::{canonicalize, read_dir, read_link};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+/**
+ * Directories that contain a lot of files, but never links to dotfiles.
+ */
+static SKIPPED_DIRS: [&str; 3] = ["node_modules", ".cache", ".git"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredLink {
+    /** relative to the repository */
+    pub src: String,
+    /** e.g. "~/.config/nvim" */
+    pub target: String,
+    /** the link points to a directory instead of a single file */
+    pub link_dir: bool,
+    pub relative: bool,
+}
+
+fn to_target(home: &Path, path: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(relative) => format!("~/{}", relative.to_string_lossy()),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+fn scan_dir(dir: &Path, depth: usize, home: &Path, repo: &Path, links: &mut Vec<DiscoveredLink>) {
+    // unreadable directories are not an error, e.g. missing permissions
+    let Ok(entries) = read_dir(dir) else {
+        return;
+    };
+
+    let mut entries: Vec<PathBuf> = entries
+        .filter_map(|entry| {
+            entry
+                .map_err(|err_entry| debug!("Skipping an entry of {}: {err_entry}", dir.display()))
+                .ok()
+        })
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let Ok(link) = read_link(&path) else {
+            let is_skipped = path
+                .file_name()
+                .map(|name| SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref()))
+                .unwrap_or(false);
+
+            if path.is_dir() && depth > 1 && !is_skipped && path != repo {
+                scan_dir(&path, depth - 1, home, repo, links);
+            }
+            continue;
+        };
+
+        // broken links are skipped
+        let Ok(resolved) = canonicalize(&path) else {
+            continue;
+        };
+
+        if let Ok(src) = resolved.strip_prefix(repo) {
+            links.push(DiscoveredLink {
+                src: src.to_string_lossy().to_string(),
+                target: to_target(home, &path),
+                link_dir: resolved.is_dir(),
+                relative: link.is_relative(),
+            });
+        }
+    }
+}
+
+/**
+ * Finds the symlinks inside of `home` that point into `repo`.
+ * Linked directories are not descended into, so every link is only found once.
+ */
+pub fn discover_links(repo: &Path, home: &Path, max_depth: usize) -> Result<Vec<DiscoveredLink>> {
+    let repo = canonicalize(repo).map_err(|e| Error::io(repo, e))?;
+    let home = canonicalize(home).map_err(|e| Error::io(home, e))?;
+
+    let mut links = vec![];
+    scan_dir(&home, max_depth, &home, &repo, &mut links);
+
+    Ok(links)
+}
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/**
+ * Links with the same options end up in one symlink command.
+ * The sources are relative to the repository, so the config belongs into its root directory.
+ */
+pub fn get_discovered_config(links: &[DiscoveredLink]) -> String {
+    let mut lines = vec![
+        String::from("tasks:"),
+        String::from("  dotfiles:"),
+        String::from("    commands:"),
+    ];
+
+    for link_dir in [false, true] {
+        for relative in [false, true] {
+            let group: Vec<&DiscoveredLink> = links
+                .iter()
+                .filter(|link| link.link_dir == link_dir && link.relative == relative)
+                .collect();
+
+            if group.is_empty() {
+                continue;
+            }
+
+            lines.push(String::from("      - symlink:"));
+            lines.push(String::from("          src: \".\""));
+            if link_dir {
+                lines.push(String::from("          link_dir: true"));
+            }
+            if relative {
+                lines.push(String::from("          relative: true"));
+            }
+            lines.push(String::from("          files:"));
+            for link in group {
+                lines.push(format!(
+                    "            {}: {}",
+                    quote(&link.src),
+                    quote(&link.target)
+                ));
+            }
+        }
+    }
+
+    if links.is_empty() {
+        lines.push(String::from(
+            "      # no links into the repository were found",
+        ));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{create_dir_all, write};
+
+    use crate::config::yaml_config::parse_yaml_value;
+
+    use super::*;
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn it_discovers_links_into_the_repository() {
+        use std::os::unix::fs::symlink;
+
+        let home = tempfile::tempdir().unwrap();
+        let repo = home.path().join("dotfiles");
+        create_dir_all(repo.join("nvim")).unwrap();
+        write(repo.join("zshrc"), "").unwrap();
+        create_dir_all(home.path().join(".config")).unwrap();
+        create_dir_all(home.path().join("node_modules")).unwrap();
+
+        symlink(repo.join("zshrc"), home.path().join(".zshrc")).unwrap();
+        symlink("../dotfiles/nvim", home.path().join(".config/nvim")).unwrap();
+        symlink(repo.join("zshrc"), home.path().join("node_modules/.zshrc")).unwrap();
+        symlink("/does/not/exist", home.path().join(".broken")).unwrap();
+
+        let links = discover_links(&repo, home.path(), 4).unwrap();
+
+        assert_eq!(
+            links,
+            vec![
+                DiscoveredLink {
+                    src: String::from("nvim"),
+                    target: String::from("~/.config/nvim"),
+                    link_dir: true,
+                    relative: true,
+                },
+                DiscoveredLink {
+                    src: String::from("zshrc"),
+                    target: String::from("~/.zshrc"),
+                    link_dir: false,
+                    relative: false,
+                },
+            ]
+        );
+
+        assert_eq!(discover_links(&repo, home.path(), 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn it_groups_links_by_their_options() {
+        let links = vec![
+            DiscoveredLink {
+                src: String::from("zshrc"),
+                target: String::from("~/.zshrc"),
+                link_dir: false,
+                relative: false,
+            },
+            DiscoveredLink {
+                src: String::from("nvim"),
+                target: String::from("~/.config/nvim"),
+                link_dir: true,
+                relative: false,
+            },
+        ];
+
+        let config = parse_yaml_value(&get_discovered_config(&links)).unwrap();
+        let commands = config.as_hash().unwrap()["tasks"].as_hash().unwrap()["dotfiles"]
+            .as_hash()
+            .unwrap()["commands"]
+            .as_vec()
+            .unwrap()
+            .clone();
+
+        assert_eq!(commands.len(), 2);
+        let dir_links = commands[1].as_hash().unwrap()["symlink"].as_hash().unwrap();
+        assert_eq!(dir_links["link_dir"].as_bool(), Some(true));
+        assert_eq!(
+            dir_links["files"].as_hash().unwrap()["nvim"].as_str(),
+            Some("~/.config/nvim")
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use ansi_term::Color::{Red, White};
+use ergo_fs::PathBuf;
+use std::io;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Validation(String),
+
+    #[error("{}: {}", .path.display(), Red.paint(.source.to_string()))]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to spawn {}: {}", White.bold().paint(.program), Red.paint(.source.to_string()))]
+    Spawn {
+        program: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{} exited with {}", White.bold().paint(.command), Red.paint(get_exit_code(.code)))]
+    ExitStatus { command: String, code: Option<i32> },
+
+    #[error("Config error: {0}")]
+    Config(String),
+
+    #[error("{0}")]
+    Command(String),
+
+    #[error("{} {}", Red.paint("Verification failed:"), .0)]
+    Verification(String),
+
+    #[error("{} > {}: {}", White.bold().paint(.task), White.bold().paint(.command), .source)]
+    Context {
+        task: String,
+        command: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("Task {} {}", White.on(Red).paint(format!(" {} ", .0)), Red.paint("not found"))]
+    TaskNotFound(String),
+
+    #[error("Task {} {}", White.on(Red).paint(format!(" {} ", .0)), Red.paint("failed"))]
+    TaskFailed(String),
+
+    #[error(
+        "{} {} {}\n{}",
+        Red.paint("Errors occurred in"),
+        Red.bold().underline().paint(.0.len().to_string()),
+        Red.paint("tasks:"),
+        .0.iter().map(|task| format!("> {task}")).collect::<Vec<String>>().join("\n")
+    )]
+    TasksFailed(Vec<String>),
+
+    #[error(
+        "{} {} {}\n{}",
+        Red.paint("Errors occurred on"),
+        Red.bold().underline().paint(.0.len().to_string()),
+        Red.paint("hosts:"),
+        .0.iter().map(|host| format!("> {host}")).collect::<Vec<String>>().join("\n")
+    )]
+    HostsFailed(Vec<String>),
+}
+
+fn get_exit_code(code: &Option<i32>) -> String {
+    match code {
+        Some(code) => format!("code {code}"),
+        None => String::from("a signal"),
+    }
+}
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /**
+     * The message without the prefix of the variant, e.g. to prepend the key of the config it belongs to.
+     */
+    pub fn into_message(self) -> String {
+        match self {
+            Error::Validation(message) | Error::Config(message) | Error::Command(message) => {
+                message
+            }
+            err => err.to_string(),
+        }
+    }
+
+    pub fn with_context(self, task: &str, command: &str) -> Self {
+        Error::Context {
+            task: task.to_string(),
+            command: command.to_string(),
+            source: Box::new(self),
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_adds_task_and_command_context() {
+        let err = Error::Command(String::from("something went wrong")).with_context("git", "copy");
+
+        assert!(matches!(err, Error::Context { .. }));
+        let message = err.to_string();
+        assert!(message.contains("git"));
+        assert!(message.contains("copy"));
+        assert!(message.contains("something went wrong"));
+    }
+
+    #[test]
+    fn it_strips_the_prefix_of_config_errors() {
+        let err = Error::Config(String::from("network: must be an object"));
+
+        assert!(err.to_string().starts_with("Config error: "));
+        assert_eq!(err.into_message(), "network: must be an object");
+    }
+
+    #[test]
+    fn it_keeps_the_io_error_as_source() {
+        let err = Error::io(
+            "/tmp/test.txt",
+            io::Error::new(io::ErrorKind::NotFound, "not found"),
+        );
+
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(err.to_string().contains("/tmp/test.txt"));
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::task_runner::{TaskRunnerMode, TaskStatus};
+
+/**
+ * Everything that happens during a run, in the order it happens.
+ * Commands of parallel tasks are interleaved, so events carry the task and command name.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    RunStarted {
+        mode: TaskRunnerMode,
+        num_tasks: usize,
+    },
+    TaskStarted {
+        task: String,
+        num_commands: usize,
+    },
+    CommandStarted {
+        task: String,
+        command: String,
+    },
+    CommandOutput {
+        task: String,
+        command: String,
+        message: String,
+    },
+    /** e.g. the number of files that were processed so far */
+    CommandProgress {
+        task: String,
+        command: String,
+        position: usize,
+        length: usize,
+    },
+    CommandFinished {
+        task: String,
+        command: String,
+        error: Option<String>,
+    },
+    TaskFinished {
+        task: String,
+        status: TaskStatus,
+    },
+    RunFinished {
+        success: bool,
+    },
+}
+
+/**
+ * Frontends implement this to display the progress of a run, see `utils::progress::IndicatifReporter`.
+ * Events are reported from the worker threads, so implementations have to synchronize themselves.
+ */
+pub trait Reporter: Send + Sync + fmt::Debug {
+    fn report(&self, event: &Event);
+}
+
+#[derive(Debug, Default)]
+pub struct SilentReporter {}
+
+impl Reporter for SilentReporter {
+    fn report(&self, _event: &Event) {}
+}
+
+/**
+ * Handed to every command, so commands only report messages and don't know how they are displayed.
+ */
+#[derive(Debug, Clone)]
+pub struct Progress {
+    reporter: Arc<dyn Reporter>,
+    task: String,
+    command: String,
+    message: Arc<Mutex<String>>,
+}
+
+impl Progress {
+    pub fn new(reporter: Arc<dyn Reporter>, task: &str, command: &str) -> Self {
+        Progress {
+            reporter,
+            task: task.to_string(),
+            command: command.to_string(),
+            message: Arc::new(Mutex::new(String::new())),
+        }
+    }
+
+    pub fn set_message(&self, message: impl Into<String>) {
+        let message = message.into();
+        *self.message.lock().unwrap() = message.clone();
+
+        self.reporter.report(&Event::CommandOutput {
+            task: self.task.to_string(),
+            command: self.command.to_string(),
+            message,
+        });
+    }
+
+    pub fn get_task(&self) -> &str {
+        &self.task
+    }
+
+    pub fn set_position(&self, position: usize, length: usize) {
+        self.reporter.report(&Event::CommandProgress {
+            task: self.task.to_string(),
+            command: self.command.to_string(),
+            position,
+            length,
+        });
+    }
+
+    /**
+     * The last message that was reported.
+     */
+    pub fn message(&self) -> String {
+        self.message.lock().unwrap().to_string()
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress::new(Arc::new(SilentReporter::default()), "", "")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingReporter {
+        events: Mutex<Vec<Event>>,
+    }
+
+    impl Reporter for RecordingReporter {
+        fn report(&self, event: &Event) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn it_reports_command_output() {
+        let reporter = Arc::new(RecordingReporter::default());
+        let progress = Progress::new(reporter.clone(), "git", "clone");
+
+        progress.set_message("Cloning ...");
+
+        assert_eq!(progress.message(), "Cloning ...");
+        assert_eq!(
+            *reporter.events.lock().unwrap(),
+            vec![Event::CommandOutput {
+                task: String::from("git"),
+                command: String::from("clone"),
+                message: String::from("Cloning ..."),
+            }]
+        );
+    }
+}
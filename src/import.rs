@@ -0,0 +1,541 @@
+use ergo_fs::{Path, PathBuf};
+use std::fs::{read_dir, read_to_string};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+static TEMPLATE_SUFFIX: &str = ".tmpl";
+static LITERAL_SUFFIX: &str = ".literal";
+
+/**
+ * Prefixes that only change how chezmoi writes the target, the content is copied as is.
+ */
+static IGNORED_ATTRIBUTES: [&str; 3] = ["readonly_", "empty_", "external_"];
+
+/**
+ * Prefixes whose behavior has no equivalent in machine_setup.
+ */
+static UNSUPPORTED_ATTRIBUTES: [&str; 5] =
+    ["encrypted_", "exact_", "create_", "modify_", "remove_"];
+
+static SCRIPT_ATTRIBUTES: [&str; 4] = ["once_", "onchange_", "before_", "after_"];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SourceName {
+    target: String,
+    symlink: bool,
+    executable: bool,
+    /** only accessible by the owner */
+    private: bool,
+    template: bool,
+    unsupported: Option<String>,
+}
+
+/**
+ * Strips the chezmoi attributes from a file or directory name, e.g. "private_dot_ssh" -> ".ssh".
+ */
+fn parse_source_name(name: &str) -> SourceName {
+    let mut source_name = SourceName::default();
+    let mut rest = name;
+
+    if let Some(stripped) = rest.strip_suffix(LITERAL_SUFFIX) {
+        rest = stripped;
+    } else if let Some(stripped) = rest.strip_suffix(TEMPLATE_SUFFIX) {
+        source_name.template = true;
+        rest = stripped;
+    }
+
+    loop {
+        if let Some(attribute) = UNSUPPORTED_ATTRIBUTES.iter().find(|a| rest.starts_with(*a)) {
+            source_name.unsupported = Some(attribute.trim_end_matches('_').to_string());
+            rest = &rest[attribute.len()..];
+        } else if let Some(attribute) = IGNORED_ATTRIBUTES.iter().find(|a| rest.starts_with(*a)) {
+            rest = &rest[attribute.len()..];
+        } else if let Some(stripped) = rest.strip_prefix("private_") {
+            source_name.private = true;
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix("executable_") {
+            source_name.executable = true;
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix("symlink_") {
+            source_name.symlink = true;
+            rest = stripped;
+        } else {
+            break;
+        }
+    }
+
+    source_name.target = if let Some(stripped) = rest.strip_prefix("literal_") {
+        stripped.to_string()
+    } else if let Some(stripped) = rest.strip_prefix("dot_") {
+        format!(".{stripped}")
+    } else {
+        rest.to_string()
+    };
+
+    source_name
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedFile {
+    /** relative to the chezmoi source directory */
+    pub src: String,
+    /** e.g. "~/.zshrc" */
+    pub target: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedSymlink {
+    /** where the link points to, the content of the chezmoi source file */
+    pub link: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedScript {
+    pub command: String,
+    pub after: bool,
+}
+
+/**
+ * Everything that was found in a chezmoi source directory.
+ * Entries that can't be translated are kept in `skipped`, so they can be converted by hand.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ChezmoiImport {
+    pub source_dir: PathBuf,
+    pub files: Vec<ImportedFile>,
+    pub symlinks: Vec<ImportedSymlink>,
+    /** targets that chezmoi makes executable */
+    pub executables: Vec<String>,
+    /** file targets that are only accessible by the owner */
+    pub private_files: Vec<String>,
+    /** directory targets that are only accessible by the owner */
+    pub private_dirs: Vec<String>,
+    pub scripts: Vec<ImportedScript>,
+    pub skipped: Vec<String>,
+}
+
+/**
+ * Scripts are run with the interpreter of their shebang, the source files are not necessarily executable.
+ */
+fn get_script_command(path: &Path) -> Result<String> {
+    let contents = read_to_string(path).map_err(|e| Error::io(path, e))?;
+    let path = format!("\"{}\"", path.display());
+
+    match contents
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+    {
+        Some(interpreter) => Ok(format!("{} {path}", interpreter.trim())),
+        None => Ok(format!("sh {path}")),
+    }
+}
+
+fn import_script(
+    import: &mut ChezmoiImport,
+    path: &Path,
+    relative: &str,
+    name: &str,
+) -> Result<()> {
+    let mut rest = name;
+    let mut after = false;
+    while let Some(attribute) = SCRIPT_ATTRIBUTES.iter().find(|a| rest.starts_with(*a)) {
+        after = after || *attribute == "after_";
+        rest = &rest[attribute.len()..];
+    }
+
+    if name.ends_with(TEMPLATE_SUFFIX) {
+        import.skipped.push(format!(
+            "{relative}: templates have to be converted by hand"
+        ));
+        return Ok(());
+    }
+
+    import.scripts.push(ImportedScript {
+        command: get_script_command(path)?,
+        after,
+    });
+
+    Ok(())
+}
+
+fn import_dir(import: &mut ChezmoiImport, dir: &Path, relative: &str, target: &str) -> Result<()> {
+    let mut entries: Vec<PathBuf> = read_dir(dir)
+        .map_err(|e| Error::io(dir, e))?
+        .filter_map(|entry| {
+            entry
+                .map_err(|err_entry| debug!("Skipping an entry of {}: {err_entry}", dir.display()))
+                .ok()
+        })
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let relative = match relative {
+            "" => name.to_string(),
+            _ => format!("{relative}/{name}"),
+        };
+
+        // chezmoi ignores all other hidden files in the source directory
+        if name.starts_with('.') {
+            if name.starts_with(".chezmoi") && !name.starts_with(".chezmoiroot") {
+                import.skipped.push(format!(
+                    "{relative}: chezmoi specific files are not imported"
+                ));
+            }
+            continue;
+        }
+
+        if let Some(script_name) = name.strip_prefix("run_") {
+            import_script(import, &path, &relative, script_name)?;
+            continue;
+        }
+
+        let source_name = parse_source_name(&name);
+        let entry_target = format!("{target}/{}", source_name.target);
+
+        if let Some(attribute) = source_name.unsupported {
+            import
+                .skipped
+                .push(format!("{relative}: {attribute} is not supported"));
+            continue;
+        }
+
+        if path.is_dir() {
+            if source_name.private {
+                import.private_dirs.push(entry_target.to_string());
+            }
+            import_dir(import, &path, &relative, &entry_target)?;
+        } else if source_name.template {
+            import.skipped.push(format!(
+                "{relative}: templates have to be converted by hand"
+            ));
+        } else if source_name.symlink {
+            let link = read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+            import.symlinks.push(ImportedSymlink {
+                link: link.trim().to_string(),
+                target: entry_target,
+            });
+        } else {
+            if source_name.executable {
+                import.executables.push(entry_target.to_string());
+            }
+            if source_name.private {
+                import.private_files.push(entry_target.to_string());
+            }
+            import.files.push(ImportedFile {
+                src: relative,
+                target: entry_target,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+pub fn import_chezmoi(source_dir: &Path) -> Result<ChezmoiImport> {
+    let source_dir = source_dir
+        .canonicalize()
+        .map_err(|e| Error::io(source_dir, e))?;
+
+    // .chezmoiroot moves the source state into a subdirectory
+    let root_file = source_dir.join(".chezmoiroot");
+    let root_dir = match read_to_string(&root_file) {
+        Ok(root) => source_dir.join(root.trim()),
+        Err(_) => source_dir.clone(),
+    };
+
+    let mut import = ChezmoiImport {
+        source_dir: root_dir.clone(),
+        ..Default::default()
+    };
+
+    import_dir(&mut import, &root_dir, "", "~")?;
+
+    Ok(import)
+}
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/**
+ * chezmoi resolves relative links from the directory of the link, e.g. "~/.vimrc" -> ".config/nvim/init.vim".
+ */
+fn get_link_source(symlink: &ImportedSymlink) -> String {
+    if Path::new(&symlink.link).is_absolute() || symlink.link.starts_with('~') {
+        return symlink.link.to_string();
+    }
+
+    match symlink.target.rsplit_once('/') {
+        Some((dir, _)) => format!("{dir}/{}", symlink.link),
+        None => symlink.link.to_string(),
+    }
+}
+
+/**
+ * Scripts only run on install, like `run_once_` in chezmoi.
+ */
+fn push_scripts(lines: &mut Vec<String>, task: &str, scripts: &[&ImportedScript]) {
+    if scripts.is_empty() {
+        return;
+    }
+
+    lines.push(format!("  {task}:"));
+    lines.push(String::from("    commands:"));
+    lines.push(String::from("      - run:"));
+    lines.push(String::from("          commands:"));
+    lines.push(String::from("            install:"));
+    for script in scripts {
+        lines.push(format!("              - {}", quote(&script.command)));
+    }
+}
+
+fn push_copy(
+    lines: &mut Vec<String>,
+    source_dir: &Path,
+    files: &[&ImportedFile],
+    mode: Option<&str>,
+) {
+    if files.is_empty() {
+        return;
+    }
+
+    lines.push(String::from("      - copy:"));
+    lines.push(format!(
+        "          src: {}",
+        quote(&source_dir.to_string_lossy())
+    ));
+    if let Some(mode) = mode {
+        lines.push(format!("          mode: {}", quote(mode)));
+    }
+    lines.push(String::from("          files:"));
+    for file in files {
+        lines.push(format!(
+            "            {}: {}",
+            quote(&file.src),
+            quote(&file.target)
+        ));
+    }
+}
+
+/**
+ * The copy command doesn't change the mode of directories, so private directories are restricted afterwards.
+ */
+fn push_private_dirs(lines: &mut Vec<String>, dirs: &[String]) {
+    if dirs.is_empty() {
+        return;
+    }
+
+    lines.push(String::from("      - run:"));
+    lines.push(String::from("          commands:"));
+    lines.push(String::from("            install:"));
+    for dir in dirs {
+        let dir = match dir.strip_prefix('~') {
+            Some(rest) => format!("$HOME{rest}"),
+            None => dir.to_string(),
+        };
+        lines.push(format!(
+            "              - {}",
+            quote(&format!("chmod 700 \"{dir}\""))
+        ));
+    }
+}
+
+/**
+ * Scripts that run before or after the files are written end up in separate tasks around the dotfiles task.
+ */
+pub fn get_import_config(import: &ChezmoiImport) -> String {
+    let mut lines = vec![
+        format!("# imported from {}", import.source_dir.display()),
+        String::from("tasks:"),
+    ];
+
+    let (after, before): (Vec<&ImportedScript>, Vec<&ImportedScript>) =
+        import.scripts.iter().partition(|script| script.after);
+
+    push_scripts(&mut lines, "chezmoi_before", &before);
+
+    if !import.files.is_empty() || !import.symlinks.is_empty() {
+        lines.push(String::from("  dotfiles:"));
+        lines.push(String::from("    commands:"));
+    }
+
+    // chezmoi writes private files with 0600 and private executables with 0700
+    for (executable, private, mode) in [
+        (false, false, None),
+        (true, false, Some("0755")),
+        (false, true, Some("0600")),
+        (true, true, Some("0700")),
+    ] {
+        let files: Vec<&ImportedFile> = import
+            .files
+            .iter()
+            .filter(|file| {
+                import.executables.contains(&file.target) == executable
+                    && import.private_files.contains(&file.target) == private
+            })
+            .collect();
+        push_copy(&mut lines, &import.source_dir, &files, mode);
+    }
+
+    push_private_dirs(&mut lines, &import.private_dirs);
+
+    if !import.symlinks.is_empty() {
+        lines.push(String::from("      - symlink:"));
+        lines.push(String::from("          files:"));
+        for symlink in &import.symlinks {
+            lines.push(format!(
+                "            {}: {}",
+                quote(&get_link_source(symlink)),
+                quote(&symlink.target)
+            ));
+        }
+    }
+
+    push_scripts(&mut lines, "chezmoi_after", &after);
+
+    if !import.skipped.is_empty() {
+        lines.push(String::new());
+        lines.push(String::from("# not imported:"));
+        for skipped in &import.skipped {
+            lines.push(format!("# - {skipped}"));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{create_dir_all, write};
+
+    use crate::config::yaml_config::parse_yaml_value;
+
+    use super::*;
+
+    #[test]
+    fn it_parses_source_names() {
+        assert_eq!(parse_source_name("dot_zshrc").target, ".zshrc");
+        assert_eq!(parse_source_name("private_dot_ssh").target, ".ssh");
+        assert!(parse_source_name("private_dot_ssh").private);
+        assert_eq!(
+            parse_source_name("executable_literal_dot_keep").target,
+            "dot_keep"
+        );
+
+        let template = parse_source_name("dot_gitconfig.tmpl");
+        assert_eq!(template.target, ".gitconfig");
+        assert!(template.template);
+
+        let symlink = parse_source_name("symlink_dot_vimrc");
+        assert_eq!(symlink.target, ".vimrc");
+        assert!(symlink.symlink);
+
+        assert_eq!(
+            parse_source_name("exact_dot_config").unsupported,
+            Some(String::from("exact"))
+        );
+    }
+
+    #[test]
+    fn it_imports_a_chezmoi_source_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        create_dir_all(path.join("private_dot_ssh")).unwrap();
+        write(path.join("private_dot_ssh/config"), "Host *").unwrap();
+        write(path.join("dot_zshrc"), "export EDITOR=nvim").unwrap();
+        write(path.join("executable_dot_profile"), "export PATH").unwrap();
+        write(path.join("private_dot_netrc"), "machine example.com").unwrap();
+        write(path.join("dot_gitconfig.tmpl"), "{{ .email }}").unwrap();
+        write(path.join("symlink_dot_vimrc"), ".config/nvim/init.vim\n").unwrap();
+        write(
+            path.join("run_once_before_install.sh"),
+            "#!/bin/bash\necho hi",
+        )
+        .unwrap();
+        write(path.join(".chezmoiignore"), "README.md").unwrap();
+
+        let import = import_chezmoi(path).unwrap();
+
+        assert_eq!(
+            import.files,
+            vec![
+                ImportedFile {
+                    src: String::from("dot_zshrc"),
+                    target: String::from("~/.zshrc"),
+                },
+                ImportedFile {
+                    src: String::from("executable_dot_profile"),
+                    target: String::from("~/.profile"),
+                },
+                ImportedFile {
+                    src: String::from("private_dot_netrc"),
+                    target: String::from("~/.netrc"),
+                },
+                ImportedFile {
+                    src: String::from("private_dot_ssh/config"),
+                    target: String::from("~/.ssh/config"),
+                },
+            ]
+        );
+        assert_eq!(import.symlinks[0].target, "~/.vimrc");
+        assert_eq!(import.executables, vec![String::from("~/.profile")]);
+        assert_eq!(import.private_files, vec![String::from("~/.netrc")]);
+        assert_eq!(import.private_dirs, vec![String::from("~/.ssh")]);
+        assert!(import.scripts[0].command.starts_with("/bin/bash "));
+        assert!(!import.scripts[0].after);
+        assert_eq!(import.skipped.len(), 2);
+
+        let config = get_import_config(&import);
+        let tasks = parse_yaml_value(&config).unwrap();
+        let tasks = tasks.as_hash().unwrap()["tasks"].as_hash().unwrap();
+        assert!(tasks.contains_key("chezmoi_before"));
+        assert!(tasks.contains_key("dotfiles"));
+        assert!(!tasks.contains_key("chezmoi_after"));
+        assert!(config.contains("# - dot_gitconfig.tmpl"));
+
+        let before = tasks["chezmoi_before"].as_hash().unwrap()["commands"]
+            .as_vec()
+            .unwrap()[0]
+            .as_hash()
+            .unwrap()["run"]
+            .as_hash()
+            .unwrap()["commands"]
+            .clone();
+        assert!(before.as_hash().unwrap().contains_key("install"));
+
+        let dotfiles = tasks["dotfiles"].as_hash().unwrap()["commands"]
+            .as_vec()
+            .unwrap()
+            .clone();
+        let executables = dotfiles[1].as_hash().unwrap()["copy"].as_hash().unwrap();
+        assert_eq!(executables["mode"].as_str(), Some("0755"));
+        assert!(executables["files"]
+            .as_hash()
+            .unwrap()
+            .contains_key("executable_dot_profile"));
+        let private_files = dotfiles[2].as_hash().unwrap()["copy"].as_hash().unwrap();
+        assert_eq!(private_files["mode"].as_str(), Some("0600"));
+        assert!(private_files["files"]
+            .as_hash()
+            .unwrap()
+            .contains_key("private_dot_netrc"));
+        let private_dirs = dotfiles[3].as_hash().unwrap()["run"].as_hash().unwrap()["commands"]
+            .as_hash()
+            .unwrap()["install"]
+            .as_vec()
+            .unwrap()
+            .clone();
+        assert_eq!(private_dirs[0].as_str(), Some("chmod 700 \"$HOME/.ssh\""));
+        let symlinks = dotfiles[4].as_hash().unwrap()["symlink"].as_hash().unwrap();
+        assert_eq!(
+            symlinks["files"].as_hash().unwrap()["~/.config/nvim/init.vim"].as_str(),
+            Some("~/.vimrc")
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use ergo_fs::{Path, PathDir};
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    config::{base_config::read_config_value, config_value::ConfigValue},
+    error::{Error, Result},
+    utils::{ssh::SshTarget, variables::is_variable_name},
+};
+
+/**
+ * A machine from the inventory.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Host {
+    pub name: String,
+    pub target: SshTarget,
+    pub groups: Vec<String>,
+    /** config file that is used for this host instead of the current one */
+    pub profile: Option<String>,
+    /** passed to the tasks as environment variables and as variables for `{{ }}` placeholders */
+    pub vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inventory {
+    pub hosts: Vec<Host>,
+    pub parallel: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostSelection {
+    All,
+    Group(String),
+    Host(String),
+}
+
+fn get_var_value(value: &ConfigValue) -> Option<String> {
+    match value {
+        ConfigValue::String(value) => Some(value.to_string()),
+        ConfigValue::Integer(value) => Some(value.to_string()),
+        ConfigValue::Float(value) => Some(value.to_string()),
+        ConfigValue::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn get_host(name: &str, value: &ConfigValue) -> Result<Host, String> {
+    let empty = ConfigValue::Hash(Default::default());
+    let value = if value.is_null() { &empty } else { value };
+    let values = value
+        .as_hash()
+        .ok_or_else(|| format!("hosts.{name}: must be an object"))?;
+
+    let address = match values.get("address") {
+        None => name,
+        Some(address) => address
+            .as_str()
+            .ok_or_else(|| format!("hosts.{name}.address: must be a string"))?,
+    };
+    let target = SshTarget::from_str(address).map_err(|err| format!("hosts.{name}: {err}"))?;
+
+    let groups = match values.get("groups") {
+        None => vec![],
+        Some(ConfigValue::String(group)) => vec![group.to_string()],
+        Some(ConfigValue::Array(groups)) => groups
+            .iter()
+            .map(|group| group.as_str().map(|group| group.to_string()))
+            .collect::<Option<Vec<String>>>()
+            .ok_or_else(|| format!("hosts.{name}.groups: must be a list of strings"))?,
+        Some(_) => return Err(format!("hosts.{name}.groups: must be a list of strings")),
+    };
+
+    let profile = match values.get("profile") {
+        None => None,
+        Some(profile) => Some(
+            profile
+                .as_str()
+                .ok_or_else(|| format!("hosts.{name}.profile: must be a string"))?
+                .to_string(),
+        ),
+    };
+
+    let mut vars = BTreeMap::new();
+    if let Some(var_values) = values.get("vars") {
+        let var_values = var_values
+            .as_hash()
+            .ok_or_else(|| format!("hosts.{name}.vars: must be an object"))?;
+
+        for (key, value) in var_values {
+            if !is_variable_name(key) {
+                return Err(format!(
+                    "hosts.{name}.vars.{key}: must be a valid variable name"
+                ));
+            }
+            let value = get_var_value(value)
+                .ok_or_else(|| format!("hosts.{name}.vars.{key}: must be a scalar value"))?;
+            vars.insert(key.to_string(), value);
+        }
+    }
+
+    Ok(Host {
+        name: name.to_string(),
+        target,
+        groups,
+        profile,
+        vars,
+    })
+}
+
+fn parse_inventory(value: &ConfigValue) -> Result<Inventory, String> {
+    let values = value
+        .as_hash()
+        .ok_or_else(|| String::from("inventory: must be an object"))?;
+
+    let host_values = values
+        .get("hosts")
+        .and_then(|hosts| hosts.as_hash())
+        .ok_or_else(|| String::from("hosts: must be an object"))?;
+
+    let mut hosts = host_values
+        .iter()
+        .map(|(name, value)| get_host(name, value))
+        .collect::<Result<Vec<Host>, String>>()?;
+    hosts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let parallel = match values.get("parallel") {
+        None => false,
+        Some(parallel) => parallel
+            .as_bool()
+            .ok_or_else(|| String::from("parallel: must be true or false"))?,
+    };
+
+    Ok(Inventory { hosts, parallel })
+}
+
+impl Inventory {
+    pub fn from_config_value(value: &ConfigValue) -> Result<Self> {
+        parse_inventory(value).map_err(Error::Config)
+    }
+
+    pub fn get_host(&self, name: &str) -> Option<&Host> {
+        self.hosts.iter().find(|host| host.name == name)
+    }
+
+    pub fn get_hosts(&self, selection: &HostSelection) -> Result<Vec<Host>> {
+        let hosts: Vec<Host> = self
+            .hosts
+            .iter()
+            .filter(|host| match selection {
+                HostSelection::All => true,
+                HostSelection::Group(group) => host.groups.contains(group),
+                HostSelection::Host(name) => &host.name == name,
+            })
+            .cloned()
+            .collect();
+
+        if hosts.is_empty() {
+            return Err(Error::Config(match selection {
+                HostSelection::All => String::from("The inventory doesn't contain any hosts"),
+                HostSelection::Group(group) => {
+                    format!("The inventory doesn't contain any hosts in group {group}")
+                }
+                HostSelection::Host(name) => format!("The inventory doesn't contain host {name}"),
+            }));
+        }
+
+        Ok(hosts)
+    }
+}
+
+/**
+ * The path of the inventory is relative to the config directory.
+ */
+pub fn load_inventory(path: &str, config_dir: &PathDir) -> Result<Inventory> {
+    let inventory_path = if Path::new(path).is_absolute() {
+        Path::new(path).to_path_buf()
+    } else {
+        config_dir.join(path).to_path_buf()
+    };
+    let inventory_path = inventory_path.to_string_lossy().to_string();
+
+    let value = read_config_value(&inventory_path)?;
+
+    Inventory::from_config_value(&value)
+        .map_err(|err| Error::Config(format!("{inventory_path}: {}", err.into_message())))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::config::yaml_config::parse_yaml_value;
+
+    fn get_inventory() -> Inventory {
+        let value = parse_yaml_value(
+            r#"
+parallel: true
+hosts:
+  laptop:
+  homeserver:
+    address: timo@homeserver:2222
+    groups: [servers]
+    profile: server.yaml
+    vars:
+      HOSTNAME: homeserver
+      SWAP_SIZE: 4
+  vm1:
+    address: root@192.168.122.10
+    groups: servers
+"#,
+        )
+        .unwrap();
+
+        Inventory::from_config_value(&value).unwrap()
+    }
+
+    #[test]
+    fn it_parses_the_inventory() {
+        let inventory = get_inventory();
+
+        assert!(inventory.parallel);
+        assert_eq!(
+            inventory
+                .hosts
+                .iter()
+                .map(|host| host.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["homeserver", "laptop", "vm1"]
+        );
+
+        let homeserver = inventory.get_host("homeserver").unwrap();
+        assert_eq!(homeserver.target.to_string(), "timo@homeserver");
+        assert_eq!(homeserver.target.port, Some(2222));
+        assert_eq!(homeserver.profile, Some(String::from("server.yaml")));
+        assert_eq!(homeserver.vars.get("SWAP_SIZE"), Some(&String::from("4")));
+
+        assert_eq!(inventory.get_host("laptop").unwrap().target.host, "laptop");
+    }
+
+    #[test]
+    fn it_selects_hosts() {
+        let inventory = get_inventory();
+
+        assert_eq!(inventory.get_hosts(&HostSelection::All).unwrap().len(), 3);
+        assert_eq!(
+            inventory
+                .get_hosts(&HostSelection::Group(String::from("servers")))
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(inventory
+            .get_hosts(&HostSelection::Group(String::from("desktops")))
+            .is_err());
+    }
+
+    #[test]
+    fn it_fails_for_invalid_vars() {
+        let mut host = HashMap::new();
+        host.insert(
+            String::from("vars"),
+            ConfigValue::Hash(HashMap::from([(
+                String::from("LIST"),
+                ConfigValue::Array(vec![]),
+            )])),
+        );
+        let mut hosts = HashMap::new();
+        hosts.insert(String::from("vm1"), ConfigValue::Hash(host));
+        let mut inventory = HashMap::new();
+        inventory.insert(String::from("hosts"), ConfigValue::Hash(hosts));
+
+        let err = Inventory::from_config_value(&ConfigValue::Hash(inventory)).unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+        assert!(err.to_string().contains("hosts.vm1.vars.LIST"));
+    }
+
+    #[test]
+    fn it_fails_for_invalid_var_names() {
+        let err = Inventory::from_config_value(
+            &parse_yaml_value("hosts:\n  vm1:\n    vars:\n      \"SWAP SIZE\": 4\n").unwrap(),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("hosts.vm1.vars.SWAP SIZE"));
+    }
+}
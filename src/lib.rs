@@ -0,0 +1,29 @@
+//! Configure and set up a machine from a YAML or JSON config.
+//!
+//! Besides the `machine_setup` binary, the crate can be embedded into other tools,
+//! see [`Runner`] and [`load_config`].
+
+extern crate tracing;
+
+pub mod bootstrap;
+pub mod command;
+pub mod commands;
+pub mod config;
+pub mod container;
+pub mod discover;
+pub mod error;
+pub mod events;
+pub mod import;
+pub mod inventory;
+pub mod remote;
+pub mod runner;
+pub mod schedule;
+pub mod task;
+pub mod task_runner;
+pub mod terminal;
+pub mod utils;
+
+pub use error::{Error, Result};
+pub use events::{Event, Progress, Reporter, SilentReporter};
+pub use runner::{load_config, Config, Runner};
+pub use task_runner::{RunOptions, RunReport, TaskResult, TaskRunnerMode as Mode, TaskStatus};
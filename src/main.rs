@@ -1,20 +1,17 @@
 extern crate tracing;
 
+use ansi_term::Color::Red;
 use clap::Parser;
+use machine_setup::{
+    terminal::{cli::Args, command::execute_command, exit_codes::EX_FAILURE},
+    utils::{progress::ProgressWriter, temp_storage::register_interrupt_handler},
+};
 use once_cell::sync::OnceCell;
-use terminal::{cli::Args, command::execute_command};
+use std::process::exit;
 use tracing::metadata::LevelFilter;
-use tracing::Level;
+use tracing::{error, Level};
 use tracing_subscriber::prelude::*;
 
-pub mod command;
-pub mod commands;
-pub mod config;
-pub mod task;
-pub mod task_runner;
-pub mod terminal;
-pub mod utils;
-
 static LOG_LEVEL: OnceCell<Level> = OnceCell::new();
 static DEBUG_MODE: OnceCell<bool> = OnceCell::new();
 
@@ -28,6 +25,7 @@ fn main() {
         .with_file(args.debug)
         .with_target(args.debug)
         .with_thread_ids(args.debug)
+        .with_writer(ProgressWriter::default)
         .without_time();
 
     let subscriber = tracing_subscriber::registry()
@@ -42,5 +40,10 @@ fn main() {
     LOG_LEVEL.set(args.level).unwrap();
     DEBUG_MODE.set(args.debug).unwrap();
 
-    execute_command(args)
+    register_interrupt_handler();
+
+    if let Err(err_command) = execute_command(args) {
+        error!("{}", Red.paint(err_command.to_string()));
+        exit(EX_FAILURE);
+    }
 }
@@ -0,0 +1,345 @@
+use ansi_term::Color::{Red, White};
+use ergo_fs::{Path, PathDir};
+use std::{collections::BTreeMap, env, io::IsTerminal, thread};
+use tracing::{error, info, Level};
+
+use crate::{
+    error::{Error, Result},
+    inventory::Host,
+    task_runner::TaskRunnerMode,
+    utils::{
+        shell::quote,
+        ssh::{self, SshTarget},
+    },
+};
+
+const REMOTE_BINARY: &str = "machine_setup";
+
+/**
+ * Everything that is forwarded to the machine_setup instance on the remote machine.
+ */
+#[derive(Debug, Clone)]
+pub struct RemoteRun {
+    pub mode: TaskRunnerMode,
+    /** file name of the config, relative to the config directory */
+    pub config_file: String,
+    pub task: Option<String>,
+    pub tag: Option<String>,
+    pub frozen: bool,
+    pub purge: bool,
+    pub force: bool,
+    pub reboot: bool,
+    pub debug: bool,
+    pub level: Level,
+    /** passed to the remote machine_setup as environment variables and with `--var` */
+    pub vars: BTreeMap<String, String>,
+}
+
+fn normalize_os(os: &str) -> String {
+    match os.to_lowercase().as_str() {
+        "darwin" => String::from("macos"),
+        os => os.to_string(),
+    }
+}
+
+fn normalize_arch(arch: &str) -> String {
+    match arch {
+        "amd64" => String::from("x86_64"),
+        "arm64" => String::from("aarch64"),
+        arch => arch.to_string(),
+    }
+}
+
+/**
+ * Compares the output of `uname -sm` with the platform this binary was built for.
+ */
+fn is_same_platform(uname: &str) -> bool {
+    let mut parts = uname.split_whitespace();
+
+    match (parts.next(), parts.next()) {
+        (Some(os), Some(arch)) => {
+            normalize_os(os) == env::consts::OS && normalize_arch(arch) == env::consts::ARCH
+        }
+        _ => false,
+    }
+}
+
+/**
+ * Compares the output of `machine_setup --version` with the version of this binary.
+ */
+fn is_same_version(version: &str) -> bool {
+    version.split_whitespace().last() == Some(env!("CARGO_PKG_VERSION"))
+}
+
+/**
+ * An installed machine_setup of the same version is preferred, otherwise this binary is copied over.
+ * Other versions might not know all of the forwarded flags.
+ */
+fn get_remote_binary(target: &SshTarget, work_dir: &str) -> Result<String> {
+    let installed = ssh::output(target, &format!("command -v {REMOTE_BINARY} || true"))?;
+    if !installed.is_empty() {
+        let version = ssh::output(target, &format!("{} --version || true", quote(&installed)))?;
+        if is_same_version(&version) {
+            return Ok(installed);
+        }
+
+        info!(
+            "{} on {target} is {}, uploading version {} ...",
+            White.bold().paint(&installed),
+            if version.is_empty() {
+                "unknown"
+            } else {
+                version.as_str()
+            },
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    let uname = ssh::output(target, "uname -sm")?;
+    if !is_same_platform(&uname) {
+        return Err(Error::Command(format!(
+            "{REMOTE_BINARY} {} is not installed on {target} and this binary can't run on {uname}",
+            env!("CARGO_PKG_VERSION")
+        )));
+    }
+
+    let binary = env::current_exe().map_err(|err| Error::io(REMOTE_BINARY, err))?;
+    let remote_binary = format!("{work_dir}/{REMOTE_BINARY}");
+    ssh::upload(target, &binary, &remote_binary)?;
+
+    Ok(remote_binary)
+}
+
+fn get_remote_command(run: &RemoteRun, binary: &str, work_dir: &str) -> String {
+    let mut args = vec![];
+
+    if !run.vars.is_empty() {
+        args.push(String::from("env"));
+        args.extend(run.vars.iter().map(|(key, value)| format!("{key}={value}")));
+    }
+
+    args.extend([
+        binary.to_string(),
+        run.mode.to_string(),
+        String::from("--config"),
+        format!("{work_dir}/config/{}", run.config_file),
+        String::from("--level"),
+        run.level.to_string().to_lowercase(),
+    ]);
+
+    for (key, value) in &run.vars {
+        args.push(String::from("--var"));
+        args.push(format!("{key}={value}"));
+    }
+
+    if let Some(task) = &run.task {
+        args.push(String::from("--task"));
+        args.push(task.to_string());
+    }
+    if let Some(tag) = &run.tag {
+        args.push(String::from("--tag"));
+        args.push(tag.to_string());
+    }
+    if run.frozen {
+        args.push(String::from("--frozen"));
+    }
+    if run.force {
+        args.push(String::from("--force"));
+    }
+    if run.reboot {
+        args.push(String::from("--reboot"));
+    }
+    if run.purge {
+        args.push(String::from("--purge"));
+    }
+    if run.debug {
+        args.push(String::from("--debug"));
+    }
+
+    args.iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/**
+ * Copies the config directory (and the binary, if needed) to a temporary directory on the target
+ * and runs the tasks there. The output is streamed back to the local terminal.
+ */
+pub fn run(target: &SshTarget, run: &RemoteRun, config_dir: &PathDir) -> Result<()> {
+    run_on_target(target, run, config_dir, None)
+}
+
+fn run_on_target(
+    target: &SshTarget,
+    run: &RemoteRun,
+    config_dir: &PathDir,
+    prefix: Option<&str>,
+) -> Result<()> {
+    info!("Preparing {} ...", White.bold().paint(target.to_string()));
+
+    let work_dir = ssh::output(target, "mktemp -d")?;
+    if work_dir.is_empty() {
+        return Err(Error::Command(format!(
+            "Failed to create a temporary directory on {target}"
+        )));
+    }
+
+    let result = (|| {
+        ssh::upload(
+            target,
+            Path::new(config_dir.as_path()),
+            &format!("{work_dir}/config"),
+        )?;
+        let binary = get_remote_binary(target, &work_dir)?;
+
+        info!("Running on {} ...", White.bold().paint(target.to_string()));
+        let remote_command = get_remote_command(run, &binary, &work_dir);
+        match prefix {
+            Some(prefix) => ssh::execute_prefixed(target, &remote_command, prefix),
+            None => ssh::execute(target, &remote_command, std::io::stdin().is_terminal()),
+        }
+    })();
+
+    ssh::execute(target, &format!("rm -rf {}", quote(&work_dir)), false).ok();
+
+    result
+}
+
+fn run_on_host(host: &Host, run: &RemoteRun, config_dir: &PathDir, parallel: bool) -> Result<()> {
+    let mut vars = run.vars.clone();
+    vars.extend(host.vars.clone());
+
+    let run = RemoteRun {
+        config_file: host
+            .profile
+            .clone()
+            .unwrap_or_else(|| run.config_file.to_string()),
+        vars,
+        ..run.clone()
+    };
+
+    let prefix = format!("{}", White.bold().paint(format!("[{}]", host.name)));
+    let result = run_on_target(
+        &host.target,
+        &run,
+        config_dir,
+        parallel.then_some(prefix.as_str()),
+    );
+
+    if let Err(err_run) = &result {
+        error!("{} {}", prefix, Red.paint(err_run.to_string()));
+    }
+
+    result
+}
+
+/**
+ * Runs the tasks on every host, one after another or all at once.
+ * A failing host doesn't stop the others.
+ */
+pub fn run_hosts(
+    hosts: &[Host],
+    run: &RemoteRun,
+    config_dir: &PathDir,
+    parallel: bool,
+) -> Result<()> {
+    let results: Vec<(String, Result<()>)> = if parallel {
+        thread::scope(|s| {
+            let handles: Vec<_> = hosts
+                .iter()
+                .map(|host| s.spawn(move || run_on_host(host, run, config_dir, true)))
+                .collect();
+
+            // a panic only fails the host it happened on
+            hosts
+                .iter()
+                .zip(handles)
+                .map(|(host, handle)| {
+                    let result = handle.join().unwrap_or_else(|_| {
+                        let err_panic = Error::Command(format!("{} panicked", host.name));
+                        error!("{}", Red.paint(err_panic.to_string()));
+                        Err(err_panic)
+                    });
+                    (host.name.to_string(), result)
+                })
+                .collect()
+        })
+    } else {
+        hosts
+            .iter()
+            .map(|host| {
+                (
+                    host.name.to_string(),
+                    run_on_host(host, run, config_dir, false),
+                )
+            })
+            .collect()
+    };
+
+    let failed: Vec<String> = results
+        .into_iter()
+        .filter(|(_, result)| result.is_err())
+        .map(|(name, _)| name)
+        .collect();
+
+    if !failed.is_empty() {
+        return Err(Error::HostsFailed(failed));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_builds_the_remote_command() {
+        let run = RemoteRun {
+            mode: TaskRunnerMode::Install,
+            config_file: String::from("machine_setup.yaml"),
+            task: Some(String::from("my task")),
+            tag: None,
+            frozen: true,
+            purge: false,
+            force: false,
+            reboot: false,
+            debug: false,
+            level: Level::WARN,
+            vars: BTreeMap::from([(String::from("HOSTNAME"), String::from("home server"))]),
+        };
+
+        assert_eq!(
+            get_remote_command(&run, "/usr/bin/machine_setup", "/tmp/tmp.x1"),
+            "env 'HOSTNAME=home server' /usr/bin/machine_setup install --config /tmp/tmp.x1/config/machine_setup.yaml --level warn --var 'HOSTNAME=home server' --task 'my task' --frozen"
+        );
+    }
+
+    #[test]
+    fn it_compares_the_remote_platform() {
+        let uname = format!(
+            "{} {}",
+            if env::consts::OS == "macos" {
+                "Darwin"
+            } else {
+                "Linux"
+            },
+            env::consts::ARCH
+        );
+
+        assert_eq!(is_same_platform(&uname), env::consts::OS != "windows");
+        assert!(!is_same_platform("Plan9 mips"));
+        assert!(!is_same_platform(""));
+    }
+
+    #[test]
+    fn it_compares_the_remote_version() {
+        assert!(is_same_version(&format!(
+            "machine_setup {}",
+            env!("CARGO_PKG_VERSION")
+        )));
+        assert!(!is_same_version("machine_setup 0.1.0"));
+        assert!(!is_same_version(""));
+    }
+}
@@ -0,0 +1,154 @@
+use ergo_fs::{expand, Path, PathDir};
+use std::fs::canonicalize;
+
+use crate::{
+    config::base_config::{get_config, resolve_config_path, TaskList},
+    error::{Error, Result},
+    task_runner::{run_with_report, RunOptions, RunReport, TaskRunnerMode},
+};
+
+/**
+ * A loaded config file together with the directory that relative paths are resolved from.
+ */
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub task_list: TaskList,
+    pub config_dir: PathDir,
+    /** file name inside of `config_dir` */
+    pub config_file: String,
+}
+
+pub fn load_config(path: &str) -> Result<Config> {
+    let config_path = expand(path)
+        .map_err(|e| Error::Config(e.to_string()))?
+        .to_string();
+
+    let config_path = resolve_config_path(&config_path)?;
+    let task_list = get_config(&config_path)?;
+
+    let parent_path = Path::new(&config_path)
+        .parent()
+        .ok_or_else(|| Error::Config(String::from("The parent path is invalid")))?;
+    let parent_path = if parent_path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent_path
+    };
+
+    let absolute_path = canonicalize(parent_path).map_err(|e| Error::io(parent_path, e))?;
+    let config_dir = PathDir::new(&absolute_path).map_err(|e| Error::Config(e.to_string()))?;
+
+    let config_file = Path::new(&config_path)
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .ok_or_else(|| Error::Config(String::from("The config path is invalid")))?;
+
+    Ok(Config {
+        task_list,
+        config_dir,
+        config_file,
+    })
+}
+
+/**
+ * Entry point for embedding machine_setup in other tools instead of shelling out to the CLI.
+ *
+ * ```no_run
+ * use machine_setup::{load_config, Mode, Runner};
+ *
+ * let config = load_config("./machine_setup.yaml").unwrap();
+ * let report = Runner::new(config).run(Mode::Install, None).unwrap();
+ *
+ * for task in report.get_failed_tasks() {
+ *     eprintln!("{} failed", task.name);
+ * }
+ * ```
+ */
+#[derive(Debug, Clone)]
+pub struct Runner {
+    config: Config,
+    options: RunOptions,
+}
+
+impl Runner {
+    pub fn new(config: Config) -> Self {
+        Runner {
+            config,
+            options: RunOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn get_task_names(&self) -> Vec<String> {
+        self.config
+            .task_list
+            .tasks
+            .iter()
+            .map(|task| task.name.to_string())
+            .collect()
+    }
+
+    pub fn run(&self, mode: TaskRunnerMode, task: Option<&str>) -> Result<RunReport> {
+        run_with_report(
+            self.config.task_list.clone(),
+            mode,
+            task.map(|task| task.to_string()),
+            self.config.config_dir.clone(),
+            RunOptions {
+                config_file: Some(self.config.config_file.to_string()),
+                ..self.options.clone()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_runs_a_loaded_config() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("machine_setup.yaml");
+        fs::write(
+            &config_path,
+            format!(
+                "temp_dir: \"{0}\"\nstate_dir: \"{0}\"\ncache_dir: \"{0}\"\ntasks:\n  first:\n    commands:\n      - run:\n          commands: \"true\"\n",
+                dir.path().display()
+            ),
+        )
+        .unwrap();
+
+        let config = load_config(config_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            config.config_dir.as_os_str(),
+            canonicalize(dir.path()).unwrap().as_os_str()
+        );
+
+        let runner = Runner::new(config);
+        assert_eq!(runner.get_task_names(), vec!["first"]);
+
+        let report = runner.run(TaskRunnerMode::Install, Some("first")).unwrap();
+        assert!(report.is_success());
+    }
+
+    #[test]
+    fn it_fails_for_unknown_tasks() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("machine_setup.yaml");
+        fs::write(&config_path, "tasks:\n  first:\n    commands: []\n").unwrap();
+
+        let runner = Runner::new(load_config(config_path.to_str().unwrap()).unwrap());
+
+        assert!(matches!(
+            runner.run(TaskRunnerMode::Install, Some("second")),
+            Err(Error::TaskNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,453 @@
+use ansi_term::Color::White;
+use core::fmt;
+use directories::BaseDirs;
+use ergo_fs::{Path, PathBuf};
+use std::{env, fs, process::Command, str::FromStr};
+use tracing::info;
+
+use crate::error::{Error, Result};
+
+static UNIT_NAME: &str = "machine-setup";
+static LAUNCHD_LABEL: &str = "com.github.timopruesse.machine-setup";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interval {
+    Hour,
+    Day,
+    Week,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Interval::Hour => write!(f, "hour"),
+            Interval::Day => write!(f, "day"),
+            Interval::Week => write!(f, "week"),
+        }
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" | "hourly" => Ok(Interval::Hour),
+            "day" | "daily" => Ok(Interval::Day),
+            "week" | "weekly" => Ok(Interval::Week),
+            _ => Err(format!("Invalid interval: {s} (hour, day or week)")),
+        }
+    }
+}
+
+impl Interval {
+    fn get_seconds(&self) -> u64 {
+        match self {
+            Interval::Hour => 60 * 60,
+            Interval::Day => 24 * 60 * 60,
+            Interval::Week => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/**
+ * A periodic run of `machine_setup <mode> --config <config_file>`.
+ */
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub binary: PathBuf,
+    /** absolute path of the config file */
+    pub config_file: PathBuf,
+    /** install or update */
+    pub mode: String,
+    pub task: Option<String>,
+    pub interval: Interval,
+}
+
+impl Schedule {
+    fn get_args(&self) -> Vec<String> {
+        let mut args = vec![
+            self.binary.display().to_string(),
+            self.mode.to_string(),
+            String::from("--config"),
+            self.config_file.display().to_string(),
+        ];
+
+        if let Some(task) = &self.task {
+            args.push(String::from("--task"));
+            args.push(task.to_string());
+        }
+
+        args
+    }
+}
+
+fn get_home_dir() -> Result<PathBuf> {
+    BaseDirs::new()
+        .map(|dirs| dirs.home_dir().to_path_buf())
+        .ok_or_else(|| Error::Command(String::from("The home directory could not be found")))
+}
+
+fn run_scheduler_command(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|err| Error::Spawn {
+            program: program.to_string(),
+            source: err,
+        })?;
+
+    if !status.success() {
+        return Err(Error::ExitStatus {
+            command: format!("{program} {}", args.join(" ")),
+            code: status.code(),
+        });
+    }
+
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+    }
+
+    fs::write(path, contents).map_err(|err| Error::io(path, err))
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    fs::remove_file(path).map_err(|err| Error::io(path, err))
+}
+
+// systemd (Linux)
+
+fn get_systemd_dir() -> Result<PathBuf> {
+    Ok(get_home_dir()?.join(".config/systemd/user"))
+}
+
+/**
+ * `%` starts a specifier in `ExecStart=`, so it has to be doubled to be kept as is.
+ */
+fn quote_systemd(arg: &str) -> String {
+    format!(
+        "\"{}\"",
+        arg.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+    )
+}
+
+fn get_systemd_service(schedule: &Schedule) -> String {
+    let exec_start = schedule
+        .get_args()
+        .iter()
+        .map(|arg| quote_systemd(arg))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!(
+        "[Unit]\nDescription=machine_setup {}\n\n[Service]\nType=oneshot\nExecStart={exec_start}\n",
+        schedule.mode
+    )
+}
+
+fn get_systemd_timer(schedule: &Schedule) -> String {
+    let calendar = match schedule.interval {
+        Interval::Hour => "hourly",
+        Interval::Day => "daily",
+        Interval::Week => "weekly",
+    };
+
+    // runs that were missed while the machine was off are caught up on the next boot
+    format!(
+        "[Unit]\nDescription=Run machine_setup {} every {}\n\n[Timer]\nOnCalendar={calendar}\nPersistent=true\nRandomizedDelaySec=300\n\n[Install]\nWantedBy=timers.target\n",
+        schedule.mode, schedule.interval
+    )
+}
+
+fn schedule_systemd(schedule: &Schedule) -> Result<PathBuf> {
+    let dir = get_systemd_dir()?;
+    let timer_path = dir.join(format!("{UNIT_NAME}.timer"));
+
+    write_file(
+        &dir.join(format!("{UNIT_NAME}.service")),
+        &get_systemd_service(schedule),
+    )?;
+    write_file(&timer_path, &get_systemd_timer(schedule))?;
+
+    run_scheduler_command("systemctl", &["--user", "daemon-reload"])?;
+    run_scheduler_command(
+        "systemctl",
+        &["--user", "enable", "--now", &format!("{UNIT_NAME}.timer")],
+    )?;
+
+    Ok(timer_path)
+}
+
+fn unschedule_systemd() -> Result<()> {
+    let dir = get_systemd_dir()?;
+    let timer_path = dir.join(format!("{UNIT_NAME}.timer"));
+
+    if timer_path.exists() {
+        run_scheduler_command(
+            "systemctl",
+            &["--user", "disable", "--now", &format!("{UNIT_NAME}.timer")],
+        )?;
+    }
+
+    remove_file(&timer_path)?;
+    remove_file(&dir.join(format!("{UNIT_NAME}.service")))?;
+
+    run_scheduler_command("systemctl", &["--user", "daemon-reload"])
+}
+
+// launchd (macOS)
+
+fn get_launchd_path() -> Result<PathBuf> {
+    Ok(get_home_dir()?
+        .join("Library/LaunchAgents")
+        .join(format!("{LAUNCHD_LABEL}.plist")))
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn get_launchd_plist(schedule: &Schedule, log_file: &Path) -> String {
+    let arguments = schedule
+        .get_args()
+        .iter()
+        .map(|arg| format!("        <string>{}</string>", escape_xml(arg)))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let log_file = escape_xml(&log_file.display().to_string());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}
+    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>StandardOutPath</key>
+    <string>{log_file}</string>
+    <key>StandardErrorPath</key>
+    <string>{log_file}</string>
+</dict>
+</plist>
+"#,
+        schedule.interval.get_seconds()
+    )
+}
+
+fn schedule_launchd(schedule: &Schedule) -> Result<PathBuf> {
+    let plist_path = get_launchd_path()?;
+    let log_file = get_home_dir()?.join(format!("Library/Logs/{UNIT_NAME}.log"));
+
+    // an existing schedule is replaced
+    if plist_path.exists() {
+        run_scheduler_command("launchctl", &["unload", &plist_path.to_string_lossy()]).ok();
+    }
+
+    write_file(&plist_path, &get_launchd_plist(schedule, &log_file))?;
+    run_scheduler_command("launchctl", &["load", "-w", &plist_path.to_string_lossy()])?;
+
+    Ok(plist_path)
+}
+
+fn unschedule_launchd() -> Result<()> {
+    let plist_path = get_launchd_path()?;
+
+    if plist_path.exists() {
+        run_scheduler_command(
+            "launchctl",
+            &["unload", "-w", &plist_path.to_string_lossy()],
+        )?;
+    }
+
+    remove_file(&plist_path)
+}
+
+// Task Scheduler (Windows)
+
+/**
+ * The command of `/TR` is a single string, so arguments with quotes can't be passed safely.
+ */
+fn get_schtasks_args(schedule: &Schedule) -> Result<Vec<String>> {
+    let interval = match schedule.interval {
+        Interval::Hour => "HOURLY",
+        Interval::Day => "DAILY",
+        Interval::Week => "WEEKLY",
+    };
+
+    let command = schedule
+        .get_args()
+        .iter()
+        .map(|arg| {
+            if arg.contains('"') {
+                return Err(Error::Command(format!(
+                    "The Task Scheduler can't run arguments with quotes: {arg}"
+                )));
+            }
+            Ok(format!("\"{arg}\""))
+        })
+        .collect::<Result<Vec<String>>>()?
+        .join(" ");
+
+    Ok(vec![
+        String::from("/Create"),
+        String::from("/F"),
+        String::from("/TN"),
+        UNIT_NAME.to_string(),
+        String::from("/SC"),
+        interval.to_string(),
+        String::from("/TR"),
+        command,
+    ])
+}
+
+fn schedule_task_scheduler(schedule: &Schedule) -> Result<PathBuf> {
+    let args = get_schtasks_args(schedule)?;
+    run_scheduler_command(
+        "schtasks",
+        &args.iter().map(String::as_str).collect::<Vec<&str>>(),
+    )?;
+
+    Ok(PathBuf::from(UNIT_NAME))
+}
+
+fn unschedule_task_scheduler() -> Result<()> {
+    run_scheduler_command("schtasks", &["/Delete", "/F", "/TN", UNIT_NAME])
+}
+
+/**
+ * Installs a timer with the scheduler of the OS (systemd, launchd or the Task Scheduler).
+ * An existing schedule is replaced, so there is only ever one.
+ */
+pub fn schedule(schedule: &Schedule) -> Result<()> {
+    let location = match env::consts::OS {
+        "linux" => schedule_systemd(schedule)?,
+        "macos" => schedule_launchd(schedule)?,
+        "windows" => schedule_task_scheduler(schedule)?,
+        os => {
+            return Err(Error::Command(format!(
+                "Scheduling is not supported on {os}"
+            )))
+        }
+    };
+
+    info!(
+        "Scheduled {} every {} ({})",
+        White.bold().paint(&schedule.mode),
+        White.bold().paint(schedule.interval.to_string()),
+        location.display()
+    );
+
+    Ok(())
+}
+
+pub fn unschedule() -> Result<()> {
+    match env::consts::OS {
+        "linux" => unschedule_systemd(),
+        "macos" => unschedule_launchd(),
+        "windows" => unschedule_task_scheduler(),
+        os => Err(Error::Command(format!(
+            "Scheduling is not supported on {os}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_schedule() -> Schedule {
+        Schedule {
+            binary: PathBuf::from("/usr/local/bin/machine_setup"),
+            config_file: PathBuf::from("/home/timo/my dotfiles/machine_setup.yaml"),
+            mode: String::from("update"),
+            task: None,
+            interval: Interval::Day,
+        }
+    }
+
+    #[test]
+    fn it_creates_systemd_units() {
+        let schedule = get_schedule();
+
+        assert!(get_systemd_service(&schedule).contains(
+            "ExecStart=\"/usr/local/bin/machine_setup\" \"update\" \"--config\" \"/home/timo/my dotfiles/machine_setup.yaml\""
+        ));
+        assert!(get_systemd_timer(&schedule).contains("OnCalendar=daily"));
+    }
+
+    #[test]
+    fn it_creates_a_launchd_plist() {
+        let plist = get_launchd_plist(
+            &Schedule {
+                task: Some(String::from("vim & tmux")),
+                ..get_schedule()
+            },
+            Path::new("/Users/timo/Library/Logs/machine-setup.log"),
+        );
+
+        assert!(plist.contains("<integer>86400</integer>"));
+        assert!(plist.contains("<string>vim &amp; tmux</string>"));
+    }
+
+    #[test]
+    fn it_creates_the_task_scheduler_arguments() {
+        let args = get_schtasks_args(&Schedule {
+            interval: Interval::Week,
+            ..get_schedule()
+        })
+        .unwrap();
+
+        assert!(args.contains(&String::from("WEEKLY")));
+        assert_eq!(
+            args.last().unwrap(),
+            "\"/usr/local/bin/machine_setup\" \"update\" \"--config\" \"/home/timo/my dotfiles/machine_setup.yaml\""
+        );
+    }
+
+    #[test]
+    fn it_escapes_specifiers_in_systemd_units() {
+        let service = get_systemd_service(&Schedule {
+            config_file: PathBuf::from("/home/timo/100%/machine_setup.yaml"),
+            ..get_schedule()
+        });
+
+        assert!(service.contains("\"/home/timo/100%%/machine_setup.yaml\""));
+    }
+
+    #[test]
+    fn it_rejects_quotes_in_task_scheduler_arguments() {
+        let err = get_schtasks_args(&Schedule {
+            task: Some(String::from("say \"hi\"")),
+            ..get_schedule()
+        })
+        .unwrap_err();
+
+        assert!(err.to_string().contains("say \"hi\""));
+    }
+
+    #[test]
+    fn it_parses_intervals() {
+        assert_eq!(Interval::from_str("hourly").unwrap(), Interval::Hour);
+        assert!(Interval::from_str("month").is_err());
+    }
+}
@@ -1,16 +1,22 @@
+use std::collections::BTreeSet;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::{env, str::FromStr};
 
-use ansi_term::Color::{Green, Red, White, Yellow};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use tracing::{debug, error, info};
+use ansi_term::Color::{Red, White, Yellow};
+use tracing::{debug, error, info, info_span};
 
 use crate::{
     command::{get_command, CommandConfig, CommandInterface},
+    commands::download,
     config::{base_config::Command, config_value::ConfigValue, os::Os},
+    error::{Error, Result},
+    events::{Event, Progress, Reporter},
     task_runner::TaskRunnerMode,
-    utils::threads::ThreadPool,
+    utils::{
+        threads::ThreadPool,
+        variables::{collect_referenced_tasks, collect_referenced_tasks_in_str},
+    },
 };
 use dialoguer::{console::Term, theme::ColorfulTheme, Select};
 
@@ -19,12 +25,16 @@ fn run_command(
     args: ConfigValue,
     mode: &TaskRunnerMode,
     config: &CommandConfig,
-    progress: &ProgressBar,
-) -> Result<(), String> {
+    progress: &Progress,
+) -> Result<()> {
     match mode {
         TaskRunnerMode::Install => command.install(args, config, progress),
         TaskRunnerMode::Update => command.update(args, config, progress),
         TaskRunnerMode::Uninstall => command.uninstall(args, config, progress),
+        TaskRunnerMode::Upgrade => Err(Error::Command(
+            "Upgrades are planned per task by the task runner and can't run a command directly"
+                .to_string(),
+        )),
     }
 }
 
@@ -34,6 +44,13 @@ pub struct Task {
     pub commands: Vec<Command>,
     pub os: Vec<Os>,
     pub parallel: bool,
+    pub tags: Vec<String>,
+    /** conditions that all have to hold, see `Variables::evaluate` */
+    pub when: Vec<String>,
+    /** shell command that has to succeed for the task to run */
+    pub only_if: Option<String>,
+    /** shell command that skips the task when it succeeds */
+    pub skip_if: Option<String>,
 }
 
 impl Task {
@@ -41,8 +58,11 @@ impl Task {
         &self,
         mode: TaskRunnerMode,
         config: &CommandConfig,
-        mp: &MultiProgress,
-    ) -> Result<(), String> {
+        reporter: &Arc<dyn Reporter>,
+    ) -> Result<()> {
+        let task_span = info_span!("task", task = %self.name, mode = %mode);
+        let _task_guard = task_span.enter();
+
         if should_skip_task(self) {
             info!(
                 "{}",
@@ -55,10 +75,18 @@ impl Task {
             return Ok(());
         }
 
-        let commands = &self.commands;
+        // commands are undone in reverse, e.g. a service is stopped before its files are removed
+        let mut commands = self.commands.clone();
+        if mode == TaskRunnerMode::Uninstall {
+            commands.reverse();
+        }
 
         let num_threads = if self.parallel { commands.len() } else { 1 };
 
+        if !self.parallel && mode == TaskRunnerMode::Install {
+            prefetch_downloads(&commands, config);
+        }
+
         if self.parallel {
             debug!(
                 "Executing commands in parallel ({} threads)...",
@@ -67,85 +95,78 @@ impl Task {
         }
 
         let task_name = self.name.clone();
-
-        let pb = ProgressBar::new(commands.len().try_into().unwrap()).with_style(
-            ProgressStyle::default_bar()
-                .template("[{bar:50.green/white}] {pos}/{len}: {msg}")
-                .unwrap()
-                .progress_chars("=> "),
-        );
-        let added_pb = mp.add(pb);
-
-        let progress_bar = Arc::new(Mutex::new(added_pb));
         let has_errors = Arc::new(AtomicBool::new(false));
 
         {
             let thread_pool = ThreadPool::new(num_threads);
 
-            for command in commands.clone() {
+            for command in commands {
                 let c = config.clone();
                 let errors = Arc::clone(&has_errors);
-                let progress = Arc::clone(&progress_bar);
+                let reporter = Arc::clone(reporter);
                 let task = task_name.clone();
+                let parent_span = task_span.clone();
 
                 let run = move || {
-                    let p = progress.lock().unwrap();
-                    p.set_message(format!(
-                        "⏳ {}: {}",
-                        &task,
-                        White.bold().paint(&command.name)
-                    ));
-
-                    let resolved_command = get_command(&command.name);
-                    if resolved_command.is_err() {
-                        error!(
-                            "{} {} {}",
-                            Red.paint("Command"),
-                            White.on(Red).paint(format!(" {} ", command.name)),
-                            Red.paint("not found")
-                        );
-
+                    // spans don't cross threads by themselves, so the task span is passed explicitly
+                    let _command_guard = info_span!(
+                        parent: &parent_span,
+                        "command",
+                        command = %command.name,
+                        mode = %mode
+                    )
+                    .entered();
+
+                    reporter.report(&Event::CommandStarted {
+                        task: task.to_string(),
+                        command: command.name.to_string(),
+                    });
+
+                    let result = match get_command(&command.name, &c.config_dir) {
+                        Err(err_command) => {
+                            error!(
+                                "{} {} {}",
+                                Red.paint("Command"),
+                                White.on(Red).paint(format!(" {} ", command.name)),
+                                Red.paint("not found")
+                            );
+
+                            Err(err_command)
+                        }
+                        Ok(resolved_command) => {
+                            let progress =
+                                Progress::new(Arc::clone(&reporter), &task, &command.name);
+
+                            // interpolated right before running, earlier commands might have registered variables
+                            let result = run_command(
+                                resolved_command,
+                                c.variables.interpolate(&command.args),
+                                &mode,
+                                &c,
+                                &progress,
+                            )
+                            .map_err(|err| err.with_context(&task, &command.name));
+
+                            if let Err(err_result) = &result {
+                                error!("{}", Red.paint("ERROR"));
+                                err_result.to_string().split('\n').for_each(|err| {
+                                    error!("{} {}", Red.bold().paint("|>"), Red.paint(err))
+                                });
+                            }
+
+                            result
+                        }
+                    };
+
+                    if result.is_err() {
                         errors.store(true, Ordering::Relaxed);
-
-                        p.set_message(format!(
-                            "❌ {}: {}",
-                            Red.paint(&task),
-                            Red.paint(&command.name)
-                        ));
-                        p.inc(1);
-                        drop(p);
-
-                        return;
                     }
 
-                    let result = run_command(
-                        resolved_command.unwrap(),
-                        command.args.clone(),
-                        &mode,
-                        &c,
-                        &p,
-                    );
-
-                    if let Err(err_result) = result {
-                        error!(
-                            "{}: {}",
-                            White.bold().paint(&command.name),
-                            Red.paint("ERROR")
-                        );
-                        err_result.split('\n').for_each(|err| {
-                            error!("{} {}", Red.bold().paint("|>"), Red.paint(err))
-                        });
-
-                        errors.store(true, Ordering::Relaxed);
-                    }
-
-                    p.set_message(format!(
-                        "✅ {}: {}",
-                        Green.paint(&task),
-                        Green.paint(&command.name)
-                    ));
-                    p.inc(1);
-                    drop(p);
+                    reporter.report(&Event::CommandFinished {
+                        task: task.to_string(),
+                        command: command.name.to_string(),
+                        error: result.err().map(|err| err.to_string()),
+                    });
                 };
 
                 thread_pool.execute(run);
@@ -153,25 +174,111 @@ impl Task {
         }
 
         if has_errors.load(Ordering::Relaxed) {
-            progress_bar.lock().unwrap().finish_with_message(format!(
-                "❌ {} ➡️ {}",
-                Red.paint(&task_name),
-                Red.bold().paint("ERR")
-            ));
-
-            Err(format!("{}", Red.paint("Task has errors")))
+            Err(Error::TaskFailed(self.name.clone()))
         } else {
-            progress_bar.lock().unwrap().finish_with_message(format!(
-                "✅ {} ➡️ {}",
-                Green.paint(&task_name),
-                Green.bold().paint("OK")
-            ));
-
             Ok(())
         }
     }
 }
 
+/**
+ * Commands of a sequential task still run one after another,
+ * but their downloads are already fetched at the same time.
+ */
+fn prefetch_downloads(commands: &[Command], config: &CommandConfig) {
+    let downloads: Vec<ConfigValue> = commands
+        .iter()
+        .filter(|command| command.name == "download")
+        .map(|command| command.args.clone())
+        .collect();
+
+    if downloads.len() > 1 {
+        debug!("Prefetching {} downloads ...", downloads.len());
+        download::prefetch(&downloads, config);
+    }
+}
+
+pub fn has_tag(task: &Task, tag: &str) -> bool {
+    task.tags.iter().any(|t| t == tag)
+}
+
+/**
+ * Tasks that register variables always have to run, later tasks depend on them.
+ * The same applies to tasks whose outputs are referenced, see `get_referenced_tasks`.
+ */
+pub fn registers_variables(task: &Task) -> bool {
+    task.commands.iter().any(|command| {
+        command
+            .args
+            .as_hash()
+            .map(|args| args.contains_key("register"))
+            .unwrap_or(false)
+    })
+}
+
+static STATE_COMMANDS: [&str; 2] = ["assert", "wait_for"];
+
+/**
+ * Assertions and waits check the current state of the machine, which might have changed since the last run.
+ */
+pub fn verifies_state(task: &Task) -> bool {
+    task.commands
+        .iter()
+        .any(|command| STATE_COMMANDS.contains(&command.name.as_str()))
+}
+
+/**
+ * Names of the tasks whose outputs are used by other tasks, e.g. "fzf" for `{{ tasks.fzf.path }}`.
+ */
+pub fn get_referenced_tasks(tasks: &[Task]) -> BTreeSet<String> {
+    let mut referenced = BTreeSet::new();
+
+    for task in tasks {
+        for command in &task.commands {
+            collect_referenced_tasks(&command.args, &mut referenced);
+        }
+        for condition in task.when.iter().chain(&task.only_if).chain(&task.skip_if) {
+            collect_referenced_tasks_in_str(condition, &mut referenced);
+        }
+    }
+
+    referenced
+}
+
+/**
+ * Guards are run with the default shell, their output is discarded.
+ */
+fn run_guard(command: &str, config: &CommandConfig) -> Result<bool> {
+    config
+        .default_shell
+        .command_succeeds(&config.variables.interpolate_str(command))
+}
+
+/**
+ * The `when` conditions are checked first, they are cheaper than the shell guards.
+ */
+pub fn conditions_are_met(task: &Task, config: &CommandConfig) -> Result<bool> {
+    for condition in &task.when {
+        if !config.variables.evaluate(condition)? {
+            return Ok(false);
+        }
+    }
+
+    if let Some(only_if) = &task.only_if {
+        if !run_guard(only_if, config)? {
+            return Ok(false);
+        }
+    }
+
+    if let Some(skip_if) = &task.skip_if {
+        if run_guard(skip_if, config)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
 pub fn get_task_names(tasks: &[Task]) -> Vec<String> {
     let mut task_names = Vec::new();
     for task in tasks {
@@ -219,12 +326,20 @@ mod test {
                 name: "task1".to_string(),
                 commands: vec![],
                 os: vec![],
+                tags: vec![],
+                when: vec![],
+                only_if: None,
+                skip_if: None,
                 parallel: false,
             },
             Task {
                 name: "task2".to_string(),
                 commands: vec![],
                 os: vec![],
+                tags: vec![],
+                when: vec![],
+                only_if: None,
+                skip_if: None,
                 parallel: false,
             },
         ];
@@ -234,11 +349,49 @@ mod test {
         assert_eq!(task_names, vec!["task1", "task2"]);
     }
 
+    #[test]
+    fn it_checks_the_tags_of_a_task() {
+        let task = Task {
+            name: "nvim".to_string(),
+            commands: vec![],
+            os: vec![],
+            tags: vec!["editor".to_string(), "dev".to_string()],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        };
+
+        assert!(has_tag(&task, "dev"));
+        assert!(!has_tag(&task, "shell"));
+    }
+
+    #[test]
+    fn it_returns_an_error_for_commands_run_in_upgrade_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CommandConfig::for_dir(dir.path());
+        let command = get_command("run", dir.path()).unwrap();
+
+        let result = run_command(
+            command,
+            ConfigValue::Null,
+            &TaskRunnerMode::Upgrade,
+            &config,
+            &Progress::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn it_only_runs_task_for_specific_os() {
         let task_linux = Task {
             os: vec![Os::Linux],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
             name: String::from("my-linux-task"),
             commands: vec![],
             parallel: false,
@@ -247,6 +400,10 @@ mod test {
 
         let task_win = Task {
             os: vec![Os::Windows],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
             name: String::from("my-linux-task"),
             commands: vec![],
             parallel: false,
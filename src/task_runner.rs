@@ -1,17 +1,49 @@
-use ansi_term::Color::{Red, White};
+use ansi_term::Color::{White, Yellow};
 use core::fmt;
 use ergo_fs::PathDir;
-use indicatif::MultiProgress;
-use std::sync::{Arc, Mutex};
-use tracing::debug;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
 
-use crate::{command::CommandConfig, config::base_config::TaskList, utils::threads::ThreadPool};
+use crate::{
+    command::CommandConfig,
+    config::base_config::{Command, TaskList},
+    error::{Error, Result},
+    events::{Event, Reporter},
+    task::{
+        conditions_are_met, get_referenced_tasks, has_tag, registers_variables, should_skip_task,
+        verifies_state, Task,
+    },
+    utils::{
+        applied_config::AppliedConfig,
+        connections::ConnectionPool,
+        directory::expand_path,
+        hash::get_config_key,
+        lockfile::Lockfile,
+        progress::IndicatifReporter,
+        purge::purge,
+        restart::{
+            get_required_restart, get_restart_notice, reboot, Restart, RestartRequirement,
+            RestartRequirements,
+        },
+        sudo::SudoKeepAlive,
+        task_results::{get_task_hash, TaskResults},
+        temp_storage::{remove_stale_temp_files, RunTempDir, STALE_TEMP_FILE_AGE},
+        threads::ThreadPool,
+        variables::Variables,
+    },
+};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TaskRunnerMode {
     Install,
     Update,
     Uninstall,
+    /** installs, updates or uninstalls each task, depending on how it changed since it was applied */
+    Upgrade,
 }
 
 impl fmt::Display for TaskRunnerMode {
@@ -20,124 +52,571 @@ impl fmt::Display for TaskRunnerMode {
             TaskRunnerMode::Install => "install",
             TaskRunnerMode::Update => "update",
             TaskRunnerMode::Uninstall => "uninstall",
+            TaskRunnerMode::Upgrade => "upgrade",
         };
 
         write!(f, "{mode}")
     }
 }
 
+/**
+ * Options that are passed on the command line instead of the config file.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub frozen: bool,
+    /** only tasks with this tag are run */
+    pub tag: Option<String>,
+    /** remove everything machine_setup stored after uninstalling */
+    pub purge: bool,
+    /** install tasks even if their inputs didn't change */
+    pub force: bool,
+    /** reboot at the end of a successful run, if a command requires it */
+    pub reboot: bool,
+    /** file name of the config inside of the config directory, its state is stored separately */
+    pub config_file: Option<String>,
+    /** variables for `{{ }}` placeholders, e.g. the `vars` of a host from the inventory */
+    pub vars: BTreeMap<String, String>,
+    /** progress bars are drawn when no reporter is set */
+    pub reporter: Option<Arc<dyn Reporter>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskStatus {
+    Succeeded,
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskResult {
+    pub name: String,
+    pub status: TaskStatus,
+    pub duration: Duration,
+}
+
+/**
+ * The results of all tasks that were run, in the order of the config.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub tasks: Vec<TaskResult>,
+    /** restarts that commands flagged, see `utils::restart` */
+    pub restarts: Vec<RestartRequirement>,
+}
+
+impl RunReport {
+    pub fn get_failed_tasks(&self) -> Vec<&TaskResult> {
+        self.tasks
+            .iter()
+            .filter(|task| matches!(task.status, TaskStatus::Failed(_)))
+            .collect()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.get_failed_tasks().is_empty()
+    }
+
+    pub fn into_result(self) -> Result<()> {
+        let mut failed: Vec<String> = self
+            .get_failed_tasks()
+            .iter()
+            .map(|task| task.name.to_string())
+            .collect();
+
+        match failed.len() {
+            0 => Ok(()),
+            _ if self.tasks.len() == 1 => Err(Error::TaskFailed(failed.remove(0))),
+            _ => Err(Error::TasksFailed(failed)),
+        }
+    }
+}
+
 pub fn run(
     task_list: TaskList,
     mode: TaskRunnerMode,
     task_name: Option<String>,
     config_dir: PathDir,
-) -> Result<(), String> {
+    options: RunOptions,
+) -> Result<()> {
+    let reboot_when_required = options.reboot;
+    let report = run_with_report(task_list, mode, task_name, config_dir, options)?;
+
+    if let Some(notice) = get_restart_notice(&report.restarts) {
+        warn!("{}", Yellow.bold().paint(notice));
+    }
+
+    // failed tasks have to be fixed first, they would be hidden behind the reboot otherwise
+    if reboot_when_required
+        && report.is_success()
+        && get_required_restart(&report.restarts) == Some(Restart::Reboot)
+    {
+        warn!("{}", Yellow.bold().paint("Rebooting ..."));
+        reboot()?;
+    }
+
+    report.into_result()
+}
+
+/**
+ * The tasks a run would start and how, removed tasks of an upgrade are uninstalled.
+ */
+pub fn get_planned_tasks(
+    task_list: &TaskList,
+    mode: TaskRunnerMode,
+    task_name: Option<String>,
+    config_dir: &PathDir,
+    options: &RunOptions,
+) -> Result<Vec<(String, TaskRunnerMode)>> {
+    let state_dir = expand_path(&task_list.state_dir, false)?;
+    let config_key = get_config_key(config_dir, options.config_file.as_deref());
+    let applied_config = AppliedConfig::load(&state_dir, &config_key);
+
+    let plan = get_plan(
+        task_list.tasks.clone(),
+        mode,
+        task_name,
+        options.tag.clone(),
+        &applied_config,
+    )?;
+
+    Ok(plan
+        .into_iter()
+        .map(|(task, mode)| (task.name, mode))
+        .collect())
+}
+
+/**
+ * Failing tasks are part of the report, only errors that prevent the run from starting are returned.
+ */
+pub fn run_with_report(
+    task_list: TaskList,
+    mode: TaskRunnerMode,
+    task_name: Option<String>,
+    config_dir: PathDir,
+    options: RunOptions,
+) -> Result<RunReport> {
     match mode {
         TaskRunnerMode::Install => debug!("{}", White.bold().paint("Installing...")),
         TaskRunnerMode::Update => debug!("{}", White.bold().paint("Updating...")),
         TaskRunnerMode::Uninstall => debug!("{}", White.bold().paint("Uninstalling...")),
+        TaskRunnerMode::Upgrade => debug!("{}", White.bold().paint("Upgrading...")),
+    }
+
+    let lockfile = Arc::new(Lockfile::load(&config_dir, options.frozen)?);
+    let state_dir = expand_path(&task_list.state_dir, false)?;
+    let config_key = get_config_key(&config_dir, options.config_file.as_deref());
+    let previous_results = Arc::new(TaskResults::load(&state_dir, &config_key, options.force));
+    let applied_config = Arc::new(AppliedConfig::load(&state_dir, &config_key));
+
+    let plan = get_plan(
+        task_list.tasks,
+        mode,
+        task_name,
+        options.tag,
+        &applied_config,
+    )?;
+    let num_threads = if task_list.parallel {
+        task_list.num_threads
+    } else {
+        1
+    };
+
+    match remove_stale_temp_files(&task_list.temp_dir, STALE_TEMP_FILE_AGE) {
+        Ok(0) => {}
+        Ok(num_removed) => debug!("Removed {num_removed} stale temp files"),
+        Err(err_cleanup) => warn!("Failed to clean up temp files: {err_cleanup}"),
+    }
+
+    // removed at the end of the run, even if a command panics
+    let run_temp_dir = RunTempDir::create(&task_list.temp_dir)?;
+    let base_temp_dir = task_list.temp_dir.to_string();
+
+    let variables = Arc::new(Variables::default());
+    for (name, value) in &options.vars {
+        variables.set(name, value);
     }
 
     let command_config = CommandConfig {
         config_dir,
-        temp_dir: task_list.temp_dir.to_string(),
-        default_shell: task_list.default_shell,
+        temp_dir: run_temp_dir.get_path(),
+        state_dir: task_list.state_dir.to_string(),
+        cache_dir: task_list.cache_dir.to_string(),
+        default_shell: task_list.default_shell.resolve(),
+        network: task_list.network.clone(),
+        lockfile: Arc::clone(&lockfile),
+        connections: Arc::new(ConnectionPool::new(&task_list.network)),
+        variables,
+        restarts: Arc::new(RestartRequirements::default()),
     };
 
-    let multi_progress = Arc::new(MultiProgress::new());
+    // the password has to be entered before any of the progress bars are drawn
+    let _sudo_keep_alive = if task_list.sudo {
+        Some(SudoKeepAlive::start()?)
+    } else {
+        None
+    };
 
-    if let Some(task_name) = task_name {
-        let task = task_list.tasks.iter().find(|t| t.name == task_name);
-        if task.is_none() {
-            return Err(format!(
-                "Task {} {}",
-                White.on(Red).paint(format!(" {task_name} ")),
-                Red.paint("not found")
-            ));
+    let reporter = options
+        .reporter
+        .unwrap_or_else(|| Arc::new(IndicatifReporter::default()));
+
+    let mut report = run_tasks(
+        plan,
+        mode,
+        num_threads,
+        &command_config,
+        &reporter,
+        &previous_results,
+        &applied_config,
+    );
+
+    report.restarts = command_config.restarts.get_all();
+
+    // versions of the tasks that succeeded are recorded, even if others failed
+    lockfile.save()?;
+    previous_results.save()?;
+    applied_config.save()?;
+
+    if options.purge && mode == TaskRunnerMode::Uninstall {
+        // state is still needed to retry failed tasks
+        if report.is_success() {
+            for path in purge(&command_config, &base_temp_dir)? {
+                info!("Purged {}", path.display());
+            }
+        } else {
+            warn!("Not purging, because some tasks failed to uninstall");
+        }
+    }
+
+    Ok(report)
+}
+
+/**
+ * Outputs of earlier tasks are part of the hash, the task has to run again when they change.
+ */
+fn get_interpolated_hash(task: &Task, config: &CommandConfig) -> Option<String> {
+    let interpolated = Task {
+        commands: task
+            .commands
+            .iter()
+            .map(|command| Command {
+                name: command.name.to_string(),
+                args: config.variables.interpolate(&command.args),
+            })
+            .collect(),
+        ..task.clone()
+    };
+
+    get_task_hash(&interpolated, &config.config_dir)
+        .map_err(|err_hash| warn!("Failed to hash task {}: {err_hash}", task.name))
+        .ok()
+}
+
+fn run_task(
+    task: &Task,
+    mode: TaskRunnerMode,
+    config: &CommandConfig,
+    reporter: &Arc<dyn Reporter>,
+    previous_results: &TaskResults,
+    applied_config: &AppliedConfig,
+    always_runs: bool,
+) -> TaskResult {
+    let start = Instant::now();
+    // guards are only run on the matching os, invalid conditions are reported as an error of the task
+    let skipped_by_os = should_skip_task(task);
+    let conditions = if skipped_by_os {
+        Ok(true)
+    } else {
+        conditions_are_met(task, config)
+    };
+    let skipped = skipped_by_os || matches!(conditions, Ok(false));
+
+    // updates have to run every time, the latest versions might have changed
+    let hash = match mode {
+        TaskRunnerMode::Install if !skipped && conditions.is_ok() && !always_runs => {
+            get_interpolated_hash(task, config)
         }
+        _ => None,
+    };
+
+    if let Some(hash) = &hash {
+        if previous_results.is_unchanged(&task.name, hash) {
+            info!(
+                "Skipping task \"{}\", nothing changed since the last run",
+                task.name
+            );
+            // the installed task matches the config, even if it was installed before the config was recorded
+            applied_config.store(task);
 
-        let task_result = task.unwrap().run(mode, &command_config, &multi_progress);
-        if task_result.is_err() {
-            return Err(format!(
-                "Task {} {}",
-                White.on(Red).paint(format!(" {task_name} ")),
-                Red.paint("failed")
-            ));
+            reporter.report(&Event::TaskFinished {
+                task: task.name.to_string(),
+                status: TaskStatus::Skipped,
+            });
+
+            return TaskResult {
+                name: task.name.to_string(),
+                status: TaskStatus::Skipped,
+                duration: start.elapsed(),
+            };
         }
+    }
 
-        return task_result;
+    if !skipped {
+        reporter.report(&Event::TaskStarted {
+            task: task.name.to_string(),
+            num_commands: task.commands.len(),
+        });
     }
 
-    let mut num_threads = if task_list.parallel {
-        task_list.num_threads
-    } else {
-        1
+    let status = match conditions {
+        Ok(false) => {
+            info!(
+                "{}",
+                Yellow.bold().paint(format!(
+                    "Skipping task \"{}\" due to its conditions ...",
+                    task.name
+                ))
+            );
+
+            TaskStatus::Skipped
+        }
+        Err(err_conditions) => TaskStatus::Failed(err_conditions.to_string()),
+        Ok(true) => match task.run(mode, config, reporter) {
+            Ok(_) if skipped => TaskStatus::Skipped,
+            Ok(_) => TaskStatus::Succeeded,
+            Err(err_task) => TaskStatus::Failed(err_task.to_string()),
+        },
+    };
+
+    match (&status, &hash) {
+        (TaskStatus::Succeeded, Some(hash)) => previous_results.store(&task.name, hash),
+        (TaskStatus::Succeeded, None) if mode == TaskRunnerMode::Uninstall => {
+            previous_results.remove(&task.name)
+        }
+        _ => {}
+    }
+
+    match (&status, mode) {
+        (TaskStatus::Succeeded, TaskRunnerMode::Uninstall) => applied_config.remove(&task.name),
+        (TaskStatus::Succeeded, _) => applied_config.store(task),
+        _ => {}
+    }
+
+    reporter.report(&Event::TaskFinished {
+        task: task.name.to_string(),
+        status: status.clone(),
+    });
+
+    TaskResult {
+        name: task.name.to_string(),
+        status,
+        duration: start.elapsed(),
+    }
+}
+
+fn get_tasks(
+    tasks: Vec<Task>,
+    task_name: Option<String>,
+    tag: Option<String>,
+) -> Result<Vec<Task>> {
+    if let Some(task_name) = task_name {
+        return match tasks.into_iter().find(|t| t.name == task_name) {
+            Some(task) => Ok(vec![task]),
+            None => Err(Error::TaskNotFound(task_name)),
+        };
+    }
+
+    match tag {
+        Some(tag) => {
+            let tagged: Vec<Task> = tasks.into_iter().filter(|t| has_tag(t, &tag)).collect();
+            if tagged.is_empty() {
+                return Err(Error::Validation(format!("No tasks are tagged with {tag}")));
+            }
+
+            Ok(tagged)
+        }
+        None => Ok(tasks),
+    }
+}
+
+/**
+ * Removed tasks are uninstalled first, starting with the one that was applied last.
+ * Unchanged tasks are installed like in any other run, so they are skipped unless their files changed
+ * or later tasks depend on them.
+ */
+fn get_upgrade_plan(
+    tasks: Vec<Task>,
+    applied_config: &AppliedConfig,
+) -> Vec<(Task, TaskRunnerMode)> {
+    let mut plan: Vec<(Task, TaskRunnerMode)> = applied_config
+        .get_removed_tasks(&tasks)
+        .into_iter()
+        .rev()
+        .map(|task| {
+            debug!("Task \"{}\" was removed", task.name);
+            (task, TaskRunnerMode::Uninstall)
+        })
+        .collect();
+
+    for task in tasks {
+        let mode = if !applied_config.contains(&task.name) {
+            debug!("Task \"{}\" was added", task.name);
+            TaskRunnerMode::Install
+        } else if !applied_config.is_unchanged(&task) {
+            debug!("Task \"{}\" was changed", task.name);
+            TaskRunnerMode::Update
+        } else {
+            TaskRunnerMode::Install
+        };
+
+        plan.push((task, mode));
+    }
+
+    plan
+}
+
+/**
+ * Decides which tasks are run and how, in the order of the config.
+ */
+fn get_plan(
+    tasks: Vec<Task>,
+    mode: TaskRunnerMode,
+    task_name: Option<String>,
+    tag: Option<String>,
+    applied_config: &AppliedConfig,
+) -> Result<Vec<(Task, TaskRunnerMode)>> {
+    let plan = match mode {
+        TaskRunnerMode::Upgrade => get_upgrade_plan(tasks, applied_config),
+        mode => tasks.into_iter().map(|task| (task, mode)).collect(),
     };
 
-    if num_threads > task_list.tasks.len() {
-        num_threads = task_list.tasks.len();
+    // removed tasks can be selected as well during an upgrade
+    let selected: Vec<String> = get_tasks(
+        plan.iter().map(|(task, _)| task.clone()).collect(),
+        task_name,
+        tag,
+    )?
+    .into_iter()
+    .map(|task| task.name)
+    .collect();
+
+    Ok(plan
+        .into_iter()
+        .filter(|(task, _)| selected.contains(&task.name))
+        .collect())
+}
+
+fn run_tasks(
+    mut plan: Vec<(Task, TaskRunnerMode)>,
+    mode: TaskRunnerMode,
+    num_threads: usize,
+    command_config: &CommandConfig,
+    reporter: &Arc<dyn Reporter>,
+    previous_results: &Arc<TaskResults>,
+    applied_config: &Arc<AppliedConfig>,
+) -> RunReport {
+    let referenced_tasks = get_referenced_tasks(
+        &plan
+            .iter()
+            .map(|(task, _)| task.clone())
+            .collect::<Vec<Task>>(),
+    );
+
+    // later tasks may depend on earlier ones, so they are removed first
+    if mode == TaskRunnerMode::Uninstall {
+        plan.reverse();
     }
 
-    if task_list.parallel {
+    let num_threads = num_threads.min(plan.len());
+
+    if num_threads > 1 {
         debug!(
             "Running tasks in parallel ({} threads)...",
             White.bold().paint(num_threads.to_string())
         );
     }
 
-    let errored_tasks = Arc::new(Mutex::new(vec![]));
+    reporter.report(&Event::RunStarted {
+        mode,
+        num_tasks: plan.len(),
+    });
+
+    let task_results = Arc::new(Mutex::new(vec![]));
 
     {
         let thread_pool = ThreadPool::new(num_threads);
 
-        for task in task_list.tasks {
+        for (index, (task, task_mode)) in plan.into_iter().enumerate() {
             let config = command_config.clone();
-            let task_clone = task.clone();
-            let errors = Arc::clone(&errored_tasks);
-            let mp = Arc::clone(&multi_progress);
+            let results = Arc::clone(&task_results);
+            let reporter = Arc::clone(reporter);
+            let previous_results = Arc::clone(previous_results);
+            let applied_config = Arc::clone(applied_config);
+            let always_runs = registers_variables(&task)
+                || referenced_tasks.contains(&task.name)
+                || verifies_state(&task);
 
             let execute = move || {
-                let task_result = task_clone.run(mode, &config, &mp);
+                let task_result = run_task(
+                    &task,
+                    task_mode,
+                    &config,
+                    &reporter,
+                    &previous_results,
+                    &applied_config,
+                    always_runs,
+                );
 
-                if task_result.is_err() {
-                    let mut e = errors.lock().unwrap();
-                    e.push(task_clone.name.to_string());
-                    drop(e);
+                // the update applied the changes, so the next install doesn't have to run the task again
+                let is_upgraded = mode == TaskRunnerMode::Upgrade
+                    && task_mode == TaskRunnerMode::Update
+                    && task_result.status == TaskStatus::Succeeded;
+                if is_upgraded && !always_runs {
+                    if let Some(hash) = get_interpolated_hash(&task, &config) {
+                        previous_results.store(&task.name, &hash);
+                    }
                 }
+
+                results.lock().unwrap().push((index, task_result));
             };
 
             thread_pool.execute(execute);
         }
     }
 
-    let errors = errored_tasks.lock().unwrap();
-    let num_errored = errors.len();
-    if num_errored > 0 {
-        return Err(format!(
-            "{} {} {}\n{}",
-            Red.paint("Errors occurred in"),
-            Red.bold().underline().paint(num_errored.to_string()),
-            Red.paint("tasks:"),
-            errors
-                .clone()
-                .into_iter()
-                .map(|e| format!("> {e}"))
-                .collect::<Vec<String>>()
-                .join("\n")
-        ));
+    let mut results = std::mem::take(&mut *task_results.lock().unwrap());
+    results.sort_by_key(|(index, _)| *index);
+    if mode == TaskRunnerMode::Uninstall {
+        // the report keeps the order of the config
+        results.reverse();
     }
 
-    Ok(())
+    let report = RunReport {
+        tasks: results.into_iter().map(|(_, result)| result).collect(),
+        ..Default::default()
+    };
+
+    reporter.report(&Event::RunFinished {
+        success: report.is_success(),
+    });
+
+    report
 }
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashMap, env::temp_dir};
+    use std::{collections::HashMap, env::temp_dir, path::Path};
+
+    use tempfile::{tempdir, TempDir};
 
     use crate::{
         config::{base_config::Command, config_value::ConfigValue},
-        task::Task,
-        utils::shell::Shell,
+        task::get_task_names,
+        utils::{
+            network::NetworkConfig,
+            shell::{DefaultShell, Shell},
+        },
     };
 
     use super::*;
@@ -146,8 +625,35 @@ mod test {
         PathDir::new(temp_dir()).unwrap()
     }
 
+    /**
+     * Every test gets its own dirs, so results of previous test runs don't skip any tasks.
+     * They are removed when the test is done.
+     */
+    struct TestDirs {
+        temp: TempDir,
+        state: TempDir,
+    }
+
+    impl TestDirs {
+        fn new() -> Self {
+            TestDirs {
+                temp: tempdir().unwrap(),
+                state: tempdir().unwrap(),
+            }
+        }
+
+        fn temp_dir(&self) -> String {
+            self.temp.path().to_str().unwrap().to_string()
+        }
+
+        fn state_dir(&self) -> String {
+            self.state.path().to_str().unwrap().to_string()
+        }
+    }
+
     #[test]
     fn it_runs_single_task_when_argument_is_passed() {
+        let dirs = TestDirs::new();
         let task_list = TaskList {
             tasks: vec![
                 Task {
@@ -157,19 +663,32 @@ mod test {
                         args: ConfigValue::Array(vec![]),
                     }],
                     os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
                     parallel: false,
                 },
                 Task {
                     name: "task_two".to_string(),
                     commands: vec![],
                     os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
                     parallel: false,
                 },
             ],
-            temp_dir: "".to_string(),
-            default_shell: Shell::Bash,
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
             num_threads: 1,
             parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
         };
 
         let result = run(
@@ -177,20 +696,27 @@ mod test {
             TaskRunnerMode::Install,
             Some("task_one".to_string()),
             get_temp_path_dir(),
+            RunOptions::default(),
         );
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("task_one"));
+        assert!(result.unwrap_err().to_string().contains("task_one"));
     }
 
     #[test]
     fn it_fails_when_the_task_doesnt_exist() {
+        let dirs = TestDirs::new();
         let task_list = TaskList {
             tasks: vec![],
-            temp_dir: "".to_string(),
-            default_shell: Shell::Bash,
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
             num_threads: 1,
             parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
         };
 
         let result = run(
@@ -198,35 +724,50 @@ mod test {
             TaskRunnerMode::Install,
             Some("test".to_string()),
             get_temp_path_dir(),
+            RunOptions::default(),
         );
 
         assert!(result.is_err());
-        let error_message = result.unwrap_err();
+        let error_message = result.unwrap_err().to_string();
         assert!(error_message.contains("test"));
         assert!(error_message.contains("not found"));
     }
 
     #[test]
     fn it_runs_all_tasks_when_no_argument_is_passed() {
+        let dirs = TestDirs::new();
         let task_list = TaskList {
             tasks: vec![
                 Task {
                     name: "task_one".to_string(),
                     commands: vec![],
                     os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
                     parallel: false,
                 },
                 Task {
                     name: "task_two".to_string(),
                     commands: vec![],
                     os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
                     parallel: false,
                 },
             ],
-            temp_dir: "".to_string(),
-            default_shell: Shell::Bash,
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
             num_threads: 1,
             parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
         };
 
         let result = run(
@@ -234,6 +775,7 @@ mod test {
             TaskRunnerMode::Install,
             None,
             get_temp_path_dir(),
+            RunOptions::default(),
         );
 
         result.unwrap();
@@ -241,6 +783,7 @@ mod test {
 
     #[test]
     fn it_prints_failing_tasks() {
+        let dirs = TestDirs::new();
         let task_list = TaskList {
             tasks: vec![
                 Task {
@@ -250,6 +793,10 @@ mod test {
                         args: ConfigValue::Array(vec![]),
                     }],
                     os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
                     parallel: false,
                 },
                 Task {
@@ -259,13 +806,22 @@ mod test {
                         args: ConfigValue::Array(vec![]),
                     }],
                     os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
                     parallel: false,
                 },
             ],
-            temp_dir: "".to_string(),
-            default_shell: Shell::Bash,
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
             num_threads: 1,
             parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
         };
 
         let result = run(
@@ -273,17 +829,199 @@ mod test {
             TaskRunnerMode::Install,
             None,
             get_temp_path_dir(),
+            RunOptions::default(),
         );
 
         assert!(result.is_err());
-        let error_message = result.unwrap_err();
+        let error_message = result.unwrap_err().to_string();
         assert!(error_message.contains("Errors occurred in"));
         assert!(error_message.contains("task_one"));
         assert!(error_message.contains("task_two"));
     }
 
+    #[test]
+    fn it_reports_the_status_of_every_task() {
+        let dirs = TestDirs::new();
+        let task_list = TaskList {
+            tasks: vec![
+                Task {
+                    name: "task_one".to_string(),
+                    commands: vec![Command {
+                        name: "_TEST_".to_string(),
+                        args: ConfigValue::Array(vec![]),
+                    }],
+                    os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
+                    parallel: false,
+                },
+                Task {
+                    name: "task_two".to_string(),
+                    commands: vec![],
+                    os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
+                    parallel: false,
+                },
+            ],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 2,
+            parallel: true,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+
+        let report = run_with_report(
+            task_list,
+            TaskRunnerMode::Install,
+            None,
+            get_temp_path_dir(),
+            RunOptions::default(),
+        )
+        .unwrap();
+
+        assert!(!report.is_success());
+        assert_eq!(report.tasks.len(), 2);
+        assert_eq!(report.tasks[0].name, "task_one");
+        assert!(matches!(report.tasks[0].status, TaskStatus::Failed(_)));
+        assert_eq!(report.tasks[1].name, "task_two");
+        assert_eq!(report.tasks[1].status, TaskStatus::Succeeded);
+    }
+
+    #[test]
+    fn it_selects_tasks_by_tag() {
+        let get_task = |name: &str, tags: Vec<&str>| Task {
+            name: name.to_string(),
+            commands: vec![],
+            os: vec![],
+            tags: tags.into_iter().map(|tag| tag.to_string()).collect(),
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        };
+        let tasks = vec![
+            get_task("nvim", vec!["editor", "dev"]),
+            get_task("zsh", vec!["shell"]),
+            get_task("rust", vec!["dev"]),
+        ];
+
+        let selected = get_tasks(tasks.clone(), None, Some(String::from("dev"))).unwrap();
+        assert_eq!(get_task_names(&selected), vec!["nvim", "rust"]);
+
+        let selected = get_tasks(tasks.clone(), Some(String::from("zsh")), None).unwrap();
+        assert_eq!(get_task_names(&selected), vec!["zsh"]);
+
+        get_tasks(tasks, None, Some(String::from("games"))).unwrap_err();
+    }
+
+    #[test]
+    fn it_reports_uninstalled_tasks_in_the_order_of_the_config() {
+        let dirs = TestDirs::new();
+        let task_list = TaskList {
+            tasks: vec![
+                Task {
+                    name: "task_one".to_string(),
+                    commands: vec![],
+                    os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
+                    parallel: false,
+                },
+                Task {
+                    name: "task_two".to_string(),
+                    commands: vec![],
+                    os: vec![],
+                    tags: vec![],
+                    when: vec![],
+                    only_if: None,
+                    skip_if: None,
+                    parallel: false,
+                },
+            ],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+
+        let report = run_with_report(
+            task_list,
+            TaskRunnerMode::Uninstall,
+            None,
+            get_temp_path_dir(),
+            RunOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.tasks[0].name, "task_one");
+        assert_eq!(report.tasks[1].name, "task_two");
+    }
+
+    #[test]
+    fn it_skips_unchanged_tasks_unless_forced() {
+        let dirs = TestDirs::new();
+        let get_task_list = || TaskList {
+            tasks: vec![Task {
+                name: "task_one".to_string(),
+                commands: vec![],
+                os: vec![],
+                tags: vec![],
+                when: vec![],
+                only_if: None,
+                skip_if: None,
+                parallel: false,
+            }],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+        let install = |force: bool| {
+            run_with_report(
+                get_task_list(),
+                TaskRunnerMode::Install,
+                None,
+                get_temp_path_dir(),
+                RunOptions {
+                    force,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .tasks[0]
+                .status
+                .clone()
+        };
+
+        assert_eq!(install(false), TaskStatus::Succeeded);
+        assert_eq!(install(false), TaskStatus::Skipped);
+        assert_eq!(install(true), TaskStatus::Succeeded);
+    }
+
     #[test]
     fn it_runs_commands() {
+        let dirs = TestDirs::new();
         let mut run_commands = HashMap::new();
         run_commands.insert(
             String::from("commands"),
@@ -300,12 +1038,21 @@ mod test {
                 name: "task_one".to_string(),
                 commands: vec![command],
                 os: vec![],
+                tags: vec![],
+                when: vec![],
+                only_if: None,
+                skip_if: None,
                 parallel: false,
             }],
-            temp_dir: temp_dir().to_str().unwrap().to_string(),
-            default_shell: Shell::Bash,
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
             num_threads: 1,
             parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
         };
 
         let result = run(
@@ -313,8 +1060,356 @@ mod test {
             TaskRunnerMode::Install,
             None,
             get_temp_path_dir(),
+            RunOptions::default(),
         );
 
         result.unwrap();
     }
+
+    #[test]
+    fn it_branches_on_registered_variables_and_outputs() {
+        let dirs = TestDirs::new();
+        let get_task = |name: &str, commands: Vec<Command>, when: &str| Task {
+            name: name.to_string(),
+            commands,
+            os: vec![],
+            tags: vec![],
+            when: if when.is_empty() {
+                vec![]
+            } else {
+                vec![when.to_string()]
+            },
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        };
+        let detect_gpu = Command {
+            name: String::from("run"),
+            args: ConfigValue::Hash(HashMap::from([
+                (
+                    String::from("commands"),
+                    ConfigValue::String(String::from("echo nvidia")),
+                ),
+                (
+                    String::from("register"),
+                    ConfigValue::String(String::from("gpu")),
+                ),
+            ])),
+        };
+
+        let task_list = TaskList {
+            tasks: vec![
+                get_task("gpu", vec![detect_gpu], ""),
+                get_task("nvidia", vec![], "{{gpu}} == nvidia"),
+                get_task("amd", vec![], "{{ gpu }} == amd"),
+                get_task("output", vec![], "{{ tasks.gpu.stdout }} == nvidia"),
+            ],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+
+        let report = run_with_report(
+            task_list,
+            TaskRunnerMode::Install,
+            None,
+            get_temp_path_dir(),
+            RunOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.tasks[0].status, TaskStatus::Succeeded);
+        assert_eq!(report.tasks[1].status, TaskStatus::Succeeded);
+        assert_eq!(report.tasks[2].status, TaskStatus::Skipped);
+        assert_eq!(report.tasks[3].status, TaskStatus::Succeeded);
+    }
+
+    #[test]
+    fn it_passes_variables_from_the_options() {
+        let dirs = TestDirs::new();
+        let get_task = |name: &str, when: &str| Task {
+            name: name.to_string(),
+            commands: vec![],
+            os: vec![],
+            tags: vec![],
+            when: vec![when.to_string()],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        };
+
+        let task_list = TaskList {
+            tasks: vec![
+                get_task("homeserver", "{{ HOSTNAME }} == homeserver"),
+                get_task("laptop", "{{ HOSTNAME }} == laptop"),
+            ],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+
+        let report = run_with_report(
+            task_list,
+            TaskRunnerMode::Install,
+            None,
+            get_temp_path_dir(),
+            RunOptions {
+                vars: BTreeMap::from([(String::from("HOSTNAME"), String::from("homeserver"))]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.tasks[0].status, TaskStatus::Succeeded);
+        assert_eq!(report.tasks[1].status, TaskStatus::Skipped);
+    }
+
+    #[test]
+    fn it_skips_tasks_by_their_guards() {
+        let dirs = TestDirs::new();
+        let get_task = |name: &str, only_if: Option<&str>, skip_if: Option<&str>| Task {
+            name: name.to_string(),
+            commands: vec![],
+            os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: only_if.map(|guard| guard.to_string()),
+            skip_if: skip_if.map(|guard| guard.to_string()),
+            parallel: false,
+        };
+
+        let task_list = TaskList {
+            tasks: vec![
+                get_task("only_if_true", Some("true"), None),
+                get_task("only_if_false", Some("exit 1"), None),
+                get_task("skip_if_true", None, Some("test -n \"$HOME\"")),
+                get_task("skip_if_false", None, Some("false")),
+            ],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+
+        let report = run_with_report(
+            task_list,
+            TaskRunnerMode::Install,
+            None,
+            get_temp_path_dir(),
+            RunOptions::default(),
+        )
+        .unwrap();
+
+        let statuses: Vec<TaskStatus> = report.tasks.into_iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                TaskStatus::Succeeded,
+                TaskStatus::Skipped,
+                TaskStatus::Skipped,
+                TaskStatus::Succeeded
+            ]
+        );
+    }
+
+    #[test]
+    fn it_collects_restart_requirements() {
+        let dirs = TestDirs::new();
+        let change_shell = Command {
+            name: String::from("run"),
+            args: ConfigValue::Hash(HashMap::from([
+                (
+                    String::from("commands"),
+                    ConfigValue::String(String::from("true")),
+                ),
+                (
+                    String::from("restart"),
+                    ConfigValue::String(String::from("relogin")),
+                ),
+            ])),
+        };
+
+        let task_list = TaskList {
+            tasks: vec![Task {
+                name: String::from("shell"),
+                commands: vec![change_shell],
+                os: vec![],
+                tags: vec![],
+                when: vec![],
+                only_if: None,
+                skip_if: None,
+                parallel: false,
+            }],
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+
+        let report = run_with_report(
+            task_list,
+            TaskRunnerMode::Install,
+            None,
+            get_temp_path_dir(),
+            RunOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.restarts,
+            vec![RestartRequirement {
+                task: String::from("shell"),
+                restart: Restart::Relogin,
+                reason: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn it_upgrades_the_tasks_that_changed_since_the_last_run() {
+        let dirs = TestDirs::new();
+        let markers = tempdir().unwrap();
+        let dir = markers.path().to_str().unwrap();
+        let get_task = |name: &str, install: &str| Task {
+            name: name.to_string(),
+            commands: vec![Command {
+                name: String::from("run"),
+                args: ConfigValue::Hash(HashMap::from([(
+                    String::from("commands"),
+                    ConfigValue::Hash(HashMap::from(["install", "update", "uninstall"].map(
+                        |mode| {
+                            let marker = format!("touch {dir}/{name}-{mode}");
+                            let command = match mode {
+                                "install" => format!("{install} && {marker}"),
+                                _ => marker,
+                            };
+
+                            (mode.to_string(), ConfigValue::String(command))
+                        },
+                    ))),
+                )])),
+            }],
+            os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        };
+        let upgrade = |mode: TaskRunnerMode, tasks: Vec<Task>| {
+            let task_list = TaskList {
+                tasks,
+                temp_dir: dirs.temp_dir(),
+                state_dir: dirs.state_dir(),
+                cache_dir: "".to_string(),
+                default_shell: DefaultShell::from(Shell::Bash),
+                num_threads: 1,
+                parallel: false,
+                sudo: false,
+                network: NetworkConfig::default(),
+                inventory: None,
+            };
+
+            run_with_report(
+                task_list,
+                mode,
+                None,
+                get_temp_path_dir(),
+                RunOptions::default(),
+            )
+            .unwrap()
+            .tasks
+            .into_iter()
+            .map(|task| (task.name, task.status))
+            .collect::<Vec<(String, TaskStatus)>>()
+        };
+        let result = |name: &str, status: TaskStatus| (name.to_string(), status);
+
+        upgrade(
+            TaskRunnerMode::Install,
+            vec![
+                get_task("kept", "true"),
+                get_task("changed", "true"),
+                get_task("removed", "true"),
+            ],
+        );
+
+        let tasks = vec![
+            get_task("kept", "true"),
+            get_task("changed", "echo"),
+            get_task("added", "true"),
+        ];
+        let task_list = TaskList {
+            tasks: tasks.clone(),
+            temp_dir: dirs.temp_dir(),
+            state_dir: dirs.state_dir(),
+            cache_dir: "".to_string(),
+            default_shell: DefaultShell::from(Shell::Bash),
+            num_threads: 1,
+            parallel: false,
+            sudo: false,
+            network: NetworkConfig::default(),
+            inventory: None,
+        };
+        assert_eq!(
+            get_planned_tasks(
+                &task_list,
+                TaskRunnerMode::Upgrade,
+                None,
+                &get_temp_path_dir(),
+                &RunOptions::default(),
+            )
+            .unwrap(),
+            vec![
+                (String::from("removed"), TaskRunnerMode::Uninstall),
+                (String::from("kept"), TaskRunnerMode::Install),
+                (String::from("changed"), TaskRunnerMode::Update),
+                (String::from("added"), TaskRunnerMode::Install),
+            ]
+        );
+        assert_eq!(
+            upgrade(TaskRunnerMode::Upgrade, tasks.clone()),
+            vec![
+                result("removed", TaskStatus::Succeeded),
+                result("kept", TaskStatus::Skipped),
+                result("changed", TaskStatus::Succeeded),
+                result("added", TaskStatus::Succeeded),
+            ]
+        );
+        for marker in ["removed-uninstall", "changed-update", "added-install"] {
+            assert!(Path::new(dir).join(marker).exists(), "{marker} is missing");
+        }
+        assert!(!Path::new(dir).join("kept-update").exists());
+
+        assert_eq!(
+            upgrade(TaskRunnerMode::Upgrade, tasks),
+            vec![
+                result("kept", TaskStatus::Skipped),
+                result("changed", TaskStatus::Skipped),
+                result("added", TaskStatus::Skipped),
+            ]
+        );
+    }
 }
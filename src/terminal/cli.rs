@@ -12,11 +12,96 @@ pub enum SubCommand {
     /// Update all of the defined tasks
     Update,
 
-    /// Uninstall all of the defined tasks
+    /// Uninstall all of the defined tasks (in reverse order)
     Uninstall,
 
+    /// Install added, update changed and uninstall removed tasks since the last run
+    Upgrade,
+
     /// List defined tasks
     List,
+
+    /// Install the config inside of disposable containers
+    Test {
+        /// container image, e.g. ubuntu:24.04 (can be given multiple times)
+        #[clap(long, required = true)]
+        image: Vec<String>,
+
+        /// docker or podman (detected automatically by default)
+        #[clap(long)]
+        engine: Option<String>,
+    },
+
+    /// Run machine_setup periodically with the scheduler of the OS
+    Schedule {
+        /// hour, day or week
+        #[clap(long, default_value = "day")]
+        every: String,
+
+        /// install, update or upgrade
+        #[clap(default_value = "update")]
+        mode: String,
+    },
+
+    /// Remove the scheduled runs
+    Unschedule,
+
+    /// Create a config from the files of another dotfiles manager
+    Import {
+        #[clap(subcommand)]
+        from: ImportSource,
+    },
+
+    /// Find symlinks in the home directory that point into a repository and print symlink tasks for them
+    Discover {
+        /// repository that contains the dotfiles
+        repo: String,
+
+        /// directory that is scanned for symlinks
+        #[clap(long, default_value = "~")]
+        home: String,
+
+        /// how many directory levels below the home directory are scanned
+        #[clap(long, default_value = "4")]
+        max_depth: usize,
+
+        /// write the config to this file instead of printing it
+        #[clap(long)]
+        output: Option<String>,
+    },
+
+    /// Print a script that installs machine_setup and runs a config repository on a new machine
+    BootstrapScript {
+        /// git repository that contains the config
+        #[clap(long)]
+        repo: String,
+
+        /// path of the config file inside of the repository
+        #[clap(long, default_value = "machine_setup.yaml")]
+        config_file: String,
+
+        /// sh or powershell
+        #[clap(long, default_value = "sh")]
+        format: String,
+
+        /// release that is installed (defaults to the version of this binary)
+        #[clap(long)]
+        release: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// Translate a chezmoi source directory
+    Chezmoi {
+        /// path of the chezmoi source directory
+        #[clap(default_value = "~/.local/share/chezmoi")]
+        path: String,
+
+        /// write the config to this file instead of printing it
+        #[clap(long)]
+        output: Option<String>,
+    },
 }
 
 impl FromStr for SubCommand {
@@ -27,6 +112,7 @@ impl FromStr for SubCommand {
             "install" => Ok(SubCommand::Install),
             "update" => Ok(SubCommand::Update),
             "uninstall" => Ok(SubCommand::Uninstall),
+            "upgrade" => Ok(SubCommand::Upgrade),
             "list" => Ok(SubCommand::List),
             _ => Err(format!("Invalid mode: {s}")),
         }
@@ -52,6 +138,11 @@ pub struct Args {
     #[clap(global = true)]
     pub task: Option<String>,
 
+    /// run all tasks with this tag
+    #[clap(long)]
+    #[clap(global = true)]
+    pub tag: Option<String>,
+
     /// Select a task to run
     #[clap(short, long)]
     #[clap(global = true)]
@@ -62,6 +153,51 @@ pub struct Args {
     #[clap(global = true)]
     pub debug: bool,
 
+    /// Pin clones and downloads to the commits and checksums in machine-setup.lock
+    #[clap(long)]
+    #[clap(global = true)]
+    pub frozen: bool,
+
+    /// Install tasks even if nothing changed since the last successful run
+    #[clap(short, long)]
+    #[clap(global = true)]
+    pub force: bool,
+
+    /// Also remove state, cached downloads and temp scripts when uninstalling
+    #[clap(long)]
+    #[clap(global = true)]
+    pub purge: bool,
+
+    /// Show a full-screen dashboard instead of progress bars (not available for remote runs)
+    #[clap(long, conflicts_with_all = ["host", "all", "group"])]
+    #[clap(global = true)]
+    pub ui: bool,
+
+    /// Reboot at the end of a successful run, if a task requires it
+    #[clap(long, conflicts_with = "ui")]
+    #[clap(global = true)]
+    pub reboot: bool,
+
+    /// Run the tasks on a remote machine over SSH ([user@]host[:port])
+    #[clap(long, conflicts_with_all = ["all", "group"])]
+    #[clap(global = true)]
+    pub host: Option<String>,
+
+    /// Run the tasks on all hosts of the inventory
+    #[clap(long, conflicts_with = "group")]
+    #[clap(global = true)]
+    pub all: bool,
+
+    /// Run the tasks on all hosts of an inventory group
+    #[clap(long)]
+    #[clap(global = true)]
+    pub group: Option<String>,
+
+    /// Set a variable for {{ }} placeholders (can be given multiple times)
+    #[clap(long = "var", value_name = "NAME=VALUE")]
+    #[clap(global = true)]
+    pub vars: Vec<String>,
+
     /// Set log level
     #[clap(short, long, default_value = "warn")]
     #[clap(global = true)]
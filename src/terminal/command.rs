@@ -1,33 +1,159 @@
-use std::fs::canonicalize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, canonicalize};
 use std::process::exit;
+use std::str::FromStr;
 
 use ansi_term::Color::{Red, White};
 use ergo_fs::expand;
 use ergo_fs::Path;
 use ergo_fs::PathBuf;
 use ergo_fs::PathDir;
-use tracing::error;
+use tracing::{error, warn};
 
+use crate::bootstrap::get_bootstrap_script;
+use crate::bootstrap::get_current_release;
+use crate::bootstrap::BootstrapOptions;
+use crate::bootstrap::ScriptFormat;
 use crate::config::base_config::get_config;
+use crate::config::base_config::resolve_config_path;
+use crate::config::base_config::TaskList;
+use crate::container::find_engine;
+use crate::container::run_tests;
+use crate::container::ContainerTest;
+use crate::container::Engine;
+use crate::discover::discover_links;
+use crate::discover::get_discovered_config;
+use crate::error::Error;
+use crate::error::Result;
+use crate::import::get_import_config;
+use crate::import::import_chezmoi;
+use crate::inventory::load_inventory;
+use crate::inventory::HostSelection;
+use crate::inventory::Inventory;
+use crate::remote;
+use crate::remote::RemoteRun;
+use crate::schedule;
+use crate::schedule::Interval;
+use crate::schedule::Schedule;
 use crate::task::get_task_names;
 use crate::task::select_task;
 use crate::task::Task;
 use crate::task_runner;
+use crate::task_runner::RunOptions;
 use crate::task_runner::TaskRunnerMode;
 use crate::terminal::exit_codes::EX_IO_ERR;
+use crate::utils::ssh::SshTarget;
+use crate::utils::variables::parse_variable;
 
 use super::cli::Args;
+use super::cli::ImportSource;
 use super::cli::SubCommand;
 
-fn get_task_runner_mode(subcommand: SubCommand) -> TaskRunnerMode {
+fn get_task_runner_mode(subcommand: &SubCommand) -> TaskRunnerMode {
     match subcommand {
         SubCommand::Install => TaskRunnerMode::Install,
         SubCommand::Update => TaskRunnerMode::Update,
         SubCommand::Uninstall => TaskRunnerMode::Uninstall,
+        SubCommand::Upgrade => TaskRunnerMode::Upgrade,
         _ => panic!("Invalid task runner mode"),
     }
 }
 
+#[cfg(feature = "tui")]
+fn run_ui(
+    task_list: TaskList,
+    mode: TaskRunnerMode,
+    task_name: Option<String>,
+    config_dir: PathDir,
+    options: RunOptions,
+) -> Result<()> {
+    super::tui::run(task_list, mode, task_name, config_dir, options)
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_ui(
+    _task_list: TaskList,
+    _mode: TaskRunnerMode,
+    _task_name: Option<String>,
+    _config_dir: PathDir,
+    _options: RunOptions,
+) -> Result<()> {
+    Err(Error::Command(String::from(
+        "--ui requires machine_setup to be built with the \"tui\" feature",
+    )))
+}
+
+fn get_config_file_name(config_path: &str) -> Result<String> {
+    Path::new(config_path)
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .ok_or_else(|| Error::Config(String::from("The config path is invalid")))
+}
+
+fn validate_purge(args: &Args) -> Result<()> {
+    if !args.purge {
+        return Ok(());
+    }
+
+    if !matches!(args.command, SubCommand::Uninstall) {
+        return Err(Error::Validation(String::from(
+            "--purge can only be used with uninstall",
+        )));
+    }
+
+    // state and downloads are shared by all tasks
+    if args.task.is_some() || args.tag.is_some() || args.select {
+        return Err(Error::Validation(String::from(
+            "--purge removes the state of all tasks and can't be combined with a task selection",
+        )));
+    }
+
+    Ok(())
+}
+
+fn get_host_selection(args: &Args) -> Option<HostSelection> {
+    if args.all {
+        return Some(HostSelection::All);
+    }
+
+    if let Some(group) = &args.group {
+        return Some(HostSelection::Group(group.to_string()));
+    }
+
+    args.host
+        .as_ref()
+        .map(|host| HostSelection::Host(host.to_string()))
+}
+
+fn get_vars(args: &Args) -> Result<BTreeMap<String, String>> {
+    args.vars.iter().map(|var| parse_variable(var)).collect()
+}
+
+fn run_remote(
+    selection: HostSelection,
+    inventory: Option<&str>,
+    run: RemoteRun,
+    config_dir: PathDir,
+) -> Result<()> {
+    let inventory = match inventory {
+        Some(path) => load_inventory(path, &config_dir)?,
+        None => Inventory::default(),
+    };
+
+    // a single host doesn't need to be part of the inventory
+    if let HostSelection::Host(host) = &selection {
+        if inventory.get_host(host).is_none() {
+            let target = SshTarget::from_str(host).map_err(Error::Validation)?;
+            return remote::run(&target, &run, &config_dir);
+        }
+    }
+
+    let hosts = inventory.get_hosts(&selection)?;
+
+    remote::run_hosts(&hosts, &run, &config_dir, inventory.parallel)
+}
+
 fn get_task_from_args(args: &Args, tasks: &[Task]) -> Result<Option<String>, String> {
     if let Some(task_name) = &args.task {
         return Ok(Some(task_name.to_string()));
@@ -63,32 +189,147 @@ fn get_absolute_path(config_path: &str) -> Result<PathBuf, String> {
     Ok(absolute_path.unwrap())
 }
 
-pub fn execute_command(args: Args) {
-    let config_path = expand(&args.config);
-    if let Err(err_config_path) = config_path {
-        error!("{}", Red.paint(err_config_path.to_string()));
-        return;
+fn print_bootstrap_script(args: &Args) -> Result<()> {
+    let SubCommand::BootstrapScript {
+        repo,
+        config_file,
+        format,
+        release,
+    } = &args.command
+    else {
+        return Ok(());
+    };
+
+    let format = ScriptFormat::from_str(format).map_err(Error::Validation)?;
+    let options = BootstrapOptions {
+        repo: repo.to_string(),
+        config_file: config_file.to_string(),
+        task: args.task.clone(),
+        release: release.clone().unwrap_or_else(get_current_release),
+    };
+
+    print!("{}", get_bootstrap_script(&options, format));
+
+    Ok(())
+}
+
+fn expand_arg(path: &str) -> Result<String> {
+    expand(path)
+        .map(|path| path.to_string())
+        .map_err(|e| Error::Config(e.to_string()))
+}
+
+fn write_generated_config(config: String, output: &Option<String>) -> Result<()> {
+    match output {
+        Some(output) => {
+            let output = expand_arg(output)?;
+            fs::write(&output, config).map_err(|e| Error::io(&output, e))
+        }
+        None => {
+            print!("{config}");
+            Ok(())
+        }
     }
-    let config_path = config_path.unwrap();
+}
 
-    let config = get_config(&config_path);
-    if let Err(err_config) = config {
-        error!("{}", Red.paint(err_config));
-        return;
+fn import_config(from: &ImportSource) -> Result<()> {
+    let ImportSource::Chezmoi { path, output } = from;
+
+    let import = import_chezmoi(Path::new(&expand_arg(path)?))?;
+
+    for skipped in &import.skipped {
+        warn!("Not imported: {skipped}");
     }
 
-    let task_list = config.unwrap();
+    write_generated_config(get_import_config(&import), output)
+}
+
+fn discover_config(args: &Args) -> Result<()> {
+    let SubCommand::Discover {
+        repo,
+        home,
+        max_depth,
+        output,
+    } = &args.command
+    else {
+        return Ok(());
+    };
+
+    let links = discover_links(
+        Path::new(&expand_arg(repo)?),
+        Path::new(&expand_arg(home)?),
+        *max_depth,
+    )?;
+
+    write_generated_config(get_discovered_config(&links), output)
+}
+
+fn run_container_tests(
+    args: &Args,
+    images: &[String],
+    engine: &Option<String>,
+    config_path: &str,
+) -> Result<()> {
+    let engine = match engine {
+        Some(engine) => Engine::from_str(engine).map_err(Error::Validation)?,
+        None => find_engine()?,
+    };
+
+    let config_dir = get_absolute_path(config_path).map_err(Error::Config)?;
+    let config_dir =
+        PathDir::new(config_dir.as_path()).map_err(|e| Error::Config(e.to_string()))?;
+
+    let test = ContainerTest {
+        image: String::new(),
+        config_file: get_config_file_name(config_path)?,
+        task: args.task.clone(),
+        level: args.level,
+    };
+
+    run_tests(engine, images, &test, &config_dir)
+}
+
+fn schedule_runs(args: &Args, every: &str, mode: &str, config_path: &str) -> Result<()> {
+    if !["install", "update", "upgrade"].contains(&mode) {
+        return Err(Error::Validation(format!(
+            "Only install, update and upgrade can be scheduled, not {mode}"
+        )));
+    }
 
+    let config_dir = get_absolute_path(config_path).map_err(Error::Config)?;
+    let binary = env::current_exe().map_err(|e| Error::io("machine_setup", e))?;
+
+    let run = Schedule {
+        binary,
+        config_file: config_dir.join(get_config_file_name(config_path)?),
+        mode: mode.to_string(),
+        task: args.task.clone(),
+        interval: Interval::from_str(every).map_err(Error::Validation)?,
+    };
+
+    schedule::schedule(&run)
+}
+
+pub fn execute_command(args: Args) -> Result<()> {
+    // don't need a local config
     match args.command {
-        SubCommand::Install | SubCommand::Uninstall | SubCommand::Update => {
-            let task_name = get_task_from_args(&args, &task_list.tasks);
+        SubCommand::BootstrapScript { .. } => return print_bootstrap_script(&args),
+        SubCommand::Unschedule => return schedule::unschedule(),
+        SubCommand::Import { ref from } => return import_config(from),
+        SubCommand::Discover { .. } => return discover_config(&args),
+        _ => {}
+    }
 
-            if let Err(err_task_name) = task_name {
-                error!("{}", Red.paint(err_task_name));
-                return;
-            }
+    let config_path = expand(&args.config).map_err(|e| Error::Config(e.to_string()))?;
+    let task_list = get_config(&config_path)?;
+
+    match args.command {
+        SubCommand::Install | SubCommand::Uninstall | SubCommand::Update | SubCommand::Upgrade => {
+            let task_name =
+                get_task_from_args(&args, &task_list.tasks).map_err(Error::Validation)?;
 
-            let mode = get_task_runner_mode(args.command);
+            validate_purge(&args)?;
+            let mode = get_task_runner_mode(&args.command);
 
             let absolute_path = get_absolute_path(&config_path);
             if let Err(err_path) = absolute_path {
@@ -110,17 +351,72 @@ pub fn execute_command(args: Args) {
             }
             let absolute_path = absolute_path.unwrap();
 
-            let run = task_runner::run(
-                task_list,
-                mode,
-                task_name.unwrap(),
-                PathDir::new(absolute_path.as_path()).unwrap(),
-            );
+            let vars = get_vars(&args)?;
+
+            if let Some(selection) = get_host_selection(&args) {
+                let run = RemoteRun {
+                    mode,
+                    config_file: get_config_file_name(&config_path)?,
+                    task: task_name,
+                    tag: args.tag.clone(),
+                    frozen: args.frozen,
+                    purge: args.purge,
+                    force: args.force,
+                    reboot: args.reboot,
+                    debug: args.debug,
+                    level: args.level,
+                    vars,
+                };
+
+                return run_remote(
+                    selection,
+                    task_list.inventory.as_deref(),
+                    run,
+                    PathDir::new(absolute_path.as_path()).unwrap(),
+                );
+            }
 
-            if run.is_err() {
-                error!("{}", Red.paint(run.unwrap_err()));
+            let options = RunOptions {
+                frozen: args.frozen,
+                tag: args.tag.clone(),
+                purge: args.purge,
+                force: args.force,
+                reboot: args.reboot,
+                config_file: Some(get_config_file_name(&resolve_config_path(&config_path)?)?),
+                vars,
+                ..Default::default()
+            };
+
+            if args.ui {
+                run_ui(
+                    task_list,
+                    mode,
+                    task_name,
+                    PathDir::new(absolute_path.as_path()).unwrap(),
+                    options,
+                )
+            } else {
+                task_runner::run(
+                    task_list,
+                    mode,
+                    task_name,
+                    PathDir::new(absolute_path.as_path()).unwrap(),
+                    options,
+                )
             }
         }
+        SubCommand::Test {
+            ref image,
+            ref engine,
+        } => run_container_tests(&args, image, engine, &config_path),
+        SubCommand::Schedule {
+            ref every,
+            ref mode,
+        } => schedule_runs(&args, every, mode, &config_path),
+        SubCommand::BootstrapScript { .. }
+        | SubCommand::Unschedule
+        | SubCommand::Import { .. }
+        | SubCommand::Discover { .. } => Ok(()),
         SubCommand::List => {
             println!(
                 "\n\tTasks\n\t--------------------------------\n{}\n\t--------------------------------",
@@ -130,12 +426,15 @@ pub fn execute_command(args: Args) {
                     .collect::<Vec<String>>()
                     .join("\n")
             );
+
+            Ok(())
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use clap::Parser;
     use tracing::Level;
 
     use super::*;
@@ -146,15 +445,29 @@ mod test {
             command: SubCommand::Install,
             config: "./machine_setup.yaml".to_string(),
             task: Some("test".to_string()),
+            tag: None,
             select: false,
             level: Level::ERROR,
             debug: false,
+            frozen: false,
+            purge: false,
+            force: false,
+            ui: false,
+            reboot: false,
+            host: None,
+            all: false,
+            group: None,
+            vars: vec![],
         };
 
         let tasks = vec![Task {
             name: "test".to_string(),
             commands: vec![],
             os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
             parallel: false,
         }];
 
@@ -169,15 +482,29 @@ mod test {
             command: SubCommand::Install,
             config: "./machine_setup.yaml".to_string(),
             task: Some("test".to_string()),
+            tag: None,
             select: true,
             level: Level::ERROR,
             debug: false,
+            frozen: false,
+            purge: false,
+            force: false,
+            ui: false,
+            reboot: false,
+            host: None,
+            all: false,
+            group: None,
+            vars: vec![],
         };
 
         let tasks = vec![Task {
             name: "test".to_string(),
             commands: vec![],
             os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
             parallel: false,
         }];
 
@@ -186,9 +513,54 @@ mod test {
         assert_eq!(task_name.unwrap(), Some("test".to_string()));
     }
 
+    #[test]
+    fn it_rejects_more_than_one_host_selection() {
+        for flags in [
+            vec!["--host", "vm1", "--all"],
+            vec!["--host", "vm1", "--group", "servers"],
+            vec!["--all", "--group", "servers"],
+            vec!["--all", "--ui"],
+        ] {
+            let err = Args::try_parse_from(["machine_setup", "install"].into_iter().chain(flags))
+                .unwrap_err();
+
+            assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn it_only_purges_when_uninstalling_everything() {
+        let mut args = Args {
+            command: SubCommand::Uninstall,
+            config: "./machine_setup.yaml".to_string(),
+            task: None,
+            tag: None,
+            select: false,
+            level: Level::ERROR,
+            debug: false,
+            frozen: false,
+            purge: true,
+            force: false,
+            ui: false,
+            reboot: false,
+            host: None,
+            all: false,
+            group: None,
+            vars: vec![],
+        };
+        validate_purge(&args).unwrap();
+
+        args.tag = Some("editor".to_string());
+        validate_purge(&args).unwrap_err();
+
+        args.tag = None;
+        args.command = SubCommand::Update;
+        validate_purge(&args).unwrap_err();
+    }
+
     #[test]
     fn it_gets_task_runner_mode() {
-        let mode = get_task_runner_mode(SubCommand::Install);
+        let mode = get_task_runner_mode(&SubCommand::Install);
 
         assert_eq!(mode.to_string(), TaskRunnerMode::Install.to_string());
     }
@@ -1,4 +1,6 @@
 pub type ExitCode = i32;
 
 pub const OK: ExitCode = 0;
+pub const EX_FAILURE: ExitCode = 1;
 pub const EX_IO_ERR: ExitCode = 74;
+pub const EX_INTERRUPTED: ExitCode = 130;
@@ -1,3 +1,5 @@
 pub mod cli;
 pub mod command;
 pub mod exit_codes;
+#[cfg(feature = "tui")]
+pub mod tui;
@@ -0,0 +1,481 @@
+use crossterm::{
+    event::{self, Event as TermEvent, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ergo_fs::PathDir;
+use once_cell::sync::Lazy;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+use regex::Regex;
+use std::{
+    io::{self, Stdout},
+    sync::{atomic::Ordering, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tracing::warn;
+
+use crate::{
+    config::base_config::TaskList,
+    error::{Error, Result},
+    events::{Event, Reporter},
+    task_runner::{
+        get_planned_tasks, run_with_report, RunOptions, RunReport, TaskRunnerMode, TaskStatus,
+    },
+    utils::{progress::SUPPRESS_OUTPUT, sudo::SudoKeepAlive},
+};
+
+const TICK_RATE: Duration = Duration::from_millis(100);
+const PAGE_SIZE: u16 = 10;
+
+static ANSI_ESCAPE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TaskState {
+    Pending,
+    Running,
+    Succeeded,
+    Skipped,
+    Failed,
+    /** failed, but the user decided to ignore it */
+    Ignored,
+}
+
+#[derive(Debug)]
+struct TaskView {
+    name: String,
+    /** removed tasks are uninstalled during an upgrade */
+    mode: TaskRunnerMode,
+    state: TaskState,
+    output: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct UiState {
+    tasks: Vec<TaskView>,
+    running: bool,
+    error: Option<String>,
+}
+
+impl UiState {
+    fn get_task(&mut self, name: &str) -> Option<&mut TaskView> {
+        self.tasks.iter_mut().find(|task| task.name == name)
+    }
+
+    fn push_output(&mut self, name: &str, line: &str) {
+        if let Some(task) = self.get_task(name) {
+            task.output
+                .push(ANSI_ESCAPE.replace_all(line, "").to_string());
+        }
+    }
+}
+
+/**
+ * Collects the events of a run, the UI thread draws the collected state.
+ */
+#[derive(Debug)]
+struct TuiReporter {
+    state: Arc<Mutex<UiState>>,
+}
+
+impl Reporter for TuiReporter {
+    fn report(&self, event: &Event) {
+        let mut state = self.state.lock().unwrap();
+
+        match event {
+            Event::RunStarted { .. } => state.running = true,
+            Event::TaskStarted { task, .. } => {
+                if let Some(view) = state.get_task(task) {
+                    view.state = TaskState::Running;
+                }
+            }
+            Event::CommandStarted { task, command } => {
+                state.push_output(task, &format!("⏳ {command}"))
+            }
+            Event::CommandOutput { task, message, .. } => state.push_output(task, message),
+            // the dashboard shows the output lines only
+            Event::CommandProgress { .. } => {}
+            Event::CommandFinished {
+                task,
+                command,
+                error,
+            } => match error {
+                Some(error) => {
+                    state.push_output(task, &format!("❌ {command}"));
+                    for line in error.lines() {
+                        state.push_output(task, &format!("   {line}"));
+                    }
+                }
+                None => state.push_output(task, &format!("✅ {command}")),
+            },
+            Event::TaskFinished { task, status } => {
+                if let Some(view) = state.get_task(task) {
+                    view.state = match status {
+                        TaskStatus::Succeeded => TaskState::Succeeded,
+                        TaskStatus::Skipped => TaskState::Skipped,
+                        TaskStatus::Failed(_) => TaskState::Failed,
+                    };
+                }
+            }
+            Event::RunFinished { .. } => state.running = false,
+        }
+    }
+}
+
+struct Run {
+    task_list: TaskList,
+    mode: TaskRunnerMode,
+    config_dir: PathDir,
+    options: RunOptions,
+}
+
+impl Run {
+    fn start(&self, task_name: Option<String>) -> JoinHandle<Result<RunReport>> {
+        let task_list = self.task_list.clone();
+        let mode = self.mode;
+        let config_dir = self.config_dir.clone();
+        let options = self.options.clone();
+
+        thread::spawn(move || run_with_report(task_list, mode, task_name, config_dir, options))
+    }
+}
+
+fn get_state_style(state: TaskState) -> (&'static str, Style) {
+    match state {
+        TaskState::Pending => ("  ", Style::default().fg(Color::DarkGray)),
+        TaskState::Running => ("⏳", Style::default().fg(Color::Yellow)),
+        TaskState::Succeeded => ("✅", Style::default().fg(Color::Green)),
+        TaskState::Skipped => ("⏭ ", Style::default().fg(Color::DarkGray)),
+        TaskState::Failed => ("❌", Style::default().fg(Color::Red)),
+        TaskState::Ignored => ("⚠ ", Style::default().fg(Color::Yellow)),
+    }
+}
+
+fn draw(frame: &mut Frame, state: &UiState, list_state: &mut ListState, scroll: u16) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = state
+        .tasks
+        .iter()
+        .map(|task| {
+            let (icon, style) = get_state_style(task.state);
+            let label = match task.mode {
+                TaskRunnerMode::Uninstall => format!("{icon} {} (uninstall)", task.name),
+                _ => format!("{icon} {}", task.name),
+            };
+            ListItem::new(label).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(" Tasks "))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, columns[0], list_state);
+
+    let selected = list_state
+        .selected()
+        .and_then(|index| state.tasks.get(index));
+    let output: Vec<Line> = selected
+        .map(|task| {
+            task.output
+                .iter()
+                .map(|line| Line::from(line.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let title = selected
+        .map(|task| format!(" {} ", task.name))
+        .unwrap_or_default();
+
+    let output = Paragraph::new(output)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((scroll, 0));
+    frame.render_widget(output, columns[1]);
+
+    let help = match (&state.error, state.running) {
+        (Some(error), _) => format!(" {error}"),
+        (None, true) => String::from(" ↑/↓ select  PgUp/PgDn scroll"),
+        (None, false) => {
+            String::from(" ↑/↓ select  PgUp/PgDn scroll  r retry  i ignore failure  q quit")
+        }
+    };
+    frame.render_widget(Paragraph::new(help), rows[1]);
+}
+
+fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+
+    Terminal::new(CrosstermBackend::new(io::stdout()))
+}
+
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
+
+/**
+ * Restores the terminal even if the dashboard panics, otherwise the shell is left in raw mode.
+ */
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        let terminal = setup_terminal()?;
+        // log lines would be drawn on top of the dashboard
+        SUPPRESS_OUTPUT.store(true, Ordering::SeqCst);
+
+        Ok(TerminalGuard { terminal })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        SUPPRESS_OUTPUT.store(false, Ordering::SeqCst);
+        restore_terminal(&mut self.terminal).ok();
+    }
+}
+
+/**
+ * A panic of the run is shown like any other error of the run.
+ */
+fn get_run_result(handle: JoinHandle<Result<RunReport>>) -> Result<RunReport> {
+    handle.join().unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown error"));
+
+        Err(Error::Command(format!("The run panicked: {message}")))
+    })
+}
+
+fn get_terminal_error(err: io::Error) -> Error {
+    Error::Command(format!("Terminal UI: {err}"))
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    run: &Run,
+    state: &Arc<Mutex<UiState>>,
+    task_name: Option<String>,
+) -> io::Result<()> {
+    let mut handle = Some(run.start(task_name));
+    let mut list_state = ListState::default().with_selected(Some(0));
+    let mut scroll: u16 = 0;
+
+    loop {
+        if handle.as_ref().is_some_and(|h| h.is_finished()) {
+            if let Err(err_run) = get_run_result(handle.take().unwrap()) {
+                let mut state = state.lock().unwrap();
+                state.error = Some(err_run.to_string());
+                state.running = false;
+            }
+        }
+
+        terminal.draw(|frame| draw(frame, &state.lock().unwrap(), &mut list_state, scroll))?;
+
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+
+        let TermEvent::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let mut state = state.lock().unwrap();
+        let num_tasks = state.tasks.len();
+        let selected = list_state.selected().unwrap_or(0);
+        let is_idle = handle.is_none();
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc if is_idle => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => {
+                list_state.select(Some(selected.saturating_sub(1)));
+                scroll = 0;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                list_state.select(Some((selected + 1).min(num_tasks.saturating_sub(1))));
+                scroll = 0;
+            }
+            KeyCode::PageUp => scroll = scroll.saturating_sub(PAGE_SIZE),
+            KeyCode::PageDown => scroll = scroll.saturating_add(PAGE_SIZE),
+            KeyCode::Char('r') if is_idle => {
+                if let Some(task) = state.tasks.get_mut(selected) {
+                    if task.state == TaskState::Failed {
+                        task.output.clear();
+                        state.error = None;
+                        handle = Some(run.start(Some(state.tasks[selected].name.to_string())));
+                    }
+                }
+            }
+            KeyCode::Char('i') if is_idle => {
+                if let Some(task) = state.tasks.get_mut(selected) {
+                    if task.state == TaskState::Failed {
+                        task.state = TaskState::Ignored;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/**
+ * Runs the tasks while showing a full-screen dashboard instead of progress bars.
+ * Failed tasks can be retried or their failure ignored once the run is finished,
+ * ignored failures are still reported after leaving the dashboard.
+ */
+pub fn run(
+    mut task_list: TaskList,
+    mode: TaskRunnerMode,
+    task_name: Option<String>,
+    config_dir: PathDir,
+    options: RunOptions,
+) -> Result<()> {
+    // the password prompt can't be shown inside of the dashboard
+    let _sudo_keep_alive = if task_list.sudo {
+        task_list.sudo = false;
+        Some(SudoKeepAlive::start()?)
+    } else {
+        None
+    };
+
+    let planned_tasks =
+        get_planned_tasks(&task_list, mode, task_name.clone(), &config_dir, &options)?;
+
+    let state = Arc::new(Mutex::new(UiState {
+        tasks: planned_tasks
+            .into_iter()
+            .map(|(name, mode)| TaskView {
+                name,
+                mode,
+                state: TaskState::Pending,
+                output: vec![],
+            })
+            .collect(),
+        running: true,
+        error: None,
+    }));
+
+    let run = Run {
+        task_list,
+        mode,
+        config_dir,
+        options: RunOptions {
+            reporter: Some(Arc::new(TuiReporter {
+                state: Arc::clone(&state),
+            })),
+            ..options
+        },
+    };
+
+    let mut guard = TerminalGuard::new().map_err(get_terminal_error)?;
+    let result = event_loop(&mut guard.terminal, &run, &state, task_name);
+    drop(guard);
+    result.map_err(get_terminal_error)?;
+
+    let state = state.lock().unwrap();
+    if let Some(error) = &state.error {
+        return Err(Error::Command(error.to_string()));
+    }
+
+    for task in &state.tasks {
+        if task.state == TaskState::Ignored {
+            warn!("Task \"{}\" failed, the failure was ignored", task.name);
+        }
+    }
+
+    let failed: Vec<String> = state
+        .tasks
+        .iter()
+        .filter(|task| task.state == TaskState::Failed)
+        .map(|task| task.name.to_string())
+        .collect();
+
+    if !failed.is_empty() {
+        return Err(Error::TasksFailed(failed));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_reporter() -> TuiReporter {
+        TuiReporter {
+            state: Arc::new(Mutex::new(UiState {
+                tasks: vec![TaskView {
+                    name: String::from("git"),
+                    mode: TaskRunnerMode::Install,
+                    state: TaskState::Pending,
+                    output: vec![],
+                }],
+                running: false,
+                error: None,
+            })),
+        }
+    }
+
+    #[test]
+    fn it_collects_task_output_and_status() {
+        let reporter = get_reporter();
+
+        reporter.report(&Event::TaskStarted {
+            task: String::from("git"),
+            num_commands: 1,
+        });
+        reporter.report(&Event::CommandOutput {
+            task: String::from("git"),
+            command: String::from("clone"),
+            message: String::from("\x1b[1mCloning\x1b[0m ..."),
+        });
+        reporter.report(&Event::CommandFinished {
+            task: String::from("git"),
+            command: String::from("clone"),
+            error: Some(String::from("exited with code 128")),
+        });
+        reporter.report(&Event::TaskFinished {
+            task: String::from("git"),
+            status: TaskStatus::Failed(String::from("failed")),
+        });
+
+        let state = reporter.state.lock().unwrap();
+        assert_eq!(state.tasks[0].state, TaskState::Failed);
+        assert_eq!(
+            state.tasks[0].output,
+            vec!["Cloning ...", "❌ clone", "   exited with code 128"]
+        );
+    }
+
+    #[test]
+    fn it_reports_a_panicking_run_as_error() {
+        let handle = thread::spawn(|| -> Result<RunReport> { panic!("lock poisoned") });
+
+        let err = get_run_result(handle).unwrap_err();
+        assert!(err.to_string().contains("lock poisoned"), "{err}");
+    }
+}
@@ -0,0 +1,65 @@
+use directories::ProjectDirs;
+use ergo_fs::PathBuf;
+
+static APP_NAME: &str = "machine_setup";
+static FALLBACK_DIR: &str = "~/.machine_setup";
+
+fn get_project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", APP_NAME)
+}
+
+fn to_dir_string(path: PathBuf) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/**
+ * $XDG_CACHE_HOME/machine_setup/tmp on Linux, the platform equivalent elsewhere.
+ * Temp scripts are only needed while a command runs, so they can live next to the cache.
+ */
+pub fn get_default_temp_dir() -> String {
+    get_project_dirs()
+        .map(|dirs| to_dir_string(dirs.cache_dir().join("tmp")))
+        .unwrap_or_else(|| format!("{FALLBACK_DIR}/tmp"))
+}
+
+/**
+ * $XDG_STATE_HOME/machine_setup on Linux.
+ * Other platforms don't have a state dir, the local data dir is used instead.
+ */
+pub fn get_default_state_dir() -> String {
+    get_project_dirs()
+        .map(|dirs| {
+            to_dir_string(
+                dirs.state_dir()
+                    .unwrap_or_else(|| dirs.data_local_dir())
+                    .to_path_buf(),
+            )
+        })
+        .unwrap_or_else(|| format!("{FALLBACK_DIR}/state"))
+}
+
+/**
+ * $XDG_CACHE_HOME/machine_setup on Linux, the platform equivalent elsewhere.
+ */
+pub fn get_default_cache_dir() -> String {
+    get_project_dirs()
+        .map(|dirs| to_dir_string(dirs.cache_dir().to_path_buf()))
+        .unwrap_or_else(|| format!("{FALLBACK_DIR}/cache"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_uses_separate_dirs() {
+        let temp_dir = get_default_temp_dir();
+        let state_dir = get_default_state_dir();
+        let cache_dir = get_default_cache_dir();
+
+        assert!(temp_dir.contains(APP_NAME));
+        assert!(state_dir.contains(APP_NAME));
+        assert!(temp_dir.starts_with(&cache_dir));
+        assert_ne!(state_dir, cache_dir);
+    }
+}
@@ -0,0 +1,236 @@
+use ergo_fs::{Path, PathBuf};
+use serde_json::{json, Map, Value};
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use tracing::{debug, warn};
+
+use crate::{
+    config::json_config::parse_json_task,
+    error::{Error, Result},
+    task::Task,
+};
+
+/**
+ * Remembers the definition of every task as it was last installed or updated successfully.
+ * `upgrade` diffs the current config against it, so it only has to touch the tasks that changed.
+ */
+#[derive(Debug, Default)]
+pub struct AppliedConfig {
+    path: Option<PathBuf>,
+    /** kept in the order the tasks were applied, removed tasks are uninstalled in reverse */
+    entries: Mutex<Vec<(String, Value)>>,
+    changed: AtomicBool,
+}
+
+/**
+ * Uses the format of the JSON config, so removed tasks can be parsed again to uninstall them.
+ */
+pub fn get_task_definition(task: &Task) -> Value {
+    let commands: Vec<Value> = task
+        .commands
+        .iter()
+        .map(|command| {
+            Value::Object(Map::from_iter([(
+                command.name.to_string(),
+                command.args.to_json(),
+            )]))
+        })
+        .collect();
+
+    json!({
+        "commands": commands,
+        "os": task.os.iter().map(|os| os.to_string()).collect::<Vec<String>>(),
+        "parallel": task.parallel,
+        "tags": task.tags,
+        "when": task.when,
+        "only_if": task.only_if,
+        "skip_if": task.skip_if,
+    })
+}
+
+fn parse_entries(contents: &str) -> Result<Vec<(String, Value)>, String> {
+    let applied: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+
+    let Some(entries) = applied.as_array() else {
+        return Err(String::from("the applied config must be a list"));
+    };
+
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            Some((
+                entry["name"].as_str()?.to_string(),
+                entry.get("task")?.clone(),
+            ))
+        })
+        .collect())
+}
+
+impl AppliedConfig {
+    /**
+     * Every config has its own file, see `get_config_key`.
+     * A broken file only means that every task is treated as new.
+     */
+    pub fn load(state_dir: &Path, config_key: &str) -> Self {
+        let path = state_dir.join(format!("applied_config.{config_key}.json"));
+
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| parse_entries(&contents).ok())
+            .unwrap_or_default();
+
+        AppliedConfig {
+            path: Some(path),
+            entries: Mutex::new(entries),
+            changed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn contains(&self, task: &str) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(name, _)| name == task)
+    }
+
+    pub fn is_unchanged(&self, task: &Task) -> bool {
+        let definition = get_task_definition(task);
+
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(name, applied)| name == &task.name && applied == &definition)
+    }
+
+    /**
+     * The applied tasks that are no longer part of the config, in the order they were applied.
+     */
+    pub fn get_removed_tasks(&self, tasks: &[Task]) -> Vec<Task> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| !tasks.iter().any(|task| &task.name == name))
+            .filter_map(|(name, definition)| {
+                parse_json_task(name, definition)
+                    .map_err(|err_task| warn!("Can't uninstall removed task {name}: {err_task}"))
+                    .ok()
+            })
+            .collect()
+    }
+
+    pub fn store(&self, task: &Task) {
+        let definition = get_task_definition(task);
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.iter_mut().find(|(name, _)| name == &task.name) {
+            Some((_, applied)) if *applied == definition => return,
+            Some((_, applied)) => *applied = definition,
+            None => entries.push((task.name.to_string(), definition)),
+        }
+
+        self.changed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn remove(&self, task: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        let num_entries = entries.len();
+        entries.retain(|(name, _)| name != task);
+
+        if entries.len() != num_entries {
+            self.changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if !self.changed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let entries: Vec<Value> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, definition)| json!({ "name": name, "task": definition }))
+            .collect();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+        }
+
+        let contents = serde_json::to_string_pretty(&Value::Array(entries))
+            .map_err(|e| Error::Command(e.to_string()))?;
+        fs::write(path, contents + "\n").map_err(|e| Error::io(path, e))?;
+        debug!("Updated {}", path.display());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    use crate::config::{base_config::Command, config_value::ConfigValue, os::Os};
+
+    use super::*;
+
+    fn get_run_task(name: &str, commands: &str) -> Task {
+        Task {
+            name: name.to_string(),
+            commands: vec![Command {
+                name: String::from("run"),
+                args: ConfigValue::Hash(HashMap::from([(
+                    String::from("commands"),
+                    ConfigValue::String(commands.to_string()),
+                )])),
+            }],
+            os: vec![Os::Linux],
+            tags: vec![String::from("cli")],
+            when: vec![],
+            only_if: None,
+            skip_if: Some(String::from("command -v git")),
+            parallel: false,
+        }
+    }
+
+    #[test]
+    fn it_remembers_applied_tasks() {
+        let dir = tempdir().unwrap();
+        let git = get_run_task("git", "apt-get install -y git");
+        let fzf = get_run_task("fzf", "apt-get install -y fzf");
+
+        let applied = AppliedConfig::load(dir.path(), "work");
+        applied.store(&git);
+        applied.store(&fzf);
+        applied.save().unwrap();
+
+        assert!(!AppliedConfig::load(dir.path(), "personal").contains("git"));
+
+        let applied = AppliedConfig::load(dir.path(), "work");
+        assert!(applied.is_unchanged(&git));
+        assert!(!applied.is_unchanged(&get_run_task("git", "brew install git")));
+        assert!(applied.contains("fzf"));
+
+        let removed = applied.get_removed_tasks(&[git]);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(get_task_definition(&removed[0]), get_task_definition(&fzf));
+
+        applied.remove("fzf");
+        applied.save().unwrap();
+        assert!(!AppliedConfig::load(dir.path(), "work").contains("fzf"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex},
+};
+use tracing::debug;
+
+use crate::error::Result;
+
+use super::network::NetworkConfig;
+
+/**
+ * Shared by all commands of a run, so parallel tasks reuse connections
+ * and don't open too many connections to the same host at once.
+ */
+#[derive(Debug)]
+pub struct ConnectionPool {
+    network: NetworkConfig,
+    /** agents are cached per proxy, every agent keeps its own pool of idle connections */
+    agents: Mutex<HashMap<Option<String>, ureq::Agent>>,
+    max_per_host: usize,
+    active: Mutex<HashMap<String, usize>>,
+    released: Condvar,
+}
+
+/**
+ * Allows one connection to a host until it's dropped.
+ */
+pub struct HostPermit<'a> {
+    pool: &'a ConnectionPool,
+    host: Option<String>,
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        let Some(host) = &self.host else {
+            return;
+        };
+
+        let mut active = self.pool.active.lock().unwrap();
+        if let Some(count) = active.get_mut(host) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(host);
+            }
+        }
+        self.pool.released.notify_all();
+    }
+}
+
+/**
+ * Supports URLs (`https://user@host:port/path`) and the scp-like syntax of git (`git@host:path`).
+ * Local paths don't have a host.
+ */
+pub fn get_host(url: &str) -> Option<String> {
+    let authority = match url.split_once("://") {
+        Some((scheme, rest)) if scheme != "file" => rest.split('/').next()?,
+        Some(_) => return None,
+        None => url.split_once(':')?.0,
+    };
+
+    let host = authority.rsplit('@').next()?;
+    if host.is_empty() || host.contains('/') {
+        return None;
+    }
+
+    Some(host.to_lowercase())
+}
+
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        ConnectionPool::new(&NetworkConfig::default())
+    }
+}
+
+impl ConnectionPool {
+    pub fn new(network: &NetworkConfig) -> Self {
+        ConnectionPool {
+            network: network.clone(),
+            agents: Mutex::new(HashMap::new()),
+            max_per_host: network.get_max_connections_per_host(),
+            active: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn get_network(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    pub fn get_agent(&self, url: &str) -> Result<ureq::Agent> {
+        let proxy = self.network.get_proxy(url);
+
+        let mut agents = self.agents.lock().unwrap();
+        if let Some(agent) = agents.get(&proxy) {
+            return Ok(agent.clone());
+        }
+
+        let agent = self.network.get_agent(url)?;
+        agents.insert(proxy, agent.clone());
+
+        Ok(agent)
+    }
+
+    /**
+     * Blocks until fewer than `max_connections_per_host` connections to the host of the URL are open.
+     */
+    pub fn acquire(&self, url: &str) -> HostPermit<'_> {
+        let host = get_host(url);
+
+        if let Some(host) = &host {
+            let mut active = self.active.lock().unwrap();
+            while active.get(host).copied().unwrap_or_default() >= self.max_per_host {
+                debug!("Waiting for a free connection to {host} ...");
+                active = self.released.wait(active).unwrap();
+            }
+            *active.entry(host.to_string()).or_default() += 1;
+        }
+
+        HostPermit { pool: self, host }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn it_gets_the_host_of_urls() {
+        assert_eq!(
+            get_host("https://GitHub.com/timopruesse/machine_setup.git"),
+            Some(String::from("github.com"))
+        );
+        assert_eq!(
+            get_host("https://user@example.com:8443/file"),
+            Some(String::from("example.com:8443"))
+        );
+        assert_eq!(
+            get_host("git@github.com:timopruesse/machine_setup.git"),
+            Some(String::from("github.com"))
+        );
+        assert_eq!(get_host("/home/timo/repo"), None);
+        assert_eq!(get_host("file:///home/timo/repo"), None);
+    }
+
+    #[test]
+    fn it_limits_the_connections_per_host() {
+        let pool = Arc::new(ConnectionPool::default());
+        let max_per_host = pool.max_per_host;
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..max_per_host * 3)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                let active = Arc::clone(&active);
+                let max_active = Arc::clone(&max_active);
+
+                thread::spawn(move || {
+                    let _permit = pool.acquire("https://example.com/file");
+                    let current = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_active.fetch_max(current, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(max_active.load(Ordering::SeqCst), max_per_host);
+        assert!(pool.active.lock().unwrap().is_empty());
+    }
+}
@@ -1,14 +1,22 @@
 use ergo_fs::{expand, Path, PathBuf, PathDir, WalkDir};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use rayon::prelude::*;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fs::create_dir_all,
+    path::Component,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 use tracing::info;
 
-use crate::config::{
-    config_value::ConfigValue,
-    validation_rules::required::Required,
-    validator::{validate_named_args, ValidationRule},
+use crate::{
+    config::{
+        config_value::ConfigValue,
+        validation_rules::{is_bool::IsBool, required::Required},
+        validator::{validate_named_args, ValidationRule},
+    },
+    error::{Error, Result},
+    events::Progress,
 };
 
 pub fn is_file_path(path: &Path) -> bool {
@@ -43,28 +51,85 @@ fn create_missing_directories(path: &PathBuf) -> Result<(), std::io::Error> {
     create_dir_all(path)
 }
 
-pub fn expand_path(path: &str, create: bool) -> Result<PathBuf, String> {
-    let expanded_path = expand(path).map_err(|err| err.to_string())?;
+pub fn expand_path(path: &str, create: bool) -> Result<PathBuf> {
+    let expanded_path = expand(path).map_err(|err| Error::Validation(err.to_string()))?;
     let expanded_path = PathBuf::from(expanded_path.to_string());
 
     if create {
         let create_result = create_missing_directories(&expanded_path);
         if let Err(err_create_missing_directories) = create_result {
-            return Err(err_create_missing_directories.to_string());
+            return Err(Error::io(&expanded_path, err_create_missing_directories));
         }
     }
 
     Ok(expanded_path)
 }
 
+/**
+ * Both paths need to be absolute.
+ * The result is the path that leads from `base` to `path`, e.g. "../dotfiles/.vimrc".
+ */
+pub fn get_relative_path(base: &Path, path: &Path) -> PathBuf {
+    let base_components: Vec<Component> = base.components().collect();
+    let path_components: Vec<Component> = path.components().collect();
+
+    let num_common = base_components
+        .iter()
+        .zip(path_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative_path = PathBuf::new();
+    for _ in num_common..base_components.len() {
+        relative_path.push("..");
+    }
+    for component in &path_components[num_common..] {
+        relative_path.push(component.as_os_str());
+    }
+
+    relative_path
+}
+
+static GIT_DIR: &str = ".git";
+
 pub static DIR_SRC: &str = "src";
 pub static DIR_TARGET: &str = "target";
 pub static DIR_IGNORE: &str = "ignore";
+pub static DIR_FILES: &str = "files";
+pub static DIR_MAX_DEPTH: &str = "max_depth";
+pub static DIR_INCLUDE_HIDDEN: &str = "include_hidden";
+pub static DIR_FOLLOW_SYMLINKS: &str = "follow_symlinks";
+
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub ignore: Vec<String>,
+    pub max_depth: Option<usize>,
+    pub include_hidden: bool,
+    pub follow_symlinks: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            ignore: vec![],
+            max_depth: None,
+            include_hidden: true,
+            follow_symlinks: false,
+        }
+    }
+}
 
 pub struct Dirs {
     pub src: String,
     pub target: String,
-    pub ignore: HashSet<String>,
+    pub walk_options: WalkOptions,
+}
+
+pub fn get_bool_arg(args: &ConfigValue, name: &str) -> bool {
+    args.as_hash()
+        .and_then(|hash| hash.get(name))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
 }
 
 pub fn get_relative_dir(root: &PathDir, dir: &str) -> String {
@@ -75,7 +140,7 @@ pub fn get_relative_dir(root: &PathDir, dir: &str) -> String {
     root.join(dir).to_string()
 }
 
-pub fn get_source_and_target(args: ConfigValue, root: &PathDir) -> Result<Dirs, String> {
+pub fn get_source_and_target(args: ConfigValue, root: &PathDir) -> Result<Dirs> {
     let src_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
     let target_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(Required {})];
 
@@ -105,91 +170,339 @@ pub fn get_source_and_target(args: ConfigValue, root: &PathDir) -> Result<Dirs,
 
     let relative_target_dir = get_relative_dir(root, target_dir);
     if relative_target_dir.is_empty() {
-        return Err(String::from("Target directory cannot be empty"));
+        return Err(Error::Validation(String::from(
+            "Target directory cannot be empty",
+        )));
     }
 
     let relative_src_dir = get_relative_dir(root, src_dir);
 
-    let ignore: HashSet<String> = args
-        .as_hash()
-        .unwrap()
-        .get(DIR_IGNORE)
-        .unwrap_or(&ConfigValue::Array(vec![]))
-        .as_vec()
-        .unwrap()
-        .iter()
-        .map(|value| value.as_str().unwrap().to_owned())
-        .collect();
+    let walk_options = get_walk_options(&args)?;
 
     Ok(Dirs {
         src: relative_src_dir,
         target: relative_target_dir,
-        ignore,
+        walk_options,
     })
 }
 
-// TODO: improve this with a better regex approach :)
-fn is_ignored(path: &Path, source: &Path, ignore: &HashSet<String>) -> bool {
-    if let Ok(rel_path) = path.strip_prefix(source) {
-        if let Some(rel_str) = rel_path.to_str() {
-            return ignore.contains(rel_str);
+pub struct FileMapping {
+    pub src: String,
+    pub target: String,
+}
+
+/**
+ * The `files` argument maps source files to differently named targets, e.g.:
+ *   files:
+ *     gitconfig-work: "~/.gitconfig"
+ *
+ * Source files are resolved relative to `src` if it's set, otherwise relative to the config dir.
+ */
+pub fn get_file_mappings(args: &ConfigValue, root: &PathDir) -> Result<Option<Vec<FileMapping>>> {
+    let arg_values = match args.as_hash() {
+        Some(arg_values) => arg_values,
+        None => return Ok(None),
+    };
+
+    let files = match arg_values.get(DIR_FILES) {
+        Some(files) => files,
+        None => return Ok(None),
+    };
+
+    let files = files.as_hash().ok_or_else(|| {
+        Error::Validation(format!(
+            "{DIR_FILES}: argument must be a map of source -> target"
+        ))
+    })?;
+
+    let src_dir = arg_values
+        .get(DIR_SRC)
+        .and_then(|src| src.as_str())
+        .map(|src| get_relative_dir(root, src));
+
+    let mut mappings = vec![];
+    for (src, target) in files {
+        let target = target
+            .as_str()
+            .filter(|target| !target.is_empty())
+            .ok_or_else(|| {
+                Error::Validation(format!("{DIR_FILES}: {src}: target must be a path"))
+            })?;
+
+        let src = match &src_dir {
+            Some(src_dir) => Path::new(src_dir).join(src).to_string_lossy().to_string(),
+            None => get_relative_dir(root, src),
+        };
+
+        mappings.push(FileMapping {
+            src,
+            target: get_relative_dir(root, target),
+        });
+    }
+
+    mappings.sort_by(|a, b| a.src.cmp(&b.src));
+
+    Ok(Some(mappings))
+}
+
+/**
+ * Unlike `expand_path`, this never treats the target as a directory.
+ * Only the parent directories are created.
+ */
+pub fn expand_file_target(target: &str) -> Result<PathBuf> {
+    let expanded_target = expand_path(target, false)?;
+
+    if let Some(parent) = expanded_target.parent() {
+        create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+
+    Ok(expanded_target)
+}
+
+pub fn get_ignore_patterns(args: &ConfigValue) -> Result<Vec<String>> {
+    let ignore = args
+        .as_hash()
+        .and_then(|arg_values| arg_values.get(DIR_IGNORE))
+        .unwrap_or(&ConfigValue::Null);
+
+    match ignore {
+        ConfigValue::Null => Ok(vec![]),
+        ConfigValue::String(pattern) => Ok(vec![pattern.to_owned()]),
+        ConfigValue::Array(patterns) => patterns
+            .iter()
+            .map(|pattern| {
+                pattern.as_str().map(|p| p.to_owned()).ok_or_else(|| {
+                    Error::Validation(format!("{DIR_IGNORE}: patterns must be strings"))
+                })
+            })
+            .collect(),
+        _ => Err(Error::Validation(format!(
+            "{DIR_IGNORE}: argument must be a list of patterns"
+        ))),
+    }
+}
+
+pub fn get_walk_options(args: &ConfigValue) -> Result<WalkOptions> {
+    let include_hidden_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+    let follow_symlinks_rules: Vec<Box<dyn ValidationRule>> = vec![Box::new(IsBool {})];
+
+    validate_named_args(
+        args.to_owned(),
+        HashMap::from([
+            (String::from(DIR_INCLUDE_HIDDEN), include_hidden_rules),
+            (String::from(DIR_FOLLOW_SYMLINKS), follow_symlinks_rules),
+        ]),
+    )?;
+
+    let arg_values = args.as_hash();
+
+    let max_depth = match arg_values.and_then(|arg_values| arg_values.get(DIR_MAX_DEPTH)) {
+        None | Some(ConfigValue::Null) => None,
+        Some(ConfigValue::Integer(depth)) if *depth >= 0 => Some(*depth as usize),
+        Some(_) => {
+            return Err(Error::Validation(format!(
+                "{DIR_MAX_DEPTH}: argument must be a positive number"
+            )))
+        }
+    };
+
+    let include_hidden = arg_values
+        .and_then(|arg_values| arg_values.get(DIR_INCLUDE_HIDDEN))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true);
+
+    Ok(WalkOptions {
+        ignore: get_ignore_patterns(args)?,
+        max_depth,
+        include_hidden,
+        follow_symlinks: get_bool_arg(args, DIR_FOLLOW_SYMLINKS),
+    })
+}
+
+pub static IGNORE_FILE: &str = ".msignore";
+
+/**
+ * Patterns follow the gitignore syntax, e.g. "*.md", "dist/" or "!keep.md".
+ * An optional `.msignore` file in the source directory is read first,
+ * so the patterns from the config take precedence.
+ */
+fn get_ignore_matcher(source: &Path, patterns: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(source);
+
+    let ignore_file = source.join(IGNORE_FILE);
+    if ignore_file.is_file() {
+        if let Some(err_ignore_file) = builder.add(&ignore_file) {
+            return Err(Error::Validation(format!(
+                "{IGNORE_FILE}: {err_ignore_file}"
+            )));
         }
     }
-    false
+
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| Error::Validation(format!("{DIR_IGNORE}: {e}")))?;
+    }
+
+    builder
+        .build()
+        .map_err(|e| Error::Validation(format!("{DIR_IGNORE}: {e}")))
 }
 
-pub fn walk_files<O: Fn(&Path, &Path)>(
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(false)
+}
+
+fn is_ignored(path: &Path, source: &Path, matcher: &Gitignore, options: &WalkOptions) -> bool {
+    if path.parent() == Some(source) && path.file_name() == Some(IGNORE_FILE.as_ref()) {
+        return true;
+    }
+
+    if !options.include_hidden && is_hidden(path) {
+        return true;
+    }
+
+    matcher.matched(path, path.is_dir()).is_ignore()
+}
+
+/**
+ * Directories are created while walking, so they exist before any of their files are processed.
+ * The files are processed in parallel afterwards, errors are reported in the order of the files.
+ */
+pub fn walk_files<O: Fn(&Path, &Path) -> Result<()> + Sync>(
     source: &PathBuf,
     target: &Path,
-    ignore: HashSet<String>,
+    options: &WalkOptions,
+    progress: &Progress,
     op: O,
-) -> Result<(), String> {
+) -> Result<()> {
     if !source.exists() {
-        return Err(format!(
+        return Err(Error::Command(format!(
             "Source directory/file does not exist: {}",
             source.to_string_lossy()
-        ));
+        )));
     }
 
     if source.is_file() {
         let source_ext = source.extension().unwrap_or_default();
         let target_ext = target.extension().unwrap_or_default();
 
-        match source_ext == target_ext {
-            true => {
-                op(source, target);
-            }
-            false => {
-                op(source, target.join(source.file_name().unwrap()).as_path());
-            }
-        }
-
-        return Ok(());
+        return match source_ext == target_ext {
+            true => op(source, target),
+            false => op(source, target.join(source.file_name().unwrap()).as_path()),
+        };
     }
 
-    for dir_entry in WalkDir::new(source).min_depth(1).into_iter() {
-        let dir_entry = dir_entry.unwrap();
-        let source_path = dir_entry.path();
+    let matcher = get_ignore_matcher(source, &options.ignore)?;
 
-        if is_ignored(source_path, source, &ignore) {
-            info!("Skipping {} ...", source_path.to_string_lossy());
-            continue;
+    let mut errors: Vec<Error> = vec![];
+    let mut files: Vec<(PathBuf, PathBuf)> = vec![];
+
+    let mut walk_dir = WalkDir::new(source)
+        .min_depth(1)
+        .follow_links(options.follow_symlinks)
+        .sort_by_file_name();
+    if let Some(max_depth) = options.max_depth {
+        walk_dir = walk_dir.max_depth(max_depth);
+    }
+
+    // ignored directories are skipped entirely, so none of their files are visited
+    let entries = walk_dir.into_iter().filter_entry(|entry| {
+        let ignored = is_ignored(entry.path(), source, &matcher, options);
+        if ignored {
+            info!("Skipping {} ...", entry.path().to_string_lossy());
         }
+        !ignored
+    });
+
+    for dir_entry in entries {
+        let dir_entry = match dir_entry {
+            Ok(dir_entry) => dir_entry,
+            Err(err_walk) => {
+                errors.push(Error::Command(err_walk.to_string()));
+                continue;
+            }
+        };
+        let source_path = dir_entry.path();
 
         let destination_path = target.join(source_path.strip_prefix(source).unwrap());
 
         if source_path.is_dir() {
             let create_result = create_dir_all(&destination_path);
             if let Err(err_create) = create_result {
-                return Err(err_create.to_string());
+                return Err(Error::io(destination_path, err_create));
             }
             continue;
         }
 
-        op(source_path, &destination_path);
+        files.push((source_path.to_path_buf(), destination_path));
     }
 
-    Ok(())
+    let num_files = files.len();
+    // reporting every single file would only slow down large trees
+    let report_interval = (num_files / 100).max(1);
+    let num_done = AtomicUsize::new(0);
+
+    let file_errors: Vec<Error> = files
+        .par_iter()
+        .filter_map(|(source_path, destination_path)| {
+            let result = op(source_path, destination_path);
+
+            let done = num_done.fetch_add(1, Ordering::Relaxed) + 1;
+            if done % report_interval == 0 || done == num_files {
+                progress.set_position(done, num_files);
+            }
+
+            result.err()
+        })
+        .collect();
+    errors.extend(file_errors);
+
+    join_errors(errors)
+}
+
+/**
+ * Everything but the directories of a source tree, without the files that are ignored when walking it.
+ * Links are listed instead of followed and `.git` is always left out.
+ */
+pub fn list_source_files(source: &Path) -> Result<Vec<PathBuf>> {
+    let options = WalkOptions::default();
+    let matcher = get_ignore_matcher(source, &options.ignore)?;
+
+    let files = WalkDir::new(source)
+        .min_depth(1)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.file_name() != GIT_DIR && !is_ignored(entry.path(), source, &matcher, &options)
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_type().is_dir())
+        .map(|entry| entry.path().to_path_buf())
+        .collect();
+
+    Ok(files)
+}
+
+/**
+ * Errors of single files shouldn't stop the whole operation.
+ * They are collected and reported together at the end.
+ */
+pub fn join_errors(errors: Vec<Error>) -> Result<()> {
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.into_iter().next().unwrap()),
+        _ => Err(Error::Command(
+            errors
+                .iter()
+                .map(|err| err.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        )),
+    }
 }
 
 #[cfg(test)]
@@ -203,7 +516,96 @@ mod test {
         let source = PathBuf::from("/tmp/does_not_exist");
         let target = PathBuf::from("/tmp/target");
 
-        walk_files(&source, &target, HashSet::new(), |_, _| {}).unwrap_err();
+        walk_files(
+            &source,
+            &target,
+            &WalkOptions::default(),
+            &Progress::default(),
+            |_, _| Ok(()),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn it_skips_ignored_files() {
+        let source_dir = tempdir().unwrap();
+        let source_path = source_dir.path();
+        std::fs::create_dir_all(source_path.join("sub").join("test")).unwrap();
+        std::fs::File::create(source_path.join("README.md")).unwrap();
+        std::fs::File::create(source_path.join("KEEP.md")).unwrap();
+        std::fs::File::create(source_path.join("config.txt")).unwrap();
+        std::fs::File::create(source_path.join("local.txt")).unwrap();
+        std::fs::File::create(source_path.join("sub").join("test").join("a.txt")).unwrap();
+        std::fs::write(source_path.join(IGNORE_FILE), "local.txt\n").unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let visited = std::sync::Mutex::new(vec![]);
+
+        walk_files(
+            &PathBuf::from(source_path),
+            target_dir.path(),
+            &WalkOptions {
+                ignore: vec![
+                    String::from("*.md"),
+                    String::from("!KEEP.md"),
+                    String::from("**/test/**"),
+                ],
+                ..Default::default()
+            },
+            &Progress::default(),
+            |src, _| {
+                visited
+                    .lock()
+                    .unwrap()
+                    .push(src.file_name().unwrap().to_string_lossy().to_string());
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        let mut visited = visited.into_inner().unwrap();
+        visited.sort();
+
+        assert_eq!(visited, vec!["KEEP.md", "config.txt"]);
+    }
+
+    #[test]
+    fn it_walks_files_up_to_max_depth() {
+        let source_dir = tempdir().unwrap();
+        let source_path = source_dir.path();
+        std::fs::create_dir_all(source_path.join("b").join("deep")).unwrap();
+        std::fs::File::create(source_path.join("c.txt")).unwrap();
+        std::fs::File::create(source_path.join("a.txt")).unwrap();
+        std::fs::File::create(source_path.join(".hidden")).unwrap();
+        std::fs::File::create(source_path.join("b").join("b.txt")).unwrap();
+        std::fs::File::create(source_path.join("b").join("deep").join("d.txt")).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let visited = std::sync::Mutex::new(vec![]);
+
+        walk_files(
+            &PathBuf::from(source_path),
+            target_dir.path(),
+            &WalkOptions {
+                max_depth: Some(2),
+                include_hidden: false,
+                ..Default::default()
+            },
+            &Progress::default(),
+            |src, _| {
+                visited
+                    .lock()
+                    .unwrap()
+                    .push(src.file_name().unwrap().to_string_lossy().to_string());
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        let mut visited = visited.into_inner().unwrap();
+        visited.sort();
+
+        assert_eq!(visited, vec!["a.txt", "b.txt", "c.txt"]);
     }
 
     #[test]
@@ -227,6 +629,48 @@ mod test {
         assert!(!is_file_path(&PathBuf::from("/tmp/test")));
     }
 
+    #[test]
+    fn it_gets_relative_path() {
+        assert_eq!(
+            get_relative_path(
+                Path::new("/home/user/.config/nvim"),
+                Path::new("/home/user/dotfiles/nvim/init.lua")
+            ),
+            PathBuf::from("../../dotfiles/nvim/init.lua")
+        );
+        assert_eq!(
+            get_relative_path(Path::new("/home/user"), Path::new("/home/user/.vimrc")),
+            PathBuf::from(".vimrc")
+        );
+    }
+
+    #[test]
+    fn it_gets_file_mappings() {
+        let root_dir = tempdir().unwrap();
+        let root = PathDir::new(root_dir.path()).unwrap();
+
+        let mut files = HashMap::new();
+        files.insert(
+            String::from("gitconfig-work"),
+            ConfigValue::String(String::from("~/.gitconfig")),
+        );
+
+        let mut args = HashMap::new();
+        args.insert(
+            String::from("src"),
+            ConfigValue::String(String::from("git")),
+        );
+        args.insert(String::from("files"), ConfigValue::Hash(files));
+
+        let mappings = get_file_mappings(&ConfigValue::Hash(args), &root)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(mappings.len(), 1);
+        assert!(Path::new(&mappings[0].src).ends_with("git/gitconfig-work"));
+        assert_eq!(mappings[0].target, "~/.gitconfig");
+    }
+
     #[test]
     fn it_creates_intermediate_dirs_when_needed() {
         let temp_dir = tempdir().unwrap();
@@ -242,4 +686,28 @@ mod test {
         );
         assert!(target_dir.exists());
     }
+
+    #[test]
+    fn it_collects_errors_of_all_files() {
+        let source_dir = tempdir().unwrap();
+        std::fs::File::create(source_dir.path().join("one.txt")).unwrap();
+        std::fs::File::create(source_dir.path().join("two.txt")).unwrap();
+
+        let target_dir = tempdir().unwrap();
+
+        let err = walk_files(
+            &PathBuf::from(source_dir.path()),
+            target_dir.path(),
+            &WalkOptions::default(),
+            &Progress::default(),
+            |src, _| Err(Error::Command(format!("failed: {}", src.display()))),
+        )
+        .unwrap_err()
+        .to_string();
+
+        // the files are processed in parallel, but the errors keep the order of the files
+        let one = err.find("one.txt").unwrap();
+        let two = err.find("two.txt").unwrap();
+        assert!(one < two);
+    }
 }
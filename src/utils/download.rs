@@ -0,0 +1,220 @@
+use ergo_fs::{Path, PathBuf};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{create_dir_all, read_to_string, rename, write, File},
+    io,
+};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+use super::{
+    connections::ConnectionPool,
+    hash::{get_file_hash, to_hex},
+};
+
+static CACHE_DIR_NAME: &str = "downloads";
+static BLOB_DIR_NAME: &str = "blobs";
+static URL_DIR_NAME: &str = "urls";
+const PARTIAL_SUFFIX_LENGTH: usize = 12;
+
+/**
+ * Downloads are stored by the sha256 of their content, so the same artifact is only stored once,
+ * no matter how many URLs or tasks reference it.
+ * An index maps the hash of every URL to the content it returned.
+ */
+pub struct DownloadCache {
+    root: PathBuf,
+}
+
+fn hash_str(value: &str) -> String {
+    to_hex(&Sha256::digest(value.as_bytes()))
+}
+
+impl DownloadCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        DownloadCache {
+            root: cache_dir.join(CACHE_DIR_NAME),
+        }
+    }
+
+    fn get_blob_path(&self, checksum: &str) -> PathBuf {
+        self.root.join(BLOB_DIR_NAME).join(checksum.to_lowercase())
+    }
+
+    fn get_url_path(&self, url: &str) -> PathBuf {
+        self.root.join(URL_DIR_NAME).join(hash_str(url))
+    }
+
+    /**
+     * A known checksum is looked up directly, even if the file was downloaded from a different URL.
+     */
+    pub fn get(&self, url: &str, checksum: Option<&str>) -> Option<PathBuf> {
+        let checksum = match checksum {
+            Some(checksum) => checksum.to_lowercase(),
+            None => read_to_string(self.get_url_path(url))
+                .ok()?
+                .trim()
+                .to_string(),
+        };
+
+        let blob_path = self.get_blob_path(&checksum);
+
+        blob_path.is_file().then_some(blob_path)
+    }
+
+    /**
+     * Moves a downloaded file into the cache and returns the path of the cached file.
+     * The file is renamed into place, so parallel tasks never see a partially written file.
+     */
+    pub fn insert(&self, url: &str, file: &Path) -> Result<PathBuf> {
+        let checksum = get_file_hash(file)?;
+        let blob_path = self.get_blob_path(&checksum);
+
+        for dir in [self.root.join(BLOB_DIR_NAME), self.root.join(URL_DIR_NAME)] {
+            create_dir_all(&dir).map_err(|e| Error::io(&dir, e))?;
+        }
+
+        if blob_path.is_file() {
+            debug!("{url} is already cached as {checksum}");
+            std::fs::remove_file(file).ok();
+        } else {
+            rename(file, &blob_path).map_err(|e| Error::io(&blob_path, e))?;
+        }
+
+        let url_path = self.get_url_path(url);
+        write(&url_path, &checksum).map_err(|e| Error::io(&url_path, e))?;
+
+        Ok(blob_path)
+    }
+
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /**
+     * Every download gets its own file, parallel tasks might fetch the same URL.
+     */
+    pub fn get_partial_path(&self, url: &str) -> PathBuf {
+        let suffix: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(PARTIAL_SUFFIX_LENGTH)
+            .map(char::from)
+            .collect();
+
+        self.root.join(format!("{}.{suffix}.part", hash_str(url)))
+    }
+}
+
+pub fn fetch(connections: &ConnectionPool, url: &str, target: &Path) -> Result<()> {
+    let url = connections.get_network().rewrite_url(url);
+    let _permit = connections.acquire(&url);
+    debug!("Downloading {url} ...");
+
+    let response = connections
+        .get_agent(&url)?
+        .get(&url)
+        .call()
+        .map_err(|e| Error::Command(format!("Failed to download {url}: {e}")))?;
+
+    if let Some(parent) = target.parent() {
+        create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+
+    let mut file = File::create(target).map_err(|e| Error::io(target, e))?;
+    io::copy(&mut response.into_reader(), &mut file).map_err(|e| Error::io(target, e))?;
+
+    Ok(())
+}
+
+/**
+ * Returns the path of the cached file and downloads it first if necessary.
+ * Files are cached by their original URL, so switching mirrors doesn't invalidate the cache.
+ * With `refresh`, files without a checksum are downloaded again, because the content behind the URL might have changed.
+ */
+pub fn download(
+    cache: &DownloadCache,
+    connections: &ConnectionPool,
+    url: &str,
+    checksum: Option<&str>,
+    refresh: bool,
+) -> Result<PathBuf> {
+    if checksum.is_some() || !refresh {
+        if let Some(cached_path) = cache.get(url, checksum) {
+            debug!("Using cached download for {url}");
+            return Ok(cached_path);
+        }
+    }
+
+    let partial_path = cache.get_partial_path(url);
+    if let Err(err_fetch) = fetch(connections, url, &partial_path) {
+        std::fs::remove_file(&partial_path).ok();
+        return Err(err_fetch);
+    }
+
+    cache.insert(url, &partial_path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_deduplicates_files_with_the_same_content() {
+        let cache_dir = tempdir().unwrap();
+        let cache = DownloadCache::new(cache_dir.path());
+
+        let file_a = cache_dir.path().join("a");
+        let file_b = cache_dir.path().join("b");
+        write(&file_a, "content").unwrap();
+        write(&file_b, "content").unwrap();
+
+        let cached_a = cache.insert("https://example.com/a", &file_a).unwrap();
+        let cached_b = cache
+            .insert("https://mirror.example.com/a", &file_b)
+            .unwrap();
+
+        assert_eq!(cached_a, cached_b);
+        assert!(!file_b.exists());
+        assert_eq!(
+            cache.get("https://mirror.example.com/a", None),
+            Some(cached_a)
+        );
+    }
+
+    #[test]
+    fn it_uses_unique_partial_files() {
+        let cache_dir = tempdir().unwrap();
+        let cache = DownloadCache::new(cache_dir.path());
+
+        let url = "https://example.com/tool.tar.gz";
+        assert_ne!(cache.get_partial_path(url), cache.get_partial_path(url));
+    }
+
+    #[test]
+    fn it_uses_cached_files_without_downloading() {
+        let cache_dir = tempdir().unwrap();
+        let cache = DownloadCache::new(cache_dir.path());
+
+        let file = cache_dir.path().join("tool.tar.gz");
+        write(&file, "content").unwrap();
+        let checksum = get_file_hash(&file).unwrap();
+        cache
+            .insert("https://example.com/tool.tar.gz", &file)
+            .unwrap();
+
+        // a different URL with a matching checksum is served from the cache as well
+        let cached_path = download(
+            &cache,
+            &ConnectionPool::default(),
+            "https://invalid.localhost/tool.tar.gz",
+            Some(&checksum),
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(read_to_string(cached_path).unwrap(), "content");
+    }
+}
@@ -0,0 +1,91 @@
+use ansi_term::Color::Red;
+use ergo_fs::Path;
+use std::{
+    fs::{hard_link, remove_file},
+    io::ErrorKind,
+};
+
+use crate::error::{Error, Result};
+
+/**
+ * Compares the file IDs (device and inode on unix, volume and file index on Windows),
+ * so a copy with the same size and modification time is never mistaken for a hard link.
+ */
+pub fn is_same_file(file_a: &Path, file_b: &Path) -> bool {
+    same_file::is_same_file(file_a, file_b).unwrap_or(false)
+}
+
+pub fn create_hard_link(src: &Path, target: &Path, replace: bool) -> Result<()> {
+    if is_same_file(src, target) {
+        return Ok(());
+    }
+
+    if replace && target.is_file() {
+        remove_file(target).map_err(|e| Error::io(target, e))?;
+    }
+
+    hard_link(src, target).map_err(|e| {
+        if e.kind() == ErrorKind::CrossesDevices {
+            return Error::Command(format!(
+                "Cannot hard link {} to {}: {}",
+                src.display(),
+                target.display(),
+                Red.paint("source and target are on different filesystems")
+            ));
+        }
+
+        Error::io(target, e)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_creates_hard_link() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("example.txt");
+        let target = dir.path().join("linked.txt");
+        File::create(&src).unwrap();
+
+        create_hard_link(&src, &target, false).unwrap();
+
+        assert!(is_same_file(&src, &target));
+        assert!(!target.is_symlink());
+    }
+
+    #[test]
+    fn it_fails_when_target_exists_without_replace() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("example.txt");
+        let target = dir.path().join("existing.txt");
+        File::create(&src).unwrap();
+        File::create(&target).unwrap();
+
+        create_hard_link(&src, &target, false).unwrap_err();
+        create_hard_link(&src, &target, true).unwrap();
+
+        assert!(is_same_file(&src, &target));
+    }
+
+    #[test]
+    fn it_doesnt_mistake_copies_for_hard_links() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("example.txt");
+        let target = dir.path().join("copy.txt");
+        File::create(&src).unwrap();
+        std::fs::copy(&src, &target).unwrap();
+        File::options()
+            .write(true)
+            .open(&target)
+            .unwrap()
+            .set_modified(src.metadata().unwrap().modified().unwrap())
+            .unwrap();
+
+        assert!(!is_same_file(&src, &target));
+        assert!(!is_same_file(&src, &dir.path().join("missing.txt")));
+    }
+}
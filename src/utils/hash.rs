@@ -0,0 +1,84 @@
+use ergo_fs::Path;
+use sha2::{Digest, Sha256};
+use std::{fs::File, io};
+
+use crate::error::{Error, Result};
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn get_file_digest<D: Digest + io::Write>(path: &Path) -> Result<String> {
+    let mut file = File::open(path).map_err(|e| Error::io(path, e))?;
+    let mut hasher = D::new();
+
+    io::copy(&mut file, &mut hasher).map_err(|e| Error::io(path, e))?;
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub fn get_file_hash(path: &Path) -> Result<String> {
+    get_file_digest::<Sha256>(path)
+}
+
+/**
+ * Identifies a config inside of the shared `state_dir`, so the state of different configs doesn't mix.
+ */
+pub fn get_config_key(config_dir: &Path, config_file: Option<&str>) -> String {
+    let path = match config_file {
+        Some(config_file) => config_dir.join(config_file),
+        None => config_dir.to_path_buf(),
+    };
+
+    to_hex(&Sha256::digest(path.to_string_lossy().as_bytes()))[..16].to_string()
+}
+
+pub fn files_are_equal(file_a: &Path, file_b: &Path) -> bool {
+    match (file_a.metadata(), file_b.metadata()) {
+        (Ok(meta_a), Ok(meta_b)) if meta_a.is_file() && meta_b.is_file() => {
+            if meta_a.len() != meta_b.len() {
+                return false;
+            }
+        }
+        _ => return false,
+    }
+
+    match (get_file_hash(file_a), get_file_hash(file_b)) {
+        (Ok(hash_a), Ok(hash_b)) => hash_a == hash_b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_hashes_file_contents() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("example.txt");
+        File::create(&path).unwrap().write_all(b"hello").unwrap();
+
+        assert_eq!(
+            get_file_hash(&path).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn it_compares_files_by_content() {
+        let dir = tempdir().unwrap();
+        let file_a = dir.path().join("a.txt");
+        let file_b = dir.path().join("b.txt");
+        let file_c = dir.path().join("c.txt");
+        File::create(&file_a).unwrap().write_all(b"hello").unwrap();
+        File::create(&file_b).unwrap().write_all(b"hello").unwrap();
+        File::create(&file_c).unwrap().write_all(b"world").unwrap();
+
+        assert!(files_are_equal(&file_a, &file_b));
+        assert!(!files_are_equal(&file_a, &file_c));
+        assert!(!files_are_equal(&file_a, &dir.path().join("missing.txt")));
+    }
+}
@@ -0,0 +1,236 @@
+use ergo_fs::{Path, PathBuf};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+pub static LOCKFILE_NAME: &str = "machine-setup.lock";
+const LOCKFILE_VERSION: u64 = 1;
+
+/**
+ * Records the commits of cloned repositories and the checksums of downloads.
+ * In frozen mode, these commands use the recorded versions instead of resolving the latest ones.
+ * Packages installed by other commands (e.g. `run`) are not locked.
+ */
+#[derive(Debug, Default)]
+pub struct Lockfile {
+    path: Option<PathBuf>,
+    frozen: bool,
+    entries: Mutex<BTreeMap<String, String>>,
+    changed: AtomicBool,
+}
+
+fn get_key(kind: &str, id: &str) -> String {
+    format!("{kind}:{id}")
+}
+
+fn parse_entries(contents: &str) -> Result<BTreeMap<String, String>, String> {
+    let lockfile: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+
+    let version = lockfile["version"].as_u64().unwrap_or_default();
+    if version != LOCKFILE_VERSION {
+        return Err(format!("unsupported version {version}"));
+    }
+
+    let Some(entries) = lockfile["entries"].as_object() else {
+        return Ok(BTreeMap::new());
+    };
+
+    entries
+        .iter()
+        .map(|(key, value)| match value.as_str() {
+            Some(version) => Ok((key.to_string(), version.to_string())),
+            None => Err(format!("{key}: version must be a string")),
+        })
+        .collect()
+}
+
+impl Lockfile {
+    pub fn load(config_dir: &Path, frozen: bool) -> Result<Self> {
+        let path = config_dir.join(LOCKFILE_NAME);
+
+        let entries = if path.is_file() {
+            let contents = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+
+            parse_entries(&contents)
+                .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?
+        } else if frozen {
+            return Err(Error::Config(format!(
+                "--frozen requires {}",
+                path.display()
+            )));
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Lockfile {
+            path: Some(path),
+            frozen,
+            entries: Mutex::new(entries),
+            changed: AtomicBool::new(false),
+        })
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /**
+     * Returns the version that has to be installed.
+     * Outside of frozen mode, commands resolve the version themselves, so nothing is returned.
+     */
+    pub fn get_frozen_version(&self, kind: &str, id: &str) -> Result<Option<String>> {
+        if !self.frozen {
+            return Ok(None);
+        }
+
+        let entries = self.entries.lock().unwrap();
+        match entries.get(&get_key(kind, id)) {
+            Some(version) => Ok(Some(version.to_string())),
+            None => Err(Error::Command(format!(
+                "{kind} {id} is missing in {LOCKFILE_NAME}, run without --frozen first"
+            ))),
+        }
+    }
+
+    pub fn lock(&self, kind: &str, id: &str, version: &str) {
+        if self.frozen {
+            return;
+        }
+
+        let previous = self
+            .entries
+            .lock()
+            .unwrap()
+            .insert(get_key(kind, id), version.to_string());
+
+        if previous.as_deref() != Some(version) {
+            self.changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn unlock(&self, kind: &str, id: &str) {
+        if self.frozen {
+            return;
+        }
+
+        if self
+            .entries
+            .lock()
+            .unwrap()
+            .remove(&get_key(kind, id))
+            .is_some()
+        {
+            self.changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if self.frozen || !self.changed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let entries: Map<String, Value> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, version)| (key.to_string(), Value::String(version.to_string())))
+            .collect();
+
+        let contents = serde_json::to_string_pretty(&json!({
+            "version": LOCKFILE_VERSION,
+            "entries": entries,
+        }))
+        .map_err(|e| Error::Command(e.to_string()))?;
+
+        fs::write(path, contents + "\n").map_err(|e| Error::io(path, e))?;
+        debug!("Updated {}", path.display());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_writes_and_reads_locked_versions() {
+        let dir = tempdir().unwrap();
+
+        let lockfile = Lockfile::load(dir.path(), false).unwrap();
+        lockfile.lock(
+            "clone",
+            "https://github.com/timopruesse/machine_setup.git",
+            "abc123",
+        );
+        lockfile.lock("download", "https://example.com/tool", "def456");
+        lockfile.save().unwrap();
+
+        let frozen = Lockfile::load(dir.path(), true).unwrap();
+        assert_eq!(
+            frozen
+                .get_frozen_version("clone", "https://github.com/timopruesse/machine_setup.git")
+                .unwrap(),
+            Some(String::from("abc123"))
+        );
+
+        frozen.lock("download", "https://example.com/tool", "changed");
+        assert_eq!(
+            frozen
+                .get_frozen_version("download", "https://example.com/tool")
+                .unwrap(),
+            Some(String::from("def456"))
+        );
+    }
+
+    #[test]
+    fn it_fails_in_frozen_mode_when_versions_are_missing() {
+        let dir = tempdir().unwrap();
+
+        assert!(Lockfile::load(dir.path(), true)
+            .unwrap_err()
+            .to_string()
+            .contains("--frozen"));
+
+        fs::write(
+            dir.path().join(LOCKFILE_NAME),
+            r#"{ "version": 1, "entries": {} }"#,
+        )
+        .unwrap();
+
+        let frozen = Lockfile::load(dir.path(), true).unwrap();
+        assert!(frozen
+            .get_frozen_version("clone", "https://example.com/repo.git")
+            .unwrap_err()
+            .to_string()
+            .contains("missing"));
+    }
+
+    #[test]
+    fn it_only_resolves_versions_in_frozen_mode() {
+        let lockfile = Lockfile::default();
+        lockfile.lock("clone", "https://example.com/repo.git", "abc123");
+
+        assert_eq!(
+            lockfile
+                .get_frozen_version("clone", "https://example.com/repo.git")
+                .unwrap(),
+            None
+        );
+    }
+}
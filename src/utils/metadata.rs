@@ -0,0 +1,151 @@
+use ergo_fs::Path;
+use std::fs::{File, FileTimes, Metadata};
+use tracing::warn;
+
+use crate::{
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+};
+
+/**
+ * File modes are written as octal numbers, e.g. "0755" or "644".
+ * YAML parses unquoted values such as 0755 as integers, so those are interpreted as octal as well.
+ */
+pub fn parse_mode(value: &ConfigValue) -> Result<u32> {
+    let mode_str = match value {
+        ConfigValue::String(s) => s.to_string(),
+        ConfigValue::Integer(i) => i.to_string(),
+        _ => {
+            return Err(Error::Validation(String::from(
+                "mode: argument must be an octal string, e.g. \"0755\"",
+            )))
+        }
+    };
+
+    let digits = mode_str.trim().trim_start_matches("0o");
+
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| Error::Validation(format!("mode: {mode_str} is not a valid file mode")))
+}
+
+#[cfg(target_family = "unix")]
+fn open_for_metadata(path: &Path) -> std::io::Result<File> {
+    File::open(path)
+}
+
+#[cfg(target_family = "windows")]
+fn open_for_metadata(path: &Path) -> std::io::Result<File> {
+    File::options().write(true).open(path)
+}
+
+#[cfg(target_family = "unix")]
+pub fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(target_family = "windows")]
+pub fn is_root() -> bool {
+    false
+}
+
+#[cfg(target_family = "unix")]
+fn preserve_owner(src_meta: &Metadata, target: &Path) -> Result<()> {
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    if !is_root() {
+        return Ok(());
+    }
+
+    chown(target, Some(src_meta.uid()), Some(src_meta.gid())).map_err(|e| Error::io(target, e))
+}
+
+#[cfg(target_family = "windows")]
+fn preserve_owner(_src_meta: &Metadata, _target: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(target_family = "unix")]
+pub fn set_mode(target: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(target, std::fs::Permissions::from_mode(mode))
+        .map_err(|e| Error::io(target, e))
+}
+
+#[cfg(target_family = "windows")]
+pub fn set_mode(target: &Path, _mode: u32) -> Result<()> {
+    warn!(
+        "Setting a file mode is not supported on Windows: {}",
+        target.display()
+    );
+    Ok(())
+}
+
+/**
+ * Copies permissions, timestamps and - when running as root - the ownership of `src` to `target`.
+ * The timestamps are set before the permissions, because a read-only target can't be opened afterwards.
+ */
+pub fn preserve_metadata(src: &Path, target: &Path) -> Result<()> {
+    let src_meta = src.metadata().map_err(|e| Error::io(src, e))?;
+
+    match (src_meta.accessed(), src_meta.modified()) {
+        (Ok(accessed), Ok(modified)) => {
+            let times = FileTimes::new()
+                .set_accessed(accessed)
+                .set_modified(modified);
+
+            open_for_metadata(target)
+                .and_then(|file| file.set_times(times))
+                .map_err(|e| Error::io(target, e))?;
+        }
+        _ => warn!("Timestamps are not available for {}", src.display()),
+    }
+
+    preserve_owner(&src_meta, target)?;
+
+    std::fs::set_permissions(target, src_meta.permissions()).map_err(|e| Error::io(target, e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_parses_octal_modes() {
+        assert_eq!(
+            parse_mode(&ConfigValue::String(String::from("0755"))).unwrap(),
+            0o755
+        );
+        assert_eq!(parse_mode(&ConfigValue::Integer(644)).unwrap(), 0o644);
+        parse_mode(&ConfigValue::String(String::from("0999"))).unwrap_err();
+        parse_mode(&ConfigValue::Boolean(true)).unwrap_err();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn it_preserves_permissions_and_timestamps() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::{Duration, SystemTime};
+
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("script.sh");
+        let target = dir.path().join("copy.sh");
+        File::create(&src).unwrap();
+        File::create(&target).unwrap();
+
+        set_mode(&src, 0o755).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        File::open(&src)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+
+        preserve_metadata(&src, &target).unwrap();
+
+        let target_meta = target.metadata().unwrap();
+        assert_eq!(target_meta.permissions().mode() & 0o777, 0o755);
+        assert_eq!(target_meta.modified().unwrap(), modified);
+    }
+}
@@ -1,5 +1,22 @@
+pub mod app_dirs;
+pub mod applied_config;
+pub mod connections;
 pub mod directory;
+pub mod download;
+pub mod hard_link;
+pub mod hash;
+pub mod lockfile;
+pub mod metadata;
+pub mod network;
+pub mod progress;
+pub mod purge;
+pub mod restart;
 pub mod shell;
+pub mod ssh;
+pub mod sudo;
+pub mod task_results;
 pub mod temp_storage;
 pub mod terminal;
 pub mod threads;
+pub mod variables;
+pub mod verify;
@@ -0,0 +1,286 @@
+use std::{env, fs, sync::Arc};
+
+use native_tls::{Certificate, TlsConnector};
+
+use crate::{
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+    utils::directory::expand_path,
+};
+
+static PEM_END: &str = "-----END CERTIFICATE-----";
+const DEFAULT_MAX_CONNECTIONS_PER_HOST: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mirror {
+    pub from: String,
+    pub to: String,
+}
+
+/**
+ * Settings for all commands that access the network, e.g. on corporate networks where direct access is blocked.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkConfig {
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+    pub ca_bundle: Option<String>,
+    pub mirrors: Vec<Mirror>,
+    pub max_connections_per_host: Option<usize>,
+}
+
+fn get_optional_str(values: &ConfigValue, name: &str) -> Result<Option<String>> {
+    match values.as_hash().and_then(|values| values.get(name)) {
+        None | Some(ConfigValue::Null) => Ok(None),
+        Some(ConfigValue::String(value)) => Ok(Some(value.to_string())),
+        Some(_) => Err(Error::Config(format!(
+            "network.{name}: argument must be a string"
+        ))),
+    }
+}
+
+impl NetworkConfig {
+    pub fn from_config_value(value: &ConfigValue) -> Result<Self> {
+        if value.is_null() || value.is_invalid() {
+            return Ok(NetworkConfig::default());
+        }
+
+        if !value.is_hash() {
+            return Err(Error::Config(String::from("network: must be an object")));
+        }
+
+        let mut mirrors = vec![];
+        if let Some(mirror_values) = value.as_hash().unwrap().get("mirrors") {
+            let mirror_values = mirror_values
+                .as_hash()
+                .ok_or_else(|| Error::Config(String::from("network.mirrors: must be an object")))?;
+
+            for (from, to) in mirror_values {
+                let to = to.as_str().ok_or_else(|| {
+                    Error::Config(format!("network.mirrors.{from}: must be a string"))
+                })?;
+
+                mirrors.push(Mirror {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+            }
+        }
+
+        // the most specific mirror wins
+        mirrors.sort_by(|a, b| b.from.len().cmp(&a.from.len()).then(a.from.cmp(&b.from)));
+
+        let max_connections_per_host =
+            match value.as_hash().unwrap().get("max_connections_per_host") {
+                None | Some(ConfigValue::Null) => None,
+                Some(ConfigValue::Integer(max)) if *max > 0 => Some(*max as usize),
+                Some(_) => {
+                    return Err(Error::Config(String::from(
+                        "network.max_connections_per_host: must be a number > 0",
+                    )))
+                }
+            };
+
+        Ok(NetworkConfig {
+            http_proxy: get_optional_str(value, "http_proxy")?,
+            https_proxy: get_optional_str(value, "https_proxy")?,
+            ca_bundle: get_optional_str(value, "ca_bundle")?,
+            mirrors,
+            max_connections_per_host,
+        })
+    }
+
+    pub fn get_max_connections_per_host(&self) -> usize {
+        self.max_connections_per_host
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS_PER_HOST)
+    }
+
+    pub fn rewrite_url(&self, url: &str) -> String {
+        self.mirrors
+            .iter()
+            .find(|mirror| url.starts_with(&mirror.from))
+            .map(|mirror| format!("{}{}", mirror.to, &url[mirror.from.len()..]))
+            .unwrap_or_else(|| url.to_string())
+    }
+
+    /**
+     * Falls back to the usual environment variables when no proxy is configured.
+     */
+    pub fn get_proxy(&self, url: &str) -> Option<String> {
+        let from_env = |names: &[&str]| names.iter().find_map(|name| env::var(name).ok());
+
+        if url.starts_with("https://") {
+            return self
+                .https_proxy
+                .clone()
+                .or_else(|| from_env(&["HTTPS_PROXY", "https_proxy"]))
+                .or_else(|| self.http_proxy.clone())
+                .or_else(|| from_env(&["HTTP_PROXY", "http_proxy"]));
+        }
+
+        self.http_proxy
+            .clone()
+            .or_else(|| from_env(&["HTTP_PROXY", "http_proxy"]))
+    }
+
+    /**
+     * Arguments that have to be passed to git before the subcommand, e.g. `git -c http.proxy=... clone`.
+     * Git reads the proxy environment variables itself, only the configured proxy for the scheme of the URL is passed.
+     */
+    pub fn get_git_args(&self, url: &str) -> Result<Vec<String>> {
+        let mut args = vec![];
+
+        let proxy = match url.starts_with("https://") {
+            true => self.https_proxy.as_ref().or(self.http_proxy.as_ref()),
+            false => self.http_proxy.as_ref(),
+        };
+        if let Some(proxy) = proxy {
+            args.push(String::from("-c"));
+            args.push(format!("http.proxy={proxy}"));
+        }
+
+        if let Some(ca_bundle) = &self.ca_bundle {
+            let ca_bundle = expand_path(ca_bundle, false)?;
+            args.push(String::from("-c"));
+            args.push(format!("http.sslCAInfo={}", ca_bundle.display()));
+        }
+
+        Ok(args)
+    }
+
+    fn get_tls_connector(&self) -> Result<Option<TlsConnector>> {
+        let Some(ca_bundle) = &self.ca_bundle else {
+            return Ok(None);
+        };
+        let ca_bundle = expand_path(ca_bundle, false)?;
+
+        let bundle = fs::read_to_string(&ca_bundle).map_err(|e| Error::io(&ca_bundle, e))?;
+
+        let mut builder = TlsConnector::builder();
+        for pem in bundle
+            .split_inclusive(PEM_END)
+            .filter(|pem| pem.contains(PEM_END))
+        {
+            let certificate = Certificate::from_pem(pem.trim().as_bytes()).map_err(|e| {
+                Error::Config(format!("network.ca_bundle: invalid certificate: {e}"))
+            })?;
+            builder.add_root_certificate(certificate);
+        }
+
+        let connector = builder
+            .build()
+            .map_err(|e| Error::Config(format!("network.ca_bundle: {e}")))?;
+
+        Ok(Some(connector))
+    }
+
+    pub fn get_agent(&self, url: &str) -> Result<ureq::Agent> {
+        let mut builder = ureq::AgentBuilder::new()
+            .max_idle_connections_per_host(self.get_max_connections_per_host());
+
+        if let Some(proxy) = self.get_proxy(url) {
+            let proxy = ureq::Proxy::new(&proxy)
+                .map_err(|e| Error::Config(format!("network: invalid proxy {proxy}: {e}")))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(connector) = self.get_tls_connector()? {
+            builder = builder.tls_connector(Arc::new(connector));
+        }
+
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn get_config() -> NetworkConfig {
+        NetworkConfig::from_config_value(&ConfigValue::Hash(HashMap::from([
+            (
+                String::from("https_proxy"),
+                ConfigValue::String(String::from("http://proxy.corp:3128")),
+            ),
+            (
+                String::from("mirrors"),
+                ConfigValue::Hash(HashMap::from([
+                    (
+                        String::from("https://github.com/"),
+                        ConfigValue::String(String::from("https://git.corp/github/")),
+                    ),
+                    (
+                        String::from("https://github.com/timopruesse/"),
+                        ConfigValue::String(String::from("https://git.corp/timo/")),
+                    ),
+                ])),
+            ),
+        ])))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_rewrites_urls_to_the_most_specific_mirror() {
+        let config = get_config();
+
+        assert_eq!(
+            config.rewrite_url("https://github.com/rust-lang/rust.git"),
+            "https://git.corp/github/rust-lang/rust.git"
+        );
+        assert_eq!(
+            config.rewrite_url("https://github.com/timopruesse/machine_setup.git"),
+            "https://git.corp/timo/machine_setup.git"
+        );
+        assert_eq!(
+            config.rewrite_url("https://example.com/file"),
+            "https://example.com/file"
+        );
+    }
+
+    #[test]
+    fn it_passes_the_proxy_to_git() {
+        let config = get_config();
+
+        assert_eq!(
+            config
+                .get_git_args("https://github.com/rust-lang/rust.git")
+                .unwrap(),
+            vec!["-c", "http.proxy=http://proxy.corp:3128"]
+        );
+    }
+
+    #[test]
+    fn it_picks_the_git_proxy_by_scheme() {
+        let config = NetworkConfig {
+            http_proxy: Some(String::from("http://proxy.corp:8080")),
+            https_proxy: Some(String::from("http://proxy.corp:3128")),
+            ca_bundle: Some(String::from("$HOME/corp-ca.pem")),
+            ..Default::default()
+        };
+
+        let home = env::var("HOME").unwrap();
+        assert_eq!(
+            config.get_git_args("http://git.corp/tools.git").unwrap(),
+            vec![
+                String::from("-c"),
+                String::from("http.proxy=http://proxy.corp:8080"),
+                String::from("-c"),
+                format!("http.sslCAInfo={home}/corp-ca.pem"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_fails_for_invalid_mirrors() {
+        let result = NetworkConfig::from_config_value(&ConfigValue::Hash(HashMap::from([(
+            String::from("mirrors"),
+            ConfigValue::String(String::from("https://git.corp")),
+        )])));
+
+        let err = result.unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+        assert!(err.to_string().contains("network.mirrors"));
+    }
+}
@@ -0,0 +1,210 @@
+use ansi_term::Color::{Green, Red, White};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    events::{Event, Reporter},
+    task_runner::TaskStatus,
+};
+
+/**
+ * All progress bars are drawn by this instance.
+ * Log lines are printed while the bars are hidden, otherwise the next redraw would clobber them.
+ */
+pub static MULTI_PROGRESS: Lazy<MultiProgress> = Lazy::new(MultiProgress::new);
+
+/**
+ * Set while a full-screen UI owns the terminal.
+ */
+pub static SUPPRESS_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default)]
+pub struct ProgressWriter {}
+
+impl Write for ProgressWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if SUPPRESS_OUTPUT.load(Ordering::SeqCst) {
+            return Ok(buf.len());
+        }
+
+        MULTI_PROGRESS.suspend(|| io::stdout().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        MULTI_PROGRESS.suspend(|| io::stdout().flush())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Bars {
+    overall: Option<ProgressBar>,
+    tasks: HashMap<String, ProgressBar>,
+    num_failed: usize,
+}
+
+/**
+ * Draws an overall bar with an ETA and one bar per running task.
+ */
+#[derive(Debug, Default)]
+pub struct IndicatifReporter {
+    bars: Mutex<Bars>,
+}
+
+fn get_overall_bar(num_tasks: usize) -> ProgressBar {
+    let bar = ProgressBar::new(num_tasks.try_into().unwrap()).with_style(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} tasks (ETA {eta}) {msg}",
+            )
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    bar.enable_steady_tick(Duration::from_millis(100));
+
+    bar
+}
+
+fn get_task_bar(task: &str, num_commands: usize) -> ProgressBar {
+    ProgressBar::new(num_commands.try_into().unwrap())
+        .with_style(
+            ProgressStyle::default_bar()
+                .template("  [{bar:40.green/white}] {pos}/{len} {prefix:.bold}: {msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        )
+        .with_prefix(task.to_string())
+}
+
+impl Reporter for IndicatifReporter {
+    fn report(&self, event: &Event) {
+        let mut bars = self.bars.lock().unwrap();
+
+        match event {
+            Event::RunStarted { num_tasks, .. } => {
+                // the overall bar is added first, so it stays on top of the task bars
+                bars.overall = Some(MULTI_PROGRESS.add(get_overall_bar(*num_tasks)));
+            }
+            Event::TaskStarted { task, num_commands } => {
+                let bar = MULTI_PROGRESS.add(get_task_bar(task, *num_commands));
+                bars.tasks.insert(task.to_string(), bar);
+            }
+            Event::CommandStarted { task, command } => {
+                if let Some(bar) = bars.tasks.get(task) {
+                    bar.set_message(format!("⏳ {}", White.bold().paint(command)));
+                }
+            }
+            Event::CommandOutput { task, message, .. } => {
+                if let Some(bar) = bars.tasks.get(task) {
+                    bar.set_message(message.to_string());
+                }
+            }
+            Event::CommandProgress {
+                task,
+                command,
+                position,
+                length,
+            } => {
+                if let Some(bar) = bars.tasks.get(task) {
+                    bar.set_message(format!(
+                        "⏳ {} {position}/{length} ({}%)",
+                        White.bold().paint(command),
+                        position * 100 / (*length).max(1)
+                    ));
+                }
+            }
+            Event::CommandFinished {
+                task,
+                command,
+                error,
+            } => {
+                if let Some(bar) = bars.tasks.get(task) {
+                    match error {
+                        Some(_) => bar.set_message(format!("❌ {}", Red.paint(command))),
+                        None => bar.set_message(format!("✅ {}", Green.paint(command))),
+                    }
+                    bar.inc(1);
+                }
+            }
+            Event::TaskFinished { task, status } => {
+                if let Some(bar) = bars.tasks.remove(task) {
+                    match status {
+                        TaskStatus::Failed(_) => {
+                            bar.finish_with_message(format!("❌ {}", Red.bold().paint("ERR")))
+                        }
+                        _ => bar.finish_with_message(format!("✅ {}", Green.bold().paint("OK"))),
+                    }
+                }
+
+                if let TaskStatus::Failed(_) = status {
+                    bars.num_failed += 1;
+                }
+
+                let num_failed = bars.num_failed;
+                if let Some(overall) = &bars.overall {
+                    if num_failed > 0 {
+                        overall
+                            .set_message(format!("{}", Red.paint(format!("{num_failed} failed"))));
+                    }
+                    overall.inc(1);
+                }
+            }
+            Event::RunFinished { success } => {
+                if let Some(overall) = bars.overall.take() {
+                    if *success {
+                        overall.finish_with_message(format!("{}", Green.paint("done")));
+                    } else {
+                        overall.abandon();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::task_runner::TaskRunnerMode;
+
+    #[test]
+    fn it_draws_bars_for_running_tasks() {
+        let reporter = IndicatifReporter::default();
+
+        reporter.report(&Event::RunStarted {
+            mode: TaskRunnerMode::Install,
+            num_tasks: 1,
+        });
+        reporter.report(&Event::TaskStarted {
+            task: String::from("git"),
+            num_commands: 1,
+        });
+        assert!(reporter.bars.lock().unwrap().tasks.contains_key("git"));
+
+        reporter.report(&Event::TaskFinished {
+            task: String::from("git"),
+            status: TaskStatus::Failed(String::from("broken")),
+        });
+
+        let bars = reporter.bars.lock().unwrap();
+        assert!(bars.tasks.is_empty());
+        assert_eq!(bars.num_failed, 1);
+        assert_eq!(bars.overall.as_ref().unwrap().position(), 1);
+    }
+
+    #[test]
+    fn it_writes_the_whole_buffer() {
+        let mut writer = ProgressWriter::default();
+
+        assert_eq!(writer.write(b"hello\n").unwrap(), 6);
+        writer.flush().unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use ergo_fs::{Path, PathBuf};
+use std::{
+    fs::{remove_dir, remove_dir_all},
+    time::Duration,
+};
+use tracing::debug;
+
+use crate::{
+    command::CommandConfig,
+    error::{Error, Result},
+};
+
+use super::{
+    directory::expand_path, download::DownloadCache, temp_storage::remove_stale_temp_files,
+};
+
+fn remove_tree(path: &Path) -> Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    debug!("Removing {} ...", path.display());
+    remove_dir_all(path).map_err(|e| Error::io(path, e))?;
+
+    Ok(Some(path.to_path_buf()))
+}
+
+/**
+ * Removes everything machine_setup keeps outside of the config directory.
+ * `temp_dir` is the configured one, not the directory of the current run.
+ * The temp and cache dirs are only removed when they are empty, because they might be shared with other tools.
+ */
+pub fn purge(config: &CommandConfig, temp_dir: &str) -> Result<Vec<PathBuf>> {
+    let mut removed = vec![];
+
+    let state_dir = expand_path(&config.state_dir, false)?;
+    removed.extend(remove_tree(&state_dir)?);
+
+    let cache_dir = expand_path(&config.cache_dir, false)?;
+    removed.extend(remove_tree(DownloadCache::new(&cache_dir).get_root())?);
+
+    let expanded_temp_dir = expand_path(temp_dir, false)?;
+    if remove_stale_temp_files(temp_dir, Duration::ZERO)? > 0 {
+        removed.push(expanded_temp_dir.clone());
+    }
+
+    for dir in [expanded_temp_dir, cache_dir] {
+        if remove_dir(&dir).is_ok() {
+            debug!("Removed empty directory {}", dir.display());
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::create_dir_all, fs::File};
+
+    use crate::utils::temp_storage::create_temp_file;
+
+    use super::*;
+
+    #[test]
+    fn it_purges_state_downloads_and_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let to_string = |path: PathBuf| path.to_str().unwrap().to_string();
+
+        let config = CommandConfig {
+            temp_dir: to_string(dir.path().join("cache/tmp")),
+            state_dir: to_string(dir.path().join("state")),
+            cache_dir: to_string(dir.path().join("cache")),
+            ..CommandConfig::for_dir(dir.path())
+        };
+
+        create_dir_all(dir.path().join("state/plugins")).unwrap();
+        create_dir_all(dir.path().join("cache/downloads/blobs")).unwrap();
+        create_temp_file("sh", &config.temp_dir).unwrap();
+        let other_file = dir.path().join("cache/notes.txt");
+        File::create(&other_file).unwrap();
+
+        let removed = purge(&config, &config.temp_dir).unwrap();
+
+        assert_eq!(removed.len(), 3);
+        assert!(!dir.path().join("state").exists());
+        assert!(!dir.path().join("cache/downloads").exists());
+        assert!(!dir.path().join("cache/tmp").exists());
+        assert!(other_file.exists());
+    }
+}
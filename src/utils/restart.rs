@@ -0,0 +1,169 @@
+use core::fmt;
+use std::{process::Command, str::FromStr, sync::Mutex};
+
+use crate::error::{Error, Result};
+
+use super::metadata::is_root;
+
+/**
+ * A reboot includes a new login, so it's the stronger requirement.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Restart {
+    Relogin,
+    Reboot,
+}
+
+impl fmt::Display for Restart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Restart::Relogin => write!(f, "relogin"),
+            Restart::Reboot => write!(f, "reboot"),
+        }
+    }
+}
+
+impl FromStr for Restart {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relogin" => Ok(Restart::Relogin),
+            "reboot" => Ok(Restart::Reboot),
+            _ => Err(format!(
+                "Unknown restart: {s}, expected \"relogin\" or \"reboot\""
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartRequirement {
+    pub task: String,
+    pub restart: Restart,
+    pub reason: Option<String>,
+}
+
+/**
+ * Collects the restarts that commands flagged during a run, e.g. after changing the login shell.
+ */
+#[derive(Debug, Default)]
+pub struct RestartRequirements {
+    entries: Mutex<Vec<RestartRequirement>>,
+}
+
+impl RestartRequirements {
+    pub fn require(&self, task: &str, restart: Restart, reason: Option<&str>) {
+        self.entries.lock().unwrap().push(RestartRequirement {
+            task: task.to_string(),
+            restart,
+            reason: reason.map(|reason| reason.to_string()),
+        });
+    }
+
+    pub fn get_all(&self) -> Vec<RestartRequirement> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+pub fn get_required_restart(requirements: &[RestartRequirement]) -> Option<Restart> {
+    requirements
+        .iter()
+        .map(|requirement| requirement.restart)
+        .max()
+}
+
+/**
+ * Lists every task that requires the restart, so it's clear where the requirement comes from.
+ */
+pub fn get_restart_notice(requirements: &[RestartRequirement]) -> Option<String> {
+    let restart = get_required_restart(requirements)?;
+
+    let mut lines = vec![match restart {
+        Restart::Reboot => String::from("Reboot the machine to apply all changes:"),
+        Restart::Relogin => String::from("Log out and back in to apply all changes:"),
+    }];
+
+    for requirement in requirements {
+        lines.push(match &requirement.reason {
+            Some(reason) => format!(
+                "  - {} ({}): {reason}",
+                requirement.task, requirement.restart
+            ),
+            None => format!("  - {} ({})", requirement.task, requirement.restart),
+        });
+    }
+
+    Some(lines.join("\n"))
+}
+
+fn get_reboot_command(is_root: bool) -> Command {
+    if cfg!(target_family = "windows") {
+        let mut command = Command::new("shutdown");
+        command.args(["/r", "/t", "0"]);
+        return command;
+    }
+
+    let mut command = if is_root {
+        Command::new("shutdown")
+    } else {
+        let mut command = Command::new("sudo");
+        command.arg("shutdown");
+        command
+    };
+    command.args(["-r", "now"]);
+    command
+}
+
+pub fn reboot() -> Result<()> {
+    let mut command = get_reboot_command(is_root());
+    let program = command.get_program().to_string_lossy().to_string();
+
+    let status = command.status().map_err(|err| Error::Spawn {
+        program: program.to_string(),
+        source: err,
+    })?;
+
+    if !status.success() {
+        return Err(Error::ExitStatus {
+            command: program,
+            code: status.code(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_reports_the_strongest_restart() {
+        let requirements = RestartRequirements::default();
+        assert_eq!(get_restart_notice(&requirements.get_all()), None);
+
+        requirements.require("shell", Restart::Relogin, None);
+        requirements.require("nvidia", Restart::Reboot, Some("kernel module"));
+        let all = requirements.get_all();
+
+        assert_eq!(get_required_restart(&all), Some(Restart::Reboot));
+        assert_eq!(
+            get_restart_notice(&all).unwrap(),
+            "Reboot the machine to apply all changes:\n  - shell (relogin)\n  - nvidia (reboot): kernel module"
+        );
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn it_reboots_with_sudo_unless_root() {
+        let command = get_reboot_command(false);
+        assert_eq!(command.get_program(), "sudo");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec!["shutdown", "-r", "now"]
+        );
+
+        assert_eq!(get_reboot_command(true).get_program(), "shutdown");
+    }
+}
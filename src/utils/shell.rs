@@ -1,15 +1,26 @@
 use core::fmt;
-use std::{fs::File, str::FromStr};
+use std::{
+    env,
+    fs::File,
+    process::{self, Stdio},
+    str::FromStr,
+};
 
-use ergo_fs::{IoWrite, PathBuf};
+use ergo_fs::{IoWrite, Path, PathBuf};
 use regex::Regex;
 
+use crate::{
+    config::{config_value::ConfigValue, os::Os},
+    error::{Error, Result},
+};
+
 use super::temp_storage::create_temp_file;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Shell {
     Zsh,
     Bash,
+    PowerShell,
 }
 
 impl fmt::Display for Shell {
@@ -17,6 +28,7 @@ impl fmt::Display for Shell {
         match self {
             Shell::Bash => write!(f, "bash"),
             Shell::Zsh => write!(f, "zsh"),
+            Shell::PowerShell => write!(f, "powershell"),
         }
     }
 }
@@ -28,55 +40,222 @@ impl FromStr for Shell {
         match s {
             "zsh" => Ok(Shell::Zsh),
             "bash" => Ok(Shell::Bash),
+            "powershell" | "pwsh" => Ok(Shell::PowerShell),
             _ => Err(format!("Unknown shell: {s}")),
         }
     }
 }
 
+impl Shell {
+    /**
+     * Windows PowerShell is only available on Windows, PowerShell Core is called pwsh everywhere else.
+     */
+    pub fn get_program(&self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::PowerShell if cfg!(target_family = "windows") => "powershell",
+            Shell::PowerShell => "pwsh",
+        }
+    }
+
+    fn get_script_extension(&self) -> &'static str {
+        match self {
+            Shell::PowerShell => "ps1",
+            _ => "sh",
+        }
+    }
+
+    pub fn get_script_args(&self, script: &Path) -> Vec<String> {
+        let script = script.to_string_lossy().to_string();
+
+        match self {
+            Shell::PowerShell => vec![
+                String::from("-NoProfile"),
+                String::from("-NonInteractive"),
+                String::from("-ExecutionPolicy"),
+                String::from("Bypass"),
+                String::from("-File"),
+                script,
+            ],
+            _ => vec![String::from("-c"), script],
+        }
+    }
+
+    pub fn get_command_args(&self, command: &str) -> Vec<String> {
+        match self {
+            Shell::PowerShell => vec![
+                String::from("-NoProfile"),
+                String::from("-NonInteractive"),
+                String::from("-Command"),
+                command.to_string(),
+            ],
+            _ => vec![String::from("-c"), command.to_string()],
+        }
+    }
+
+    /**
+     * Runs a single command without any output, e.g. for guards of a task.
+     */
+    pub fn command_succeeds(&self, command: &str) -> Result<bool> {
+        let program = self.get_program();
+
+        let status = process::Command::new(program)
+            .args(self.get_command_args(command))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|err| Error::Spawn {
+                program: program.to_string(),
+                source: err,
+            })?;
+
+        Ok(status.success())
+    }
+}
+
+/**
+ * The shell from $SHELL if it's supported, otherwise the usual shell of the platform.
+ */
+pub fn detect_shell() -> Shell {
+    let login_shell = env::var("SHELL").ok().and_then(|shell| {
+        Path::new(&shell)
+            .file_name()
+            .and_then(|name| Shell::from_str(&name.to_string_lossy()).ok())
+    });
+
+    match login_shell {
+        Some(shell) => shell,
+        None if cfg!(target_family = "windows") => Shell::PowerShell,
+        None => Shell::Bash,
+    }
+}
+
+/**
+ * `default_shell` is either a single shell or one shell per OS, e.g. `{ linux: bash, windows: powershell }`.
+ * It's resolved for the current OS when the tasks are run.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct DefaultShell {
+    shell: Option<Shell>,
+    per_os: Vec<(Os, Shell)>,
+}
+
+impl From<Shell> for DefaultShell {
+    fn from(shell: Shell) -> Self {
+        DefaultShell {
+            shell: Some(shell),
+            per_os: vec![],
+        }
+    }
+}
+
+impl DefaultShell {
+    pub fn from_config_value(value: &ConfigValue) -> Result<Self> {
+        match value {
+            ConfigValue::Null | ConfigValue::Invalid => Ok(DefaultShell::default()),
+            ConfigValue::String(shell) => Ok(DefaultShell::from(
+                Shell::from_str(shell).map_err(Error::Config)?,
+            )),
+            ConfigValue::Hash(shells) => {
+                let mut per_os = vec![];
+                for (os, shell) in shells {
+                    let shell = shell
+                        .as_str()
+                        .ok_or_else(|| format!("{os}: must be a string"))
+                        .and_then(Shell::from_str)
+                        .map_err(Error::Config)?;
+
+                    per_os.push((Os::from_str(os).map_err(Error::Config)?, shell));
+                }
+
+                Ok(DefaultShell {
+                    shell: None,
+                    per_os,
+                })
+            }
+            _ => Err(Error::Config(String::from(
+                "must be a shell or an object with a shell per OS",
+            ))),
+        }
+    }
+
+    pub fn resolve_for(&self, os: &Os) -> Shell {
+        self.per_os
+            .iter()
+            .find(|(shell_os, _)| shell_os == os)
+            .map(|(_, shell)| *shell)
+            .or(self.shell)
+            .unwrap_or_else(detect_shell)
+    }
+
+    pub fn resolve(&self) -> Shell {
+        match Os::from_str(env::consts::OS) {
+            Ok(os) => self.resolve_for(&os),
+            Err(_) => self.shell.unwrap_or_else(detect_shell),
+        }
+    }
+}
+
 const BASH_STR: &str = "#!/bin/bash\nsource $HOME/.bashrc >/dev/null 2>&1\n";
 const ZSH_STR: &str = "#!/bin/zsh\nsource $HOME/.zshrc >/dev/null 2>&1\n";
+const POWERSHELL_STR: &str = "$ErrorActionPreference = \"Stop\"\n";
 
 #[cfg(target_family = "windows")]
-fn make_executable(file: &mut File) -> Result<(), String> {
+fn make_executable(file: &mut File, path: &PathBuf) -> Result<()> {
     // TODO: How to set permissions for Windows?
     Ok(())
 }
 
 #[cfg(target_family = "unix")]
-fn make_executable(file: &mut File) -> Result<(), String> {
+fn make_executable(file: &mut File, path: &PathBuf) -> Result<()> {
     let perm_result = file.set_permissions(
         <std::fs::Permissions as std::os::unix::prelude::PermissionsExt>::from_mode(0o755),
     );
     if let Err(err_perm) = perm_result {
-        return Err(err_perm.to_string());
+        return Err(crate::error::Error::io(path, err_perm));
     }
 
     Ok(())
 }
 
-pub fn create_script_file(
-    shell: Shell,
-    commands: Vec<String>,
-    temp_dir: &str,
-) -> Result<PathBuf, String> {
-    let temp_file = create_temp_file("sh", temp_dir)?;
+pub fn create_script_file(shell: Shell, commands: Vec<String>, temp_dir: &str) -> Result<PathBuf> {
+    let temp_file = create_temp_file(shell.get_script_extension(), temp_dir)?;
     let mut file = temp_file.file;
     let path = temp_file.path;
 
     match shell {
         Shell::Bash => write!(file, "{}", BASH_STR).unwrap_or_default(),
         Shell::Zsh => write!(file, "{}", ZSH_STR).unwrap_or_default(),
+        Shell::PowerShell => write!(file, "{}", POWERSHELL_STR).unwrap_or_default(),
     }
 
     for command in commands {
         writeln!(file, "{}", command).unwrap_or_default();
     }
 
-    make_executable(&mut file)?;
+    make_executable(&mut file, &path)?;
 
     Ok(path)
 }
 
+/**
+ * Quotes an argument for a POSIX shell, e.g. for commands that are run over ssh or in a container.
+ */
+pub fn quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:@,+%".contains(c));
+
+    if is_safe {
+        return arg.to_string();
+    }
+
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
 /**
  * We remove irritating info such as:
  *   - temp script file name
@@ -130,6 +309,63 @@ mod test {
         assert!(file.contains("echo 'hello world'"));
     }
 
+    #[test]
+    fn it_creates_powershell_script_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir_path = temp_dir.path().to_str().unwrap();
+        let script_file = create_script_file(
+            Shell::PowerShell,
+            vec![String::from("Write-Output 'hello world'")],
+            temp_dir_path,
+        )
+        .unwrap();
+
+        assert!(script_file.to_str().unwrap().ends_with(".ps1"));
+        assert_eq!(
+            Shell::PowerShell.get_script_args(&script_file).last(),
+            Some(&script_file.to_string_lossy().to_string())
+        );
+
+        let file = std::fs::read_to_string(script_file).unwrap();
+        assert!(file.contains(POWERSHELL_STR));
+    }
+
+    #[test]
+    fn it_resolves_the_default_shell_per_os() {
+        let value = ConfigValue::Hash(std::collections::HashMap::from([
+            (
+                String::from("macos"),
+                ConfigValue::String(String::from("zsh")),
+            ),
+            (
+                String::from("windows"),
+                ConfigValue::String(String::from("powershell")),
+            ),
+        ]));
+        let default_shell = DefaultShell::from_config_value(&value).unwrap();
+
+        assert_eq!(default_shell.resolve_for(&Os::Mac), Shell::Zsh);
+        assert_eq!(default_shell.resolve_for(&Os::Windows), Shell::PowerShell);
+        assert_eq!(default_shell.resolve_for(&Os::Linux), detect_shell());
+
+        assert_eq!(
+            DefaultShell::from(Shell::Zsh).resolve_for(&Os::Linux),
+            Shell::Zsh
+        );
+        DefaultShell::from_config_value(&ConfigValue::String(String::from("fish"))).unwrap_err();
+    }
+
+    #[test]
+    fn it_quotes_arguments_for_the_shell() {
+        assert_eq!(
+            quote("/tmp/tmp.x1/machine_setup"),
+            "/tmp/tmp.x1/machine_setup"
+        );
+        assert_eq!(quote("my task"), "'my task'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+    }
+
     #[test]
     fn it_replaces_unneeded_err_info() {
         assert_eq!(
@@ -0,0 +1,262 @@
+use core::fmt;
+use std::{
+    env,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
+};
+
+use ergo_fs::Path;
+
+use crate::error::{Error, Result};
+
+use super::shell::quote;
+
+/**
+ * A machine that is reachable over SSH, written as `[user@]host[:port]`.
+ * Everything else (keys, jump hosts, ...) is picked up from `~/.ssh/config`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshTarget {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl FromStr for SshTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (user, host) = match s.split_once('@') {
+            Some((user, host)) if !user.is_empty() => (Some(user.to_string()), host),
+            Some(_) => return Err(format!("Invalid host: {s}")),
+            None => (None, s),
+        };
+
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| format!("Invalid port in host: {s}"))?;
+                (host, Some(port))
+            }
+            None => (host, None),
+        };
+
+        if host.is_empty() {
+            return Err(format!("Invalid host: {s}"));
+        }
+
+        Ok(SshTarget {
+            user,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl fmt::Display for SshTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_destination())
+    }
+}
+
+impl SshTarget {
+    fn get_destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.to_string(),
+        }
+    }
+
+    /**
+     * All connections of a run share one master connection,
+     * so a password or key passphrase is only asked for once.
+     */
+    fn get_options(&self) -> Vec<String> {
+        let control_path = env::temp_dir().join("machine-setup-ssh-%C");
+
+        vec![
+            String::from("-o"),
+            String::from("ControlMaster=auto"),
+            String::from("-o"),
+            format!("ControlPath={}", control_path.display()),
+            String::from("-o"),
+            String::from("ControlPersist=60"),
+        ]
+    }
+
+    fn ssh(&self) -> Command {
+        let mut command = Command::new("ssh");
+        command.args(self.get_options());
+
+        if let Some(port) = self.port {
+            command.arg("-p").arg(port.to_string());
+        }
+
+        command
+    }
+
+    fn scp(&self) -> Command {
+        let mut command = Command::new("scp");
+        command.args(self.get_options()).arg("-q").arg("-r");
+
+        if let Some(port) = self.port {
+            command.arg("-P").arg(port.to_string());
+        }
+
+        command
+    }
+}
+
+fn check_status(program: &str, command: &mut Command) -> Result<()> {
+    let status = command.status().map_err(|err| Error::Spawn {
+        program: program.to_string(),
+        source: err,
+    })?;
+
+    if !status.success() {
+        return Err(Error::ExitStatus {
+            command: program.to_string(),
+            code: status.code(),
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Runs a command on the target and returns its trimmed stdout.
+ */
+pub fn output(target: &SshTarget, remote_command: &str) -> Result<String> {
+    let output = target
+        .ssh()
+        .arg(target.get_destination())
+        .arg(remote_command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| Error::Spawn {
+            program: String::from("ssh"),
+            source: err,
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::ExitStatus {
+            command: format!("ssh {target} {remote_command}"),
+            code: output.status.code(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/**
+ * Runs a command on the target with the local terminal attached.
+ * With `interactive`, a pseudo terminal is allocated, so progress bars and prompts (e.g. sudo) work remotely.
+ */
+pub fn execute(target: &SshTarget, remote_command: &str, interactive: bool) -> Result<()> {
+    let mut command = target.ssh();
+    if interactive {
+        command.arg("-t");
+    }
+
+    check_status(
+        "ssh",
+        command.arg(target.get_destination()).arg(remote_command),
+    )
+}
+
+/**
+ * Runs a command on the target without a terminal and prints every line of its output with a prefix,
+ * so the output of several machines running at the same time can be told apart.
+ */
+pub fn execute_prefixed(target: &SshTarget, remote_command: &str, prefix: &str) -> Result<()> {
+    let mut command = target
+        .ssh()
+        .arg(target.get_destination())
+        .arg(remote_command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::Spawn {
+            program: String::from("ssh"),
+            source: err,
+        })?;
+
+    let stdout_reader = BufReader::new(command.stdout.take().unwrap());
+    let stderr_reader = BufReader::new(command.stderr.take().unwrap());
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            stdout_reader
+                .lines()
+                .map_while(|line| line.ok())
+                .for_each(|line| println!("{prefix} {line}"));
+        });
+        s.spawn(|| {
+            stderr_reader
+                .lines()
+                .map_while(|line| line.ok())
+                .for_each(|line| eprintln!("{prefix} {line}"));
+        });
+    });
+
+    let status = command.wait().map_err(|err| Error::Spawn {
+        program: String::from("ssh"),
+        source: err,
+    })?;
+
+    if !status.success() {
+        return Err(Error::ExitStatus {
+            command: format!("ssh {target}"),
+            code: status.code(),
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Copies a file or a whole directory to the target.
+ */
+pub fn upload(target: &SshTarget, local: &Path, remote: &str) -> Result<()> {
+    check_status(
+        "scp",
+        target
+            .scp()
+            .arg(local)
+            .arg(format!("{}:{}", target.get_destination(), quote(remote))),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_parses_targets() {
+        assert_eq!(
+            SshTarget::from_str("timo@homeserver:2222").unwrap(),
+            SshTarget {
+                user: Some(String::from("timo")),
+                host: String::from("homeserver"),
+                port: Some(2222),
+            }
+        );
+        assert_eq!(
+            SshTarget::from_str("vm1").unwrap(),
+            SshTarget {
+                user: None,
+                host: String::from("vm1"),
+                port: None,
+            }
+        );
+
+        assert!(SshTarget::from_str("@vm1").is_err());
+        assert!(SshTarget::from_str("vm1:ssh").is_err());
+        assert!(SshTarget::from_str("timo@").is_err());
+    }
+}
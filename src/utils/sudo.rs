@@ -0,0 +1,140 @@
+use std::{
+    env,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tracing::{debug, warn};
+
+use crate::error::{Error, Result};
+
+use super::progress::MULTI_PROGRESS;
+
+static ASKPASS_ENV: &str = "SUDO_ASKPASS";
+
+/**
+ * sudo forgets the credentials after 5 minutes by default.
+ * Refreshing them more often keeps long runs from stalling on a prompt.
+ */
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/**
+ * With `SUDO_ASKPASS` set, sudo asks the helper program for the password (e.g. a script that reads it from the OS keyring).
+ * Otherwise sudo prompts on the terminal directly.
+ * Either way the password never passes through this process or the progress bars.
+ */
+fn get_sudo_command(non_interactive: bool) -> Command {
+    let mut command = Command::new("sudo");
+
+    if non_interactive {
+        command.arg("-n");
+    } else if env::var_os(ASKPASS_ENV).is_some() {
+        command.arg("-A");
+    }
+
+    command.arg("-v");
+    command
+}
+
+fn validate_credentials() -> Result<()> {
+    // the prompt would be overwritten by the progress bars otherwise
+    let status = MULTI_PROGRESS.suspend(|| get_sudo_command(false).status());
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(Error::ExitStatus {
+            command: String::from("sudo -v"),
+            code: status.code(),
+        }),
+        Err(err_spawn) => Err(Error::Spawn {
+            program: String::from("sudo"),
+            source: err_spawn,
+        }),
+    }
+}
+
+fn refresh_credentials() -> bool {
+    get_sudo_command(true)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/**
+ * Asks for the sudo password once and keeps the credentials alive until it's dropped.
+ */
+pub struct SudoKeepAlive {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SudoKeepAlive {
+    pub fn start() -> Result<Self> {
+        if cfg!(target_family = "windows") {
+            return Err(Error::Command(String::from(
+                "sudo is not supported on Windows",
+            )));
+        }
+
+        validate_credentials()?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let mut elapsed = Duration::ZERO;
+
+            while !stop_thread.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+                elapsed += POLL_INTERVAL;
+
+                if elapsed < REFRESH_INTERVAL {
+                    continue;
+                }
+                elapsed = Duration::ZERO;
+
+                if refresh_credentials() {
+                    debug!("Refreshed sudo credentials");
+                } else {
+                    warn!("Failed to refresh sudo credentials");
+                }
+            }
+        });
+
+        Ok(SudoKeepAlive {
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for SudoKeepAlive {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_refreshes_without_prompting() {
+        let command = get_sudo_command(true);
+        let args: Vec<_> = command.get_args().collect();
+
+        assert_eq!(args, vec!["-n", "-v"]);
+    }
+}
@@ -0,0 +1,289 @@
+use ergo_fs::{Path, PathBuf};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use tracing::debug;
+
+use crate::{
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+    task::Task,
+};
+
+use super::{
+    directory::list_source_files,
+    hash::{get_file_hash, to_hex},
+};
+
+/**
+ * Remembers the inputs of every task that was installed successfully.
+ * Tasks whose inputs didn't change since then are skipped, unless the run is forced.
+ */
+#[derive(Debug, Default)]
+pub struct TaskResults {
+    path: Option<PathBuf>,
+    force: bool,
+    entries: Mutex<BTreeMap<String, String>>,
+    changed: AtomicBool,
+}
+
+fn collect_paths(value: &ConfigValue, config_dir: &Path, paths: &mut Vec<PathBuf>) {
+    match value {
+        ConfigValue::String(s) if !s.is_empty() && Path::new(s).is_relative() => {
+            let path = config_dir.join(s);
+            if path.exists() {
+                paths.push(path);
+            }
+        }
+        ConfigValue::Array(values) => values
+            .iter()
+            .for_each(|value| collect_paths(value, config_dir, paths)),
+        ConfigValue::Hash(values) => values
+            .values()
+            .for_each(|value| collect_paths(value, config_dir, paths)),
+        _ => {}
+    }
+}
+
+/**
+ * Links are hashed by where they point to, so a link to a parent directory can't cause an endless loop.
+ */
+fn hash_file(path: &Path, hasher: &mut Sha256) -> Result<()> {
+    let metadata = path.symlink_metadata().map_err(|e| Error::io(path, e))?;
+    hasher.update(path.to_string_lossy().as_bytes());
+
+    if metadata.is_symlink() {
+        let link = fs::read_link(path).map_err(|e| Error::io(path, e))?;
+        hasher.update(link.to_string_lossy().as_bytes());
+    } else if metadata.is_file() {
+        hasher.update(get_file_hash(path)?.as_bytes());
+    }
+
+    Ok(())
+}
+
+/**
+ * Directories are hashed like they are walked by the commands, e.g. without `.git` and ignored files.
+ */
+fn hash_path(path: &Path, hasher: &mut Sha256) -> Result<()> {
+    let is_dir = path
+        .symlink_metadata()
+        .map_err(|e| Error::io(path, e))?
+        .is_dir();
+    if !is_dir {
+        return hash_file(path, hasher);
+    }
+
+    for file in list_source_files(path)? {
+        hash_file(&file, hasher)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Hashes the args of all commands and the contents of every file in the config directory they reference,
+ * e.g. the source of a copy command.
+ * The version is part of the hash, because commands might behave differently after an upgrade.
+ */
+pub fn get_task_hash(task: &Task, config_dir: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+
+    let mut paths = vec![];
+    for command in &task.commands {
+        hasher.update(command.name.as_bytes());
+        hasher.update(command.args.to_json().to_string().as_bytes());
+
+        collect_paths(&command.args, config_dir, &mut paths);
+    }
+
+    for path in paths {
+        hash_path(&path, &mut hasher)?;
+    }
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn parse_entries(contents: &str) -> Result<BTreeMap<String, String>, String> {
+    let results: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+
+    let Some(entries) = results.as_object() else {
+        return Err(String::from("task results must be an object"));
+    };
+
+    Ok(entries
+        .iter()
+        .filter_map(|(task, hash)| Some((task.to_string(), hash.as_str()?.to_string())))
+        .collect())
+}
+
+impl TaskResults {
+    /**
+     * Every config has its own file, see `get_config_key`.
+     * A broken file only means that every task runs again.
+     */
+    pub fn load(state_dir: &Path, config_key: &str, force: bool) -> Self {
+        let path = state_dir.join(format!("task_results.{config_key}.json"));
+
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| parse_entries(&contents).ok())
+            .unwrap_or_default();
+
+        TaskResults {
+            path: Some(path),
+            force,
+            entries: Mutex::new(entries),
+            changed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_unchanged(&self, task: &str, hash: &str) -> bool {
+        !self.force && self.entries.lock().unwrap().get(task).map(String::as_str) == Some(hash)
+    }
+
+    pub fn store(&self, task: &str, hash: &str) {
+        let previous = self
+            .entries
+            .lock()
+            .unwrap()
+            .insert(task.to_string(), hash.to_string());
+
+        if previous.as_deref() != Some(hash) {
+            self.changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn remove(&self, task: &str) {
+        if self.entries.lock().unwrap().remove(task).is_some() {
+            self.changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if !self.changed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let entries: Map<String, Value> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(task, hash)| (task.to_string(), Value::String(hash.to_string())))
+            .collect();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+        }
+
+        let contents = serde_json::to_string_pretty(&json!(entries))
+            .map_err(|e| Error::Command(e.to_string()))?;
+        fs::write(path, contents + "\n").map_err(|e| Error::io(path, e))?;
+        debug!("Updated {}", path.display());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    use crate::config::base_config::Command;
+
+    use super::*;
+
+    fn get_copy_task(src: &str) -> Task {
+        Task {
+            name: String::from("dotfiles"),
+            commands: vec![Command {
+                name: String::from("copy"),
+                args: ConfigValue::Hash(HashMap::from([
+                    (String::from("src"), ConfigValue::String(src.to_string())),
+                    (
+                        String::from("target"),
+                        ConfigValue::String(String::from("~/.config")),
+                    ),
+                ])),
+            }],
+            os: vec![],
+            tags: vec![],
+            when: vec![],
+            only_if: None,
+            skip_if: None,
+            parallel: false,
+        }
+    }
+
+    #[test]
+    fn it_changes_the_hash_when_a_source_file_changes() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::write(dir.path().join("files/.vimrc"), "set number").unwrap();
+        let task = get_copy_task("files");
+
+        let hash = get_task_hash(&task, dir.path()).unwrap();
+        assert_eq!(hash, get_task_hash(&task, dir.path()).unwrap());
+
+        fs::write(dir.path().join("files/.vimrc"), "set nonumber").unwrap();
+        assert_ne!(hash, get_task_hash(&task, dir.path()).unwrap());
+
+        assert_ne!(
+            hash,
+            get_task_hash(&get_copy_task("other"), dir.path()).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_skips_git_and_doesnt_follow_links() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/index"), "a").unwrap();
+        fs::write(dir.path().join(".vimrc"), "set number").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+        let task = get_copy_task(".");
+
+        let hash = get_task_hash(&task, dir.path()).unwrap();
+
+        fs::write(dir.path().join(".git/index"), "b").unwrap();
+        assert_eq!(hash, get_task_hash(&task, dir.path()).unwrap());
+    }
+
+    #[test]
+    fn it_remembers_successful_tasks() {
+        let dir = tempdir().unwrap();
+
+        let results = TaskResults::load(dir.path(), "work", false);
+        assert!(!results.is_unchanged("dotfiles", "abc"));
+        results.store("dotfiles", "abc");
+        results.save().unwrap();
+
+        let results = TaskResults::load(dir.path(), "work", false);
+        assert!(results.is_unchanged("dotfiles", "abc"));
+        assert!(!results.is_unchanged("dotfiles", "def"));
+
+        assert!(!TaskResults::load(dir.path(), "personal", false).is_unchanged("dotfiles", "abc"));
+
+        let forced = TaskResults::load(dir.path(), "work", true);
+        assert!(!forced.is_unchanged("dotfiles", "abc"));
+
+        results.remove("dotfiles");
+        results.save().unwrap();
+        assert!(!TaskResults::load(dir.path(), "work", false).is_unchanged("dotfiles", "abc"));
+    }
+}
@@ -1,14 +1,40 @@
 use ergo_fs::PathBuf;
+use once_cell::sync::Lazy;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use std::fs::File;
+use std::{
+    fs::{create_dir_all, read_dir, remove_dir_all, remove_file, File},
+    process::exit,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    error::{Error, Result},
+    terminal::exit_codes::EX_INTERRUPTED,
+};
 
 use super::directory::expand_path;
 
+const RANDOM_NAME_LENGTH: usize = 25;
+const RUN_DIR_PREFIX: &str = "run-";
+
+/**
+ * Temp dirs of the runs in this process, they are removed when the run is interrupted.
+ */
+static ACTIVE_RUN_DIRS: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/**
+ * Scripts of runs that crashed are never removed by the command itself.
+ * Everything that is older than this is considered stale.
+ */
+pub const STALE_TEMP_FILE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
 fn get_random_string() -> String {
     thread_rng()
         .sample_iter(&Alphanumeric)
-        .take(25)
+        .take(RANDOM_NAME_LENGTH)
         .map(char::from)
         .collect()
 }
@@ -18,7 +44,7 @@ pub struct FileInfo {
     pub file: File,
 }
 
-pub fn create_temp_file(file_ending: &str, temp_dir: &str) -> Result<FileInfo, String> {
+pub fn create_temp_file(file_ending: &str, temp_dir: &str) -> Result<FileInfo> {
     let expanded_dir = expand_path(temp_dir, true)?;
     let expanded_temp_dir = expanded_dir.to_str().unwrap();
 
@@ -27,7 +53,7 @@ pub fn create_temp_file(file_ending: &str, temp_dir: &str) -> Result<FileInfo, S
 
     let file = match File::create(&file_path) {
         Ok(file) => file,
-        Err(error) => return Err(error.to_string()),
+        Err(error) => return Err(Error::io(&file_path, error)),
     };
 
     Ok(FileInfo {
@@ -35,3 +61,199 @@ pub fn create_temp_file(file_ending: &str, temp_dir: &str) -> Result<FileInfo, S
         file,
     })
 }
+
+fn is_random_name(name: &str) -> bool {
+    name.len() == RANDOM_NAME_LENGTH && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_temp_file_name(file_name: &str) -> bool {
+    is_random_name(file_name.split('.').next().unwrap_or_default())
+}
+
+fn is_run_dir_name(dir_name: &str) -> bool {
+    dir_name
+        .strip_prefix(RUN_DIR_PREFIX)
+        .map(is_random_name)
+        .unwrap_or(false)
+}
+
+fn remove_active_run_dirs() {
+    for dir in ACTIVE_RUN_DIRS.lock().unwrap().drain(..) {
+        remove_dir_all(&dir).ok();
+    }
+}
+
+/**
+ * Removes the temp dirs of the active runs and exits when the process is interrupted.
+ * Only the binary registers it, applications that use the library keep their own signal handling
+ * and rely on the temp dirs being removed when their guard is dropped.
+ */
+pub fn register_interrupt_handler() {
+    let handler = ctrlc::set_handler(|| {
+        remove_active_run_dirs();
+        exit(EX_INTERRUPTED);
+    });
+
+    if let Err(err_handler) = handler {
+        debug!("Failed to register the interrupt handler: {err_handler}");
+    }
+}
+
+/**
+ * Every run writes its scripts into its own directory inside of `temp_dir`.
+ * The directory is removed when the guard is dropped (even while panicking) or when the binary is interrupted.
+ */
+#[derive(Debug)]
+pub struct RunTempDir {
+    path: PathBuf,
+}
+
+impl RunTempDir {
+    pub fn create(temp_dir: &str) -> Result<Self> {
+        let path =
+            expand_path(temp_dir, false)?.join(format!("{RUN_DIR_PREFIX}{}", get_random_string()));
+        create_dir_all(&path).map_err(|e| Error::io(&path, e))?;
+
+        ACTIVE_RUN_DIRS.lock().unwrap().push(path.clone());
+
+        Ok(RunTempDir { path })
+    }
+
+    pub fn get_path(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for RunTempDir {
+    fn drop(&mut self) {
+        ACTIVE_RUN_DIRS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|dir| dir != &self.path);
+
+        if let Err(err_remove) = remove_dir_all(&self.path) {
+            if self.path.exists() {
+                warn!("Failed to remove {}: {err_remove}", self.path.display());
+            }
+        }
+    }
+}
+
+/**
+ * Only files that were created by `create_temp_file` and the directories of runs are removed,
+ * because `temp_dir` might be shared with other tools.
+ */
+pub fn remove_stale_temp_files(temp_dir: &str, max_age: Duration) -> Result<usize> {
+    let expanded_dir = expand_path(temp_dir, false)?;
+    if !expanded_dir.is_dir() {
+        return Ok(0);
+    }
+
+    let entries = read_dir(&expanded_dir)
+        .map_err(|e| Error::io(&expanded_dir, e))?
+        .filter_map(|entry| {
+            entry
+                .map_err(|err_entry| {
+                    debug!(
+                        "Skipping an entry of {}: {err_entry}",
+                        expanded_dir.display()
+                    )
+                })
+                .ok()
+        });
+    let now = SystemTime::now();
+    let mut num_removed = 0;
+
+    for entry in entries {
+        let path = entry.path();
+        let is_stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .map(|modified| now.duration_since(modified).unwrap_or_default() >= max_age)
+            .unwrap_or(false);
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_stale {
+            continue;
+        }
+
+        let removed = if path.is_file() && is_temp_file_name(&name) {
+            remove_file(&path)
+        } else if path.is_dir() && is_run_dir_name(&name) {
+            remove_dir_all(&path)
+        } else {
+            continue;
+        };
+
+        if removed.is_ok() {
+            debug!("Removed stale temp file {}", path.display());
+            num_removed += 1;
+        }
+    }
+
+    Ok(num_removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_only_removes_stale_temp_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir_path = temp_dir.path().to_str().unwrap();
+
+        let temp_file = create_temp_file("sh", temp_dir_path).unwrap();
+        let other_file = temp_dir.path().join("notes.txt");
+        File::create(&other_file).unwrap();
+
+        assert_eq!(
+            remove_stale_temp_files(temp_dir_path, STALE_TEMP_FILE_AGE).unwrap(),
+            0
+        );
+        assert!(temp_file.path.exists());
+
+        assert_eq!(
+            remove_stale_temp_files(temp_dir_path, Duration::ZERO).unwrap(),
+            1
+        );
+        assert!(!temp_file.path.exists());
+        assert!(other_file.exists());
+    }
+
+    #[test]
+    fn it_removes_the_run_dir_when_dropped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let run_dir = RunTempDir::create(temp_dir.path().to_str().unwrap()).unwrap();
+
+        let script = create_temp_file("sh", &run_dir.get_path()).unwrap();
+        assert!(script.path.exists());
+        assert!(is_run_dir_name(
+            &run_dir.path.file_name().unwrap().to_string_lossy()
+        ));
+
+        drop(run_dir);
+
+        assert!(!script.path.exists());
+        assert_eq!(read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn it_removes_stale_run_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir_path = temp_dir.path().to_str().unwrap();
+        let crashed_run = temp_dir
+            .path()
+            .join(format!("{RUN_DIR_PREFIX}{}", get_random_string()));
+        create_temp_file("sh", crashed_run.to_str().unwrap()).unwrap();
+        let other_dir = temp_dir.path().join("run-other");
+        create_dir_all(&other_dir).unwrap();
+
+        assert_eq!(
+            remove_stale_temp_files(temp_dir_path, Duration::ZERO).unwrap(),
+            1
+        );
+        assert!(!crashed_run.exists());
+        assert!(other_dir.exists());
+    }
+}
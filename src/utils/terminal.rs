@@ -4,9 +4,10 @@ use std::env;
 
 use crate::config::config_value::ConfigValue;
 use crate::config::validator::arguments_are_named;
+use crate::error::{Error, Result};
 use crate::utils::directory::expand_path;
 
-fn parse_environment_variables(args: ConfigValue) -> Result<Option<ConfigValue>, String> {
+fn parse_environment_variables(args: ConfigValue) -> Result<Option<ConfigValue>> {
     if !arguments_are_named(Some(&args)) {
         return Ok(None);
     }
@@ -20,10 +21,10 @@ fn parse_environment_variables(args: ConfigValue) -> Result<Option<ConfigValue>,
         return Ok(Some(env.to_owned()));
     }
 
-    Err(String::from("env is not set correctly"))
+    Err(Error::Validation(String::from("env is not set correctly")))
 }
 
-pub fn set_environment_variables(args: &ConfigValue) -> Result<(), String> {
+pub fn set_environment_variables(args: &ConfigValue) -> Result<()> {
     let env = parse_environment_variables(args.to_owned())?;
 
     if let Some(env) = env {
@@ -31,7 +32,9 @@ pub fn set_environment_variables(args: &ConfigValue) -> Result<(), String> {
         println!("-----------------------------");
 
         if !env.is_hash() {
-            return Err(String::from("Environment needs to be defined as a map"));
+            return Err(Error::Validation(String::from(
+                "Environment needs to be defined as a map",
+            )));
         }
 
         for (key, value) in env.as_hash().unwrap() {
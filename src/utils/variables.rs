@@ -0,0 +1,254 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    sync::RwLock,
+};
+
+use crate::{
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+};
+
+pub(crate) static PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_.-]*)\s*\}\}").unwrap());
+
+static OUTPUTS_PREFIX: &str = "tasks.";
+
+/**
+ * Variables from the outside are passed on as environment variables as well, so they need a valid name for both.
+ */
+static VARIABLE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+
+pub(crate) fn is_variable_name(name: &str) -> bool {
+    VARIABLE_NAME.is_match(name)
+}
+
+/**
+ * e.g. "HOSTNAME=homeserver" from `--var`
+ */
+pub(crate) fn parse_variable(value: &str) -> Result<(String, String)> {
+    match value.split_once('=') {
+        Some((name, value)) if is_variable_name(name) => Ok((name.to_string(), value.to_string())),
+        _ => Err(Error::Validation(format!(
+            "{value}: variables have to be passed as NAME=VALUE"
+        ))),
+    }
+}
+
+/**
+ * Values that are shared between the tasks of a run, e.g. the output of a `run` command with `register`
+ * or the outputs of the commands of a task.
+ * They are referenced as `{{name}}` in the arguments of commands and in `when` conditions.
+ * `os` and `arch` are always defined.
+ */
+#[derive(Debug)]
+pub struct Variables {
+    values: RwLock<BTreeMap<String, String>>,
+}
+
+impl Default for Variables {
+    fn default() -> Self {
+        Variables {
+            values: RwLock::new(BTreeMap::from([
+                (String::from("os"), env::consts::OS.to_string()),
+                (String::from("arch"), env::consts::ARCH.to_string()),
+            ])),
+        }
+    }
+}
+
+impl Variables {
+    pub fn set(&self, name: &str, value: &str) {
+        self.values
+            .write()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+    }
+
+    /**
+     * Outputs are declared by the commands of a task, e.g. the `path` of a download.
+     * They are referenced as `{{ tasks.<task>.<output> }}`.
+     */
+    pub fn set_output(&self, task: &str, name: &str, value: &str) {
+        self.set(&format!("{OUTPUTS_PREFIX}{task}.{name}"), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.values.read().unwrap().get(name).cloned()
+    }
+
+    /**
+     * Placeholders of unknown variables are kept as they are,
+     * the same syntax might be used by other tools inside of a script.
+     */
+    pub fn interpolate_str(&self, value: &str) -> String {
+        let values = self.values.read().unwrap();
+
+        PLACEHOLDER
+            .replace_all(value, |captures: &Captures| {
+                values
+                    .get(&captures[1])
+                    .cloned()
+                    .unwrap_or_else(|| captures[0].to_string())
+            })
+            .to_string()
+    }
+
+    pub fn interpolate(&self, value: &ConfigValue) -> ConfigValue {
+        match value {
+            ConfigValue::String(s) => ConfigValue::String(self.interpolate_str(s)),
+            ConfigValue::Array(values) => {
+                ConfigValue::Array(values.iter().map(|v| self.interpolate(v)).collect())
+            }
+            ConfigValue::Hash(values) => ConfigValue::Hash(
+                values
+                    .iter()
+                    .map(|(key, v)| (key.to_string(), self.interpolate(v)))
+                    .collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    /**
+     * Conditions are either `{{a}} == b`, `{{a}} != b` or a single value,
+     * which holds when it's neither empty, "false" nor "0".
+     */
+    pub fn evaluate(&self, condition: &str) -> Result<bool> {
+        let condition = self.interpolate_str(condition);
+
+        if let Some(captures) = PLACEHOLDER.captures(&condition) {
+            return Err(Error::Validation(format!(
+                "when: variable \"{}\" is not defined",
+                &captures[1]
+            )));
+        }
+
+        if let Some((left, right)) = condition.split_once("!=") {
+            return Ok(unquote(left) != unquote(right));
+        }
+
+        if let Some((left, right)) = condition.split_once("==") {
+            return Ok(unquote(left) == unquote(right));
+        }
+
+        let value = unquote(&condition);
+        Ok(!value.is_empty() && value != "false" && value != "0")
+    }
+}
+
+/**
+ * Collects the names of the tasks whose outputs are referenced in a value.
+ */
+pub fn collect_referenced_tasks(value: &ConfigValue, tasks: &mut BTreeSet<String>) {
+    match value {
+        ConfigValue::String(s) => collect_referenced_tasks_in_str(s, tasks),
+        ConfigValue::Array(values) => values
+            .iter()
+            .for_each(|value| collect_referenced_tasks(value, tasks)),
+        ConfigValue::Hash(values) => values
+            .values()
+            .for_each(|value| collect_referenced_tasks(value, tasks)),
+        _ => {}
+    }
+}
+
+pub fn collect_referenced_tasks_in_str(value: &str, tasks: &mut BTreeSet<String>) {
+    for captures in PLACEHOLDER.captures_iter(value) {
+        let task = captures[1]
+            .strip_prefix(OUTPUTS_PREFIX)
+            .and_then(|output| output.rsplit_once('.'))
+            .map(|(task, _)| task.to_string());
+
+        if let Some(task) = task {
+            tasks.insert(task);
+        }
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn it_interpolates_variables() {
+        let variables = Variables::default();
+        variables.set("gpu", "nvidia");
+
+        let args = ConfigValue::Hash(HashMap::from([(
+            String::from("commands"),
+            ConfigValue::Array(vec![ConfigValue::String(String::from(
+                "install-driver {{ gpu }} {{unknown}}",
+            ))]),
+        )]));
+
+        assert_eq!(
+            variables.interpolate(&args).as_hash().unwrap()["commands"]
+                .as_vec()
+                .unwrap()[0]
+                .as_str(),
+            Some("install-driver nvidia {{unknown}}")
+        );
+    }
+
+    #[test]
+    fn it_interpolates_task_outputs() {
+        let variables = Variables::default();
+        variables.set_output("fzf", "path", "/opt/fzf/bin/fzf");
+
+        assert_eq!(
+            variables.interpolate_str("ln -s {{ tasks.fzf.path }} ~/.local/bin/fzf"),
+            "ln -s /opt/fzf/bin/fzf ~/.local/bin/fzf"
+        );
+
+        let mut tasks = BTreeSet::new();
+        collect_referenced_tasks_in_str(
+            "{{tasks.fzf.path}} {{ tasks.my-tools.commit }} {{gpu}}",
+            &mut tasks,
+        );
+        assert_eq!(
+            tasks.into_iter().collect::<Vec<String>>(),
+            vec![String::from("fzf"), String::from("my-tools")]
+        );
+    }
+
+    #[test]
+    fn it_evaluates_conditions() {
+        let variables = Variables::default();
+        variables.set("gpu", "nvidia");
+        variables.set("laptop", "false");
+
+        assert!(variables.evaluate("{{gpu}} == nvidia").unwrap());
+        assert!(variables.evaluate("{{gpu}} != \"amd\"").unwrap());
+        assert!(!variables.evaluate("{{laptop}}").unwrap());
+        assert!(variables
+            .evaluate(&format!("{{{{os}}}} == {}", env::consts::OS))
+            .unwrap());
+        assert!(variables.evaluate("{{missing}} == nvidia").is_err());
+    }
+
+    #[test]
+    fn it_parses_variables_from_the_command_line() {
+        assert_eq!(
+            parse_variable("HOSTNAME=home=server").unwrap(),
+            (String::from("HOSTNAME"), String::from("home=server"))
+        );
+        assert!(parse_variable("HOSTNAME").is_err());
+        assert!(parse_variable("HOST-NAME=home").is_err());
+        assert!(parse_variable("=home").is_err());
+    }
+}
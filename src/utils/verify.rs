@@ -0,0 +1,328 @@
+use ergo_fs::{Path, PathDir};
+use sha2::{Sha256, Sha512};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+use crate::{
+    config::config_value::ConfigValue,
+    error::{Error, Result},
+};
+
+use super::{
+    directory::{expand_path, get_relative_dir},
+    hash::get_file_digest,
+};
+
+static CHECKSUM_ARGS: [&str; 2] = ["sha256", "sha512"];
+static MINISIGN_KEY_ARG: &str = "minisign_key";
+static GPG_KEY_ARG: &str = "gpg_key";
+static SIGNATURE_ARG: &str = "signature";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureKey {
+    /** public key, e.g. "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3" */
+    Minisign(String),
+    /** path to a keyring that contains the public key */
+    Gpg(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub url: String,
+    pub key: SignatureKey,
+}
+
+/**
+ * Describes how a fetched artifact is verified before it is used.
+ * Every configured check has to pass, a failing check is always an error.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Verification {
+    pub checksums: Vec<Checksum>,
+    pub signature: Option<Signature>,
+}
+
+fn get_str_arg(args: &ConfigValue, name: &str) -> Result<Option<String>> {
+    match args.as_hash().and_then(|values| values.get(name)) {
+        None | Some(ConfigValue::Null) => Ok(None),
+        Some(ConfigValue::String(value)) => Ok(Some(value.trim().to_string())),
+        Some(_) => Err(Error::Validation(format!(
+            "{name}: argument must be a string"
+        ))),
+    }
+}
+
+/**
+ * A relative keyring is resolved from the config dir, like the other paths of the config.
+ */
+pub fn get_verification(args: &ConfigValue, config_dir: &PathDir) -> Result<Verification> {
+    let mut checksums = vec![];
+
+    for (name, algorithm) in CHECKSUM_ARGS
+        .iter()
+        .zip([ChecksumAlgorithm::Sha256, ChecksumAlgorithm::Sha512])
+    {
+        if let Some(value) = get_str_arg(args, name)? {
+            checksums.push(Checksum {
+                algorithm,
+                value: value.to_lowercase(),
+            });
+        }
+    }
+
+    let key = match (
+        get_str_arg(args, MINISIGN_KEY_ARG)?,
+        get_str_arg(args, GPG_KEY_ARG)?,
+    ) {
+        (Some(_), Some(_)) => {
+            return Err(Error::Validation(format!(
+                "{MINISIGN_KEY_ARG} and {GPG_KEY_ARG} can't be used together"
+            )))
+        }
+        (Some(public_key), None) => Some(SignatureKey::Minisign(public_key)),
+        (None, Some(keyring)) => {
+            let keyring = expand_path(&get_relative_dir(config_dir, &keyring), false)?;
+            Some(SignatureKey::Gpg(keyring.to_string_lossy().to_string()))
+        }
+        (None, None) => None,
+    };
+
+    let signature = match (get_str_arg(args, SIGNATURE_ARG)?, key) {
+        (Some(url), Some(key)) => Some(Signature { url, key }),
+        (None, None) => None,
+        (Some(_), None) => {
+            return Err(Error::Validation(format!(
+                "{SIGNATURE_ARG}: requires {MINISIGN_KEY_ARG} or {GPG_KEY_ARG}"
+            )))
+        }
+        (None, Some(_)) => {
+            return Err(Error::Validation(format!(
+                "{SIGNATURE_ARG}: is required to verify the signature"
+            )))
+        }
+    };
+
+    Ok(Verification {
+        checksums,
+        signature,
+    })
+}
+
+pub fn verify_checksum(file: &Path, checksum: &Checksum) -> Result<()> {
+    let actual = match checksum.algorithm {
+        ChecksumAlgorithm::Sha256 => get_file_digest::<Sha256>(file)?,
+        ChecksumAlgorithm::Sha512 => get_file_digest::<Sha512>(file)?,
+    };
+
+    if !actual.eq_ignore_ascii_case(&checksum.value) {
+        return Err(Error::Verification(format!(
+            "{}: expected {:?} checksum {}, got {actual}",
+            file.display(),
+            checksum.algorithm,
+            checksum.value
+        )));
+    }
+
+    Ok(())
+}
+
+fn get_signature_command(file: &Path, signature_file: &Path, key: &SignatureKey) -> Command {
+    let mut command = match key {
+        SignatureKey::Minisign(public_key) => {
+            let mut command = Command::new("minisign");
+            command.args(["-V", "-P", public_key, "-m"]);
+            command.arg(file).arg("-x").arg(signature_file);
+            command
+        }
+        SignatureKey::Gpg(keyring) => {
+            let mut command = Command::new("gpgv");
+            command.args(["--keyring", keyring]);
+            command.arg(signature_file).arg(file);
+            command
+        }
+    };
+
+    command.stdin(Stdio::null());
+    command
+}
+
+pub fn verify_signature(file: &Path, signature_file: &Path, key: &SignatureKey) -> Result<()> {
+    let mut command = get_signature_command(file, signature_file, key);
+    let program = command.get_program().to_string_lossy().to_string();
+
+    let output = command.output().map_err(|e| Error::Spawn {
+        program: program.to_string(),
+        source: e,
+    })?;
+
+    if !output.status.success() {
+        return Err(Error::Verification(format!(
+            "{}: invalid {program} signature\n{}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+impl Verification {
+    /**
+     * The signature file has to be fetched by the caller, because the verification doesn't touch the network.
+     */
+    pub fn verify(&self, file: &Path, signature_file: Option<&Path>) -> Result<()> {
+        for checksum in &self.checksums {
+            verify_checksum(file, checksum)?;
+        }
+
+        if let Some(signature) = &self.signature {
+            let signature_file = signature_file.ok_or_else(|| {
+                Error::Verification(format!("{}: signature is missing", file.display()))
+            })?;
+
+            verify_signature(file, signature_file, &signature.key)?;
+        }
+
+        debug!("Verified {}", file.display());
+
+        Ok(())
+    }
+
+    /**
+     * Used as the cache lookup key, so a cached file is never used without a known checksum.
+     */
+    pub fn get_sha256(&self) -> Option<&str> {
+        self.checksums
+            .iter()
+            .find(|checksum| checksum.algorithm == ChecksumAlgorithm::Sha256)
+            .map(|checksum| checksum.value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, fs};
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    static HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    static HELLO_SHA512: &str = "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043";
+
+    #[test]
+    fn it_verifies_all_checksums() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("hello");
+        fs::write(&file, "hello").unwrap();
+
+        let verification = get_verification(
+            &ConfigValue::Hash(HashMap::from([
+                (
+                    String::from("sha256"),
+                    ConfigValue::String(HELLO_SHA256.to_uppercase()),
+                ),
+                (
+                    String::from("sha512"),
+                    ConfigValue::String(HELLO_SHA512.to_string()),
+                ),
+            ])),
+            &PathDir::new(dir.path()).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(verification.checksums.len(), 2);
+        assert_eq!(verification.get_sha256(), Some(HELLO_SHA256));
+        verification.verify(&file, None).unwrap();
+    }
+
+    #[test]
+    fn it_fails_when_a_checksum_doesnt_match() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("hello");
+        fs::write(&file, "hello").unwrap();
+
+        let err = verify_checksum(
+            &file,
+            &Checksum {
+                algorithm: ChecksumAlgorithm::Sha512,
+                value: HELLO_SHA256.to_string(),
+            },
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, Error::Verification(_)));
+        assert!(err.to_string().contains("Sha512"));
+    }
+
+    #[test]
+    fn it_requires_a_key_for_signatures() {
+        let dir = tempdir().unwrap();
+
+        let err = get_verification(
+            &ConfigValue::Hash(HashMap::from([(
+                String::from("signature"),
+                ConfigValue::String(String::from("https://example.com/tool.minisig")),
+            )])),
+            &PathDir::new(dir.path()).unwrap(),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("minisign_key"));
+    }
+
+    #[test]
+    fn it_resolves_the_keyring_from_the_config_dir() {
+        let dir = tempdir().unwrap();
+        let config_dir = PathDir::new(dir.path()).unwrap();
+
+        let verification = get_verification(
+            &ConfigValue::Hash(HashMap::from([
+                (
+                    String::from("signature"),
+                    ConfigValue::String(String::from("https://example.com/tool.sig")),
+                ),
+                (
+                    String::from("gpg_key"),
+                    ConfigValue::String(String::from("keys/release.gpg")),
+                ),
+            ])),
+            &config_dir,
+        )
+        .unwrap();
+
+        assert_eq!(
+            verification.signature.unwrap().key,
+            SignatureKey::Gpg(config_dir.join("keys/release.gpg").to_string())
+        );
+    }
+
+    #[test]
+    fn it_fails_when_the_signature_file_is_missing() {
+        let verification = Verification {
+            checksums: vec![],
+            signature: Some(Signature {
+                url: String::from("https://example.com/tool.minisig"),
+                key: SignatureKey::Minisign(String::from("RWQ...")),
+            }),
+        };
+
+        let err = verification
+            .verify(Path::new("/tmp/tool"), None)
+            .unwrap_err();
+
+        assert!(err.to_string().contains("signature is missing"));
+    }
+}